@@ -0,0 +1,33 @@
+//! Backends compiled into this build, chosen by target and cargo features.
+use serpentines_platform::{BackendRegistry, Platform, Result};
+use tracing::info;
+
+/// Environment variable that forces a backend by name (e.g. `x11` under XWayland).
+pub const BACKEND_ENV_VAR: &str = "SERPENTINES_BACKEND";
+
+pub fn registry() -> BackendRegistry {
+    #[allow(unused_mut)]
+    let mut registry = BackendRegistry::new();
+    #[cfg(all(windows, feature = "win"))]
+    registry.register(serpentines_win::backend());
+    #[cfg(all(target_os = "linux", feature = "wayland"))]
+    registry.register(serpentines_wayland::backend());
+    #[cfg(all(target_os = "linux", feature = "x11"))]
+    registry.register(serpentines_x11::backend());
+    registry
+}
+
+/// Select a backend (`preferred`, else `BACKEND_ENV_VAR`, else the best available) and create
+/// its platform.
+pub fn create(preferred: Option<&str>) -> Result<Platform> {
+    let from_env = std::env::var(BACKEND_ENV_VAR).ok();
+    let registry = registry();
+    let backend = registry.select(preferred.or(from_env.as_deref()))?;
+    let capabilities = (backend.capabilities)();
+    info!(
+        "using {} backend (capabilities: {})",
+        backend.name,
+        capabilities.names().join(", ")
+    );
+    (backend.create)()
+}
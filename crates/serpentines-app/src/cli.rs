@@ -0,0 +1,333 @@
+//! Command-line interface: the GUI by default, plus headless subcommands for scripts and CI.
+
+use std::fs::File;
+use std::io::BufWriter;
+use std::path::{Path, PathBuf};
+
+use clap::{ArgAction, Args, Parser, Subcommand};
+use serpentines_core::thumbnail::DEMO_PERIOD;
+use serpentines_core::{PresetLibrary, PresetPack, TrailPreset};
+
+use crate::export::{write_gif, GifOptions};
+use crate::ipc::{self, IpcMethod};
+use crate::{backends, ConfigStore, IpcClient, UserConfig};
+
+pub type CliResult<T> = std::result::Result<T, Box<dyn std::error::Error + Send + Sync>>;
+
+#[derive(Debug, Parser)]
+#[command(name = "serpentines", version, about = "Cursor trails for the desktop")]
+pub struct Cli {
+    #[command(subcommand)]
+    pub command: Option<Command>,
+    /// More log output: -v for debug, -vv for trace.
+    #[arg(short, long, action = ArgAction::Count, global = true)]
+    pub verbose: u8,
+    /// Less log output: -q for warnings, -qq for errors only.
+    #[arg(short, long, action = ArgAction::Count, global = true, conflicts_with = "verbose")]
+    pub quiet: u8,
+}
+
+impl Cli {
+    /// `EnvFilter` directive for the requested verbosity.
+    pub fn log_filter(&self) -> &'static str {
+        match (self.verbose, self.quiet) {
+            (0, 0) => "info",
+            (1, _) => "debug",
+            (_, 0) => "trace",
+            (_, 1) => "warn",
+            _ => "error",
+        }
+    }
+}
+
+#[derive(Debug, Subcommand)]
+pub enum Command {
+    /// Run the overlay and control panel (the default).
+    Run(RunArgs),
+    /// List the displays the platform backend sees.
+    Monitors {
+        /// Print JSON instead of a table.
+        #[arg(long)]
+        json: bool,
+        /// Platform backend to ask, overriding the automatic choice.
+        #[arg(long, value_name = "NAME")]
+        backend: Option<String>,
+    },
+    /// Check preset and pack files for errors.
+    Validate {
+        #[arg(required = true, value_name = "FILE")]
+        files: Vec<PathBuf>,
+    },
+    /// Render a preset to an animated GIF without opening any windows.
+    Render(RenderArgs),
+    /// Print the default configuration file.
+    DefaultConfig,
+    /// Control a running instance over its IPC socket.
+    Ctl(CtlArgs),
+}
+
+#[derive(Debug, Default, Args)]
+pub struct RunArgs {
+    /// Config file to load and save instead of the default location.
+    #[arg(long, value_name = "FILE")]
+    pub config: Option<PathBuf>,
+    /// Start with this preset from the library.
+    #[arg(long, value_name = "NAME")]
+    pub preset: Option<String>,
+    /// Platform backend to use, overriding the automatic choice.
+    #[arg(long, value_name = "NAME")]
+    pub backend: Option<String>,
+    /// IPC socket path (a `\\.\pipe\` name on Windows) instead of the default.
+    #[arg(long, value_name = "PATH")]
+    pub socket: Option<PathBuf>,
+    /// Don't listen for IPC clients.
+    #[arg(long, conflicts_with = "socket")]
+    pub no_ipc: bool,
+}
+
+#[derive(Debug, Args)]
+pub struct CtlArgs {
+    /// IPC socket of the instance to control, instead of the default.
+    #[arg(long, value_name = "PATH")]
+    pub socket: Option<PathBuf>,
+    #[command(subcommand)]
+    pub action: CtlAction,
+}
+
+/// Results are printed as JSON.
+#[derive(Debug, Subcommand)]
+pub enum CtlAction {
+    Enable,
+    Disable,
+    /// Switch to a preset from the library.
+    SetPreset {
+        name: String,
+    },
+    Status,
+    Monitors,
+    /// Print events as JSON lines until the instance exits.
+    Watch,
+    /// Send any JSON-RPC method, with optional JSON params.
+    Call {
+        method: String,
+        params: Option<String>,
+    },
+}
+
+#[derive(Debug, Args)]
+pub struct RenderArgs {
+    /// Preset name from the library, or a preset or pack file (its first preset is used).
+    pub preset: String,
+    #[arg(short, long, value_name = "FILE")]
+    pub output: PathBuf,
+    #[arg(long, default_value_t = 320)]
+    pub width: u16,
+    #[arg(long, default_value_t = 180)]
+    pub height: u16,
+    /// Frame rate, rounded to what GIF frame delays allow (at most 50).
+    #[arg(long, default_value_t = 30)]
+    pub fps: u16,
+    /// Animation length; the default is one seamless loop.
+    #[arg(long, default_value_t = DEMO_PERIOD)]
+    pub seconds: f32,
+    /// Config whose preset library is searched by name.
+    #[arg(long, value_name = "FILE")]
+    pub config: Option<PathBuf>,
+}
+
+/// The store at `path`, or at the default location.
+pub fn open_store(path: Option<&Path>) -> Option<ConfigStore> {
+    match path {
+        Some(path) => Some(ConfigStore::new(path)),
+        None => ConfigStore::open_default(),
+    }
+}
+
+/// Run a headless subcommand. `Run` needs the UI and is handled by the binary.
+pub fn execute(command: Command) -> CliResult<()> {
+    match command {
+        Command::Run(_) => Err("`run` is handled by the app binary".into()),
+        Command::Monitors { json, backend } => monitors(json, backend.as_deref()),
+        Command::Validate { files } => validate(&files),
+        Command::Render(args) => render(&args),
+        Command::DefaultConfig => {
+            print!("{}", UserConfig::default().to_toml()?);
+            Ok(())
+        }
+        Command::Ctl(args) => ctl(args),
+    }
+}
+
+/// `--socket`, else the `SOCKET_ENV_VAR` or default endpoint.
+pub fn socket_path(socket: Option<PathBuf>) -> PathBuf {
+    socket.unwrap_or_else(ipc::endpoint)
+}
+
+fn ctl(args: CtlArgs) -> CliResult<()> {
+    let endpoint = socket_path(args.socket);
+    let mut client = IpcClient::connect(&endpoint)
+        .map_err(|err| format!("no running instance at {}: {err}", endpoint.display()))?;
+    let result = match args.action {
+        CtlAction::Enable => client.request(&IpcMethod::Enable)?,
+        CtlAction::Disable => client.request(&IpcMethod::Disable)?,
+        CtlAction::SetPreset { name } => client.request(&IpcMethod::SetPreset { name })?,
+        CtlAction::Status => client.request(&IpcMethod::GetStatus)?,
+        CtlAction::Monitors => client.request(&IpcMethod::ListMonitors)?,
+        CtlAction::Watch => {
+            client.subscribe()?;
+            while let Some(event) = client.next_event()? {
+                println!("{}", serde_json::to_string(&event)?);
+            }
+            return Ok(());
+        }
+        CtlAction::Call { method, params } => {
+            let params = match params {
+                Some(params) => serde_json::from_str(&params)?,
+                None => serde_json::Value::Null,
+            };
+            client.call(&method, params)?
+        }
+    };
+    println!("{}", serde_json::to_string_pretty(&result)?);
+    Ok(())
+}
+
+fn monitors(json: bool, backend: Option<&str>) -> CliResult<()> {
+    let mut platform = backends::create(backend)?;
+    let monitors = platform.event_loop.overlays().monitors()?;
+    if json {
+        println!("{}", serde_json::to_string_pretty(&monitors)?);
+        return Ok(());
+    }
+    println!(
+        "{:<18} {:>12} {:>12} {:>5}",
+        "ID", "POSITION", "SIZE", "DPI"
+    );
+    for monitor in &monitors {
+        println!(
+            "{:<18} {:>12} {:>12} {:>5}",
+            monitor.id.to_string(),
+            format!("{},{}", monitor.x, monitor.y),
+            format!("{}x{}", monitor.width, monitor.height),
+            monitor.dpi
+        );
+    }
+    Ok(())
+}
+
+fn validate(files: &[PathBuf]) -> CliResult<()> {
+    let mut failed = 0;
+    for path in files {
+        match load_preset_file(path) {
+            Ok(presets) => println!("ok    {}: {} preset(s)", path.display(), presets.len()),
+            Err(err) => {
+                failed += 1;
+                println!("FAIL  {}: {err}", path.display());
+            }
+        }
+    }
+    if failed > 0 {
+        return Err(format!("{failed} of {} files failed validation", files.len()).into());
+    }
+    Ok(())
+}
+
+fn render(args: &RenderArgs) -> CliResult<()> {
+    let preset = resolve_preset(&args.preset, args.config.as_deref())?;
+    let options = GifOptions {
+        width: args.width,
+        height: args.height,
+        fps: args.fps,
+        seconds: args.seconds,
+    };
+    let file =
+        File::create(&args.output).map_err(|err| format!("{}: {err}", args.output.display()))?;
+    let frames = write_gif(&preset, &options, BufWriter::new(file))?;
+    println!(
+        "wrote '{}' ({frames} frames) to {}",
+        preset.name,
+        args.output.display()
+    );
+    Ok(())
+}
+
+/// Load and validate a single-preset file or a pack (recognized by its `[[preset]]` tables).
+pub fn load_preset_file(path: &Path) -> CliResult<Vec<TrailPreset>> {
+    let text = std::fs::read_to_string(path)?;
+    let table: toml::Table = text.parse()?;
+    if table.contains_key("preset") {
+        return Ok(PresetPack::from_toml(&text)?.presets);
+    }
+    let preset: TrailPreset = toml::from_str(&text)?;
+    if let Err(problems) = preset.validate() {
+        let problems: Vec<String> = problems.iter().map(ToString::to_string).collect();
+        return Err(problems.join("; ").into());
+    }
+    Ok(vec![preset])
+}
+
+/// `spec` as a file path if one exists, else as a preset name in the configured library.
+fn resolve_preset(spec: &str, config: Option<&Path>) -> CliResult<TrailPreset> {
+    let path = Path::new(spec);
+    if path.is_file() {
+        return load_preset_file(path)?
+            .into_iter()
+            .next()
+            .ok_or_else(|| format!("{spec}: no presets").into());
+    }
+    let library = match open_store(config) {
+        Some(store) => store.load().library(),
+        None => PresetLibrary::builtin(),
+    };
+    library.get(spec).cloned().ok_or_else(|| {
+        let names: Vec<&str> = library.names().collect();
+        format!("unknown preset '{spec}' (known: {})", names.join(", ")).into()
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use clap::CommandFactory;
+
+    use super::*;
+
+    #[test]
+    fn command_definition_is_consistent() {
+        Cli::command().debug_assert();
+    }
+
+    #[test]
+    fn verbosity_flags_pick_the_log_filter() {
+        let cases: [(&[&str], &str); 7] = [
+            (&[], "info"),
+            (&["-v"], "debug"),
+            (&["-vv"], "trace"),
+            (&["-vvv"], "trace"),
+            (&["-q"], "warn"),
+            (&["-qq"], "error"),
+            (&["--quiet", "--quiet", "--quiet"], "error"),
+        ];
+        for (flags, filter) in cases {
+            let cli =
+                Cli::try_parse_from(std::iter::once("serpentines").chain(flags.iter().copied()))
+                    .unwrap();
+            assert_eq!(cli.log_filter(), filter, "flags {flags:?}");
+        }
+        assert!(Cli::try_parse_from(["serpentines", "-v", "-q"]).is_err());
+    }
+
+    #[test]
+    fn global_flags_work_after_subcommands() {
+        let cli =
+            Cli::try_parse_from(["serpentines", "render", "Comet", "-o", "out.gif", "-v"]).unwrap();
+        assert_eq!(cli.log_filter(), "debug");
+        let Some(Command::Render(args)) = cli.command else {
+            panic!("expected render");
+        };
+        assert_eq!(
+            (args.preset.as_str(), args.fps, args.width),
+            ("Comet", 30, 320)
+        );
+        assert!(Cli::try_parse_from(["serpentines", "run", "--socket", "x", "--no-ipc"]).is_err());
+    }
+}
@@ -0,0 +1,350 @@
+//! Persistent user configuration: engine config, preset library and panel settings in one TOML
+//! file under the platform config directory (`$XDG_CONFIG_HOME` on Linux, `%APPDATA%` on Windows).
+//!
+//! Saves write a temp file and rename it over the config, copying the previous version to a
+//! `.bak` file first. A config that fails to parse is set aside as `.corrupt` and the backup, or
+//! failing that the defaults, is used instead. A config written by a newer version is loaded
+//! as far as this one understands it but never overwritten.
+
+use std::fmt;
+use std::fs::{self, File};
+use std::io::{self, Write};
+use std::path::{Path, PathBuf};
+
+use serde::{Deserialize, Serialize};
+use serpentines_core::{EngineConfig, PresetLibrary, TrailPreset};
+use serpentines_ui::UiSettings;
+use tracing::{info, warn};
+
+/// Written to `UserConfig::version`; bump when the layout changes incompatibly.
+pub const CONFIG_VERSION: u32 = 1;
+const CONFIG_FILE_NAME: &str = "config.toml";
+
+/// Everything saved between runs.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(default)]
+pub struct UserConfig {
+    pub version: u32,
+    pub engine: EngineConfig,
+    pub ui: UiSettings,
+    /// The preset library. Empty means the built-in presets.
+    #[serde(rename = "preset", skip_serializing_if = "Vec::is_empty")]
+    pub presets: Vec<TrailPreset>,
+}
+
+impl Default for UserConfig {
+    fn default() -> Self {
+        Self {
+            version: CONFIG_VERSION,
+            engine: EngineConfig::default(),
+            ui: UiSettings::default(),
+            presets: Vec::new(),
+        }
+    }
+}
+
+impl UserConfig {
+    /// The saved presets, or the built-in ones if none were saved.
+    pub fn library(&self) -> PresetLibrary {
+        if self.presets.is_empty() {
+            PresetLibrary::builtin()
+        } else {
+            self.presets.iter().cloned().collect()
+        }
+    }
+
+    pub fn from_toml(text: &str) -> Result<Self, ConfigError> {
+        toml::from_str(text).map_err(ConfigError::Parse)
+    }
+
+    pub fn to_toml(&self) -> Result<String, ConfigError> {
+        toml::to_string_pretty(self).map_err(ConfigError::Serialize)
+    }
+}
+
+#[derive(Debug)]
+pub enum ConfigError {
+    Io(io::Error),
+    Parse(toml::de::Error),
+    Serialize(toml::ser::Error),
+    /// The file on disk has a higher `version` than `CONFIG_VERSION`.
+    NewerVersion(u32),
+}
+
+impl fmt::Display for ConfigError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            ConfigError::Io(err) => write!(f, "config file error: {err}"),
+            ConfigError::Parse(err) => write!(f, "invalid config: {err}"),
+            ConfigError::Serialize(err) => write!(f, "failed to serialize config: {err}"),
+            ConfigError::NewerVersion(version) => write!(
+                f,
+                "config was written by a newer version (v{version}, this build understands v{CONFIG_VERSION})"
+            ),
+        }
+    }
+}
+
+impl std::error::Error for ConfigError {}
+
+impl From<io::Error> for ConfigError {
+    fn from(err: io::Error) -> Self {
+        ConfigError::Io(err)
+    }
+}
+
+/// Loads and saves a `UserConfig` at one path.
+#[derive(Debug, Clone)]
+pub struct ConfigStore {
+    path: PathBuf,
+}
+
+impl ConfigStore {
+    pub fn new(path: impl Into<PathBuf>) -> Self {
+        Self { path: path.into() }
+    }
+
+    /// `<config dir>/serpentines/config.toml`, if the platform has a config directory.
+    pub fn default_path() -> Option<PathBuf> {
+        dirs::config_dir().map(|dir| dir.join("serpentines").join(CONFIG_FILE_NAME))
+    }
+
+    /// Store at `default_path()`.
+    pub fn open_default() -> Option<Self> {
+        Self::default_path().map(Self::new)
+    }
+
+    pub fn path(&self) -> &Path {
+        &self.path
+    }
+
+    /// The previous config, replaced on every save.
+    pub fn backup_path(&self) -> PathBuf {
+        self.sibling("bak")
+    }
+
+    fn temp_path(&self) -> PathBuf {
+        self.sibling("tmp")
+    }
+
+    fn corrupt_path(&self) -> PathBuf {
+        self.sibling("corrupt")
+    }
+
+    fn sibling(&self, extension: &str) -> PathBuf {
+        let mut name = self.path.as_os_str().to_owned();
+        name.push(format!(".{extension}"));
+        PathBuf::from(name)
+    }
+
+    /// Read the config. `Ok(None)` if there is no file yet.
+    pub fn read(&self) -> Result<Option<UserConfig>, ConfigError> {
+        read_file(&self.path)
+    }
+
+    /// Load the config, never failing: a missing file gives the defaults, and an unreadable one
+    /// is logged, moved aside, and replaced by the backup or the defaults.
+    pub fn load(&self) -> UserConfig {
+        let err = match self.read() {
+            Ok(Some(config)) => {
+                info!("loaded config from {}", self.path.display());
+                if config.version > CONFIG_VERSION {
+                    warn!(
+                        "{}; settings it doesn't know are ignored",
+                        ConfigError::NewerVersion(config.version)
+                    );
+                }
+                return config;
+            }
+            Ok(None) => return UserConfig::default(),
+            Err(err) => err,
+        };
+        warn!("failed to load {}: {err}", self.path.display());
+        if let ConfigError::Parse(_) = err {
+            let corrupt = self.corrupt_path();
+            match fs::rename(&self.path, &corrupt) {
+                Ok(()) => warn!("moved unreadable config to {}", corrupt.display()),
+                Err(err) => warn!("failed to move unreadable config aside: {err}"),
+            }
+        }
+        match read_file(&self.backup_path()) {
+            Ok(Some(config)) => {
+                warn!("using backup config {}", self.backup_path().display());
+                config
+            }
+            Ok(None) => {
+                warn!("using default config");
+                UserConfig::default()
+            }
+            Err(err) => {
+                warn!("backup config unusable ({err}); using defaults");
+                UserConfig::default()
+            }
+        }
+    }
+
+    /// Write `config` atomically, keeping the previous file as the backup. Refuses to replace a
+    /// config from a newer version, which would drop the settings this one doesn't know.
+    pub fn save(&self, config: &UserConfig) -> Result<(), ConfigError> {
+        let previous = self.read();
+        if let Ok(Some(previous)) = &previous {
+            if previous.version > CONFIG_VERSION {
+                return Err(ConfigError::NewerVersion(previous.version));
+            }
+        }
+        let text = config.to_toml()?;
+        if let Some(dir) = self.path.parent() {
+            fs::create_dir_all(dir)?;
+        }
+        let temp = self.temp_path();
+        let mut file = File::create(&temp)?;
+        file.write_all(text.as_bytes())?;
+        file.sync_all()?;
+        drop(file);
+        // Only a config that still parses is worth keeping as the backup.
+        if matches!(previous, Ok(Some(_))) {
+            fs::copy(&self.path, self.backup_path())?;
+        }
+        fs::rename(&temp, &self.path)?;
+        Ok(())
+    }
+}
+
+fn read_file(path: &Path) -> Result<Option<UserConfig>, ConfigError> {
+    match fs::read_to_string(path) {
+        Ok(text) => UserConfig::from_toml(&text).map(Some),
+        Err(err) if err.kind() == io::ErrorKind::NotFound => Ok(None),
+        Err(err) => Err(ConfigError::Io(err)),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use serpentines_core::HotkeyAction;
+    use tempfile::TempDir;
+
+    use super::*;
+
+    fn store() -> (TempDir, ConfigStore) {
+        let dir = tempfile::tempdir().unwrap();
+        let store = ConfigStore::new(dir.path().join("serpentines").join(CONFIG_FILE_NAME));
+        (dir, store)
+    }
+
+    fn custom_config() -> UserConfig {
+        let mut config = UserConfig::default();
+        config.engine.enabled = false;
+        config.presets = vec![TrailPreset {
+            name: "Mine".into(),
+            ..TrailPreset::default()
+        }];
+        config
+    }
+
+    fn toml_of(config: &UserConfig) -> String {
+        config.to_toml().unwrap()
+    }
+
+    #[test]
+    fn missing_file_gives_defaults() {
+        let (_dir, store) = store();
+        assert!(store.read().unwrap().is_none());
+        assert_eq!(toml_of(&store.load()), toml_of(&UserConfig::default()));
+        assert!(!store.corrupt_path().exists());
+    }
+
+    #[test]
+    fn save_and_load_round_trip() {
+        let (_dir, store) = store();
+        store.save(&custom_config()).unwrap();
+        assert_eq!(toml_of(&store.load()), toml_of(&custom_config()));
+        assert_eq!(store.load().library().len(), 1);
+        assert!(!store.temp_path().exists());
+    }
+
+    #[test]
+    fn backup_keeps_only_a_config_that_parsed() {
+        let (_dir, store) = store();
+        store.save(&UserConfig::default()).unwrap();
+        assert!(
+            !store.backup_path().exists(),
+            "nothing to back up on the first save"
+        );
+
+        store.save(&custom_config()).unwrap();
+        let backup = read_file(&store.backup_path()).unwrap().unwrap();
+        assert_eq!(toml_of(&backup), toml_of(&UserConfig::default()));
+
+        fs::write(store.path(), "not = [toml").unwrap();
+        store.save(&UserConfig::default()).unwrap();
+        let backup = read_file(&store.backup_path()).unwrap().unwrap();
+        assert_eq!(toml_of(&backup), toml_of(&UserConfig::default()));
+    }
+
+    #[test]
+    fn corrupt_file_is_moved_aside_and_the_backup_used() {
+        let (_dir, store) = store();
+        store.save(&custom_config()).unwrap();
+        store.save(&UserConfig::default()).unwrap();
+        fs::write(store.path(), "engine = 42").unwrap();
+
+        assert_eq!(toml_of(&store.load()), toml_of(&custom_config()));
+        assert!(!store.path().exists());
+        assert_eq!(
+            fs::read_to_string(store.corrupt_path()).unwrap(),
+            "engine = 42"
+        );
+    }
+
+    #[test]
+    fn unreadable_file_is_left_in_place() {
+        let (_dir, store) = store();
+        fs::create_dir_all(store.path()).unwrap();
+        fs::write(store.backup_path(), toml_of(&custom_config())).unwrap();
+
+        assert!(matches!(store.read(), Err(ConfigError::Io(_))));
+        assert_eq!(toml_of(&store.load()), toml_of(&custom_config()));
+        assert!(store.path().is_dir());
+        assert!(!store.corrupt_path().exists());
+    }
+
+    #[test]
+    fn bad_hotkey_does_not_set_the_config_aside() {
+        let (_dir, store) = store();
+        let text = toml_of(&custom_config());
+        assert!(text.contains("\"Ctrl+Alt+T\""));
+        fs::create_dir_all(store.path().parent().unwrap()).unwrap();
+        fs::write(
+            store.path(),
+            text.replace("\"Ctrl+Alt+T\"", "\"Ctrl+Alt+Bogus\""),
+        )
+        .unwrap();
+
+        let config = store.load();
+        assert!(!store.corrupt_path().exists());
+        assert!(!config.engine.enabled);
+        assert!(!config
+            .engine
+            .hotkeys
+            .bindings
+            .contains_key(&HotkeyAction::ToggleTrails));
+        assert_eq!(config.engine.hotkeys.unparsed.len(), 1);
+    }
+
+    #[test]
+    fn newer_config_loads_but_is_not_overwritten() {
+        let (_dir, store) = store();
+        let mut newer = custom_config();
+        newer.version = CONFIG_VERSION + 1;
+        store.save(&newer).unwrap();
+        let text = fs::read_to_string(store.path()).unwrap();
+
+        let loaded = store.load();
+        assert!(!loaded.engine.enabled);
+        assert!(matches!(
+            store.save(&UserConfig::default()),
+            Err(ConfigError::NewerVersion(version)) if version == CONFIG_VERSION + 1
+        ));
+        assert_eq!(fs::read_to_string(store.path()).unwrap(), text);
+    }
+}
@@ -0,0 +1,101 @@
+//! Headless export of preset animations to GIF.
+
+use std::io::Write;
+
+use serpentines_core::thumbnail::{PresetAnimation, DEMO_PERIOD};
+use serpentines_core::TrailPreset;
+
+/// Size, length and frame rate of an exported animation.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct GifOptions {
+    pub width: u16,
+    pub height: u16,
+    /// Requested frame rate, snapped to what a GIF frame delay can express (at most 50).
+    pub fps: u16,
+    /// Length of the animation. One demo loop gives a seamless repeat.
+    pub seconds: f32,
+}
+
+impl Default for GifOptions {
+    fn default() -> Self {
+        Self {
+            width: 320,
+            height: 180,
+            fps: 30,
+            seconds: DEMO_PERIOD,
+        }
+    }
+}
+
+/// Encode `preset` following the demo path as a looping GIF. A full demo loop is simulated
+/// first so the trail is already built up on the first frame. Returns the number of frames.
+pub fn write_gif(
+    preset: &TrailPreset,
+    options: &GifOptions,
+    writer: impl Write,
+) -> Result<usize, gif::EncodingError> {
+    // GIF delays are whole hundredths of a second and viewers slow anything under 2 down to 10,
+    // so the animation advances by exactly the delay it is played back at.
+    let delay = (100.0 / options.fps.max(1) as f32).round().max(2.0) as u16;
+    let step = delay as f32 / 100.0;
+    let frames = (options.seconds.max(0.0) / step).round().max(1.0) as usize;
+    let mut animation = PresetAnimation::new(preset, options.width.into(), options.height.into());
+    animation.advance(DEMO_PERIOD);
+
+    let mut encoder = gif::Encoder::new(writer, options.width.max(1), options.height.max(1), &[])?;
+    encoder.set_repeat(gif::Repeat::Infinite)?;
+    for _ in 0..frames {
+        let mut image = animation.render();
+        let mut frame = gif::Frame::from_rgba_speed(
+            image.width as u16,
+            image.height as u16,
+            &mut image.pixels,
+            10,
+        );
+        frame.delay = delay;
+        encoder.write_frame(&frame)?;
+        animation.advance(step);
+    }
+    Ok(frames)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// Frame count and the delay of every frame in an encoded GIF.
+    fn frame_delays(fps: u16, seconds: f32) -> (usize, Vec<u16>) {
+        let options = GifOptions {
+            width: 8,
+            height: 8,
+            fps,
+            seconds,
+        };
+        let mut bytes = Vec::new();
+        let frames = write_gif(&TrailPreset::default(), &options, &mut bytes).unwrap();
+        let mut decoder = gif::DecodeOptions::new()
+            .read_info(bytes.as_slice())
+            .unwrap();
+        let mut delays = Vec::new();
+        while let Some(frame) = decoder.read_next_frame().unwrap() {
+            delays.push(frame.delay);
+        }
+        (frames, delays)
+    }
+
+    #[test]
+    fn frame_rate_snaps_to_whole_delays() {
+        // 30 fps is 3.33 hundredths; playing at 3 means 33.3 frames per second of animation.
+        let (frames, delays) = frame_delays(30, 0.9);
+        assert_eq!(frames, 30);
+        assert_eq!(delays, vec![3; 30]);
+    }
+
+    #[test]
+    fn frame_rate_is_capped_at_fifty() {
+        let (frames, delays) = frame_delays(100, 1.0);
+        assert_eq!(frames, 50);
+        assert!(delays.iter().all(|&delay| delay == 2));
+        assert_eq!(frame_delays(0, 0.0), (1, vec![100]));
+    }
+}
@@ -0,0 +1,636 @@
+//! Local control socket for scripting a running instance: newline-delimited JSON-RPC 2.0 over a
+//! Unix domain socket, or a named pipe on Windows.
+//!
+//! Methods are `enable`, `disable`, `set_preset` (`{"name": ...}`), `get_status`,
+//! `list_monitors` and `subscribe`. After `subscribe` the connection also receives `event`
+//! notifications carrying an `IpcEvent`.
+//!
+//! ```text
+//! -> {"jsonrpc": "2.0", "id": 1, "method": "set_preset", "params": {"name": "Comet"}}
+//! <- {"jsonrpc": "2.0", "id": 1, "result": {"enabled": true, "preset": "Comet", ...}}
+//! ```
+
+use std::collections::VecDeque;
+use std::fmt;
+use std::io::{self, BufRead, BufReader, Write};
+use std::path::{Path, PathBuf};
+use std::sync::mpsc::{channel, Receiver, Sender};
+use std::sync::{Arc, Mutex};
+
+use interprocess::local_socket::prelude::*;
+use interprocess::local_socket::{GenericFilePath, ListenerOptions, Name, RecvHalf, SendHalf};
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+use serpentines_platform::MonitorRect;
+use tracing::{info, warn};
+
+/// Overrides the socket path (or pipe name on Windows) for both the server and the CLI.
+pub const SOCKET_ENV_VAR: &str = "SERPENTINES_SOCKET";
+pub const JSONRPC_VERSION: &str = "2.0";
+/// Method name of server-to-client event notifications.
+pub const EVENT_METHOD: &str = "event";
+
+/// Per-user socket path in the runtime directory on Unix. Pipe names on Windows are
+/// machine-wide, so the pipe is named after the user and login session.
+pub fn default_endpoint() -> PathBuf {
+    if cfg!(windows) {
+        let user = std::env::var("USERNAME").unwrap_or_default();
+        let session = std::env::var("SESSIONNAME").unwrap_or_default();
+        return PathBuf::from(format!(r"\\.\pipe\serpentines-{user}-{session}"));
+    }
+    match dirs::runtime_dir() {
+        Some(dir) => dir.join("serpentines.sock"),
+        None => {
+            let user = std::env::var("USER").unwrap_or_default();
+            std::env::temp_dir().join(format!("serpentines-{user}.sock"))
+        }
+    }
+}
+
+/// `SOCKET_ENV_VAR` if set, else `default_endpoint()`.
+pub fn endpoint() -> PathBuf {
+    std::env::var_os(SOCKET_ENV_VAR)
+        .map(PathBuf::from)
+        .unwrap_or_else(default_endpoint)
+}
+
+fn socket_name(endpoint: &Path) -> io::Result<Name<'_>> {
+    endpoint.to_fs_name::<GenericFilePath>()
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct RpcRequest {
+    pub jsonrpc: String,
+    /// Absent for notifications, which get no response.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub id: Option<Value>,
+    pub method: String,
+    #[serde(default, skip_serializing_if = "Value::is_null")]
+    pub params: Value,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct RpcResponse {
+    pub jsonrpc: String,
+    pub id: Value,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub result: Option<Value>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub error: Option<RpcError>,
+}
+
+impl RpcResponse {
+    fn new(id: Value, result: Result<Value, RpcError>) -> Self {
+        let (result, error) = match result {
+            Ok(result) => (Some(result), None),
+            Err(error) => (None, Some(error)),
+        };
+        Self {
+            jsonrpc: JSONRPC_VERSION.into(),
+            id,
+            result,
+            error,
+        }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct RpcError {
+    pub code: i64,
+    pub message: String,
+}
+
+impl RpcError {
+    pub const PARSE_ERROR: i64 = -32700;
+    pub const INVALID_REQUEST: i64 = -32600;
+    pub const METHOD_NOT_FOUND: i64 = -32601;
+    pub const INVALID_PARAMS: i64 = -32602;
+    /// The runtime stopped before answering.
+    pub const UNAVAILABLE: i64 = -32000;
+    pub const UNKNOWN_PRESET: i64 = -32001;
+
+    pub fn new(code: i64, message: impl Into<String>) -> Self {
+        Self {
+            code,
+            message: message.into(),
+        }
+    }
+}
+
+impl fmt::Display for RpcError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{} (code {})", self.message, self.code)
+    }
+}
+
+impl std::error::Error for RpcError {}
+
+/// Calls answered by the runtime. `subscribe` is handled by the server itself.
+#[derive(Debug, Clone, PartialEq)]
+pub enum IpcMethod {
+    Enable,
+    Disable,
+    SetPreset { name: String },
+    GetStatus,
+    ListMonitors,
+}
+
+#[derive(Deserialize)]
+struct SetPresetParams {
+    name: String,
+}
+
+impl IpcMethod {
+    pub fn parse(method: &str, params: Value) -> Result<Self, RpcError> {
+        Ok(match method {
+            "enable" => IpcMethod::Enable,
+            "disable" => IpcMethod::Disable,
+            "set_preset" => {
+                let params: SetPresetParams = serde_json::from_value(params)
+                    .map_err(|err| RpcError::new(RpcError::INVALID_PARAMS, err.to_string()))?;
+                IpcMethod::SetPreset { name: params.name }
+            }
+            "get_status" => IpcMethod::GetStatus,
+            "list_monitors" => IpcMethod::ListMonitors,
+            _ => {
+                return Err(RpcError::new(
+                    RpcError::METHOD_NOT_FOUND,
+                    format!("unknown method '{method}'"),
+                ))
+            }
+        })
+    }
+
+    pub fn name(&self) -> &'static str {
+        match self {
+            IpcMethod::Enable => "enable",
+            IpcMethod::Disable => "disable",
+            IpcMethod::SetPreset { .. } => "set_preset",
+            IpcMethod::GetStatus => "get_status",
+            IpcMethod::ListMonitors => "list_monitors",
+        }
+    }
+
+    pub fn params(&self) -> Value {
+        match self {
+            IpcMethod::SetPreset { name } => serde_json::json!({ "name": name }),
+            _ => Value::Null,
+        }
+    }
+}
+
+/// Result of `get_status`, and of the calls that change it.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct IpcStatus {
+    pub enabled: bool,
+    pub paused: bool,
+    pub preset: String,
+    /// Library preset names, in menu order.
+    pub presets: Vec<String>,
+    pub live_particles: usize,
+    pub monitors: usize,
+}
+
+/// Pushed to subscribed connections.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(tag = "type", content = "data", rename_all = "snake_case")]
+pub enum IpcEvent {
+    StatusChanged(IpcStatus),
+    MonitorsChanged(Vec<MonitorRect>),
+    /// The app is exiting; the connection closes next.
+    Quitting,
+}
+
+/// A call from a client, to be answered with `respond` on the runtime thread.
+pub struct IpcRequest {
+    pub method: IpcMethod,
+    id: Option<Value>,
+    reply: Sender<String>,
+}
+
+impl IpcRequest {
+    pub fn respond(self, result: Result<Value, RpcError>) {
+        if let Some(id) = self.id {
+            let _ = self.reply.send(to_line(&RpcResponse::new(id, result)));
+        }
+    }
+}
+
+/// Accepts connections on a helper thread and hands their calls to the runtime.
+pub struct IpcServer {
+    endpoint: PathBuf,
+    /// Outgoing line queues of subscribed connections.
+    subscribers: Arc<Mutex<Vec<Sender<String>>>>,
+}
+
+impl IpcServer {
+    /// Listen on `endpoint`. Fails if another instance is already listening there; a stale
+    /// socket file left by a crash is replaced.
+    pub fn bind(endpoint: PathBuf) -> io::Result<(Self, Receiver<IpcRequest>)> {
+        if LocalSocketStream::connect(socket_name(&endpoint)?).is_ok() {
+            return Err(io::Error::new(
+                io::ErrorKind::AddrInUse,
+                format!("another instance is listening on {}", endpoint.display()),
+            ));
+        }
+        let listener = ListenerOptions::new()
+            .name(socket_name(&endpoint)?)
+            .try_overwrite(true)
+            .create_sync()?;
+        info!("IPC listening on {}", endpoint.display());
+        let (request_sender, requests) = channel();
+        let subscribers: Arc<Mutex<Vec<Sender<String>>>> = Arc::default();
+        let connection_subscribers = Arc::clone(&subscribers);
+        std::thread::spawn(move || {
+            for stream in listener.incoming() {
+                match stream {
+                    Ok(stream) => {
+                        let requests = request_sender.clone();
+                        let subscribers = Arc::clone(&connection_subscribers);
+                        std::thread::spawn(move || serve_connection(stream, requests, subscribers));
+                    }
+                    Err(err) => warn!("IPC accept failed: {err}"),
+                }
+            }
+        });
+        Ok((
+            Self {
+                endpoint,
+                subscribers,
+            },
+            requests,
+        ))
+    }
+
+    pub fn endpoint(&self) -> &Path {
+        &self.endpoint
+    }
+
+    pub fn has_subscribers(&self) -> bool {
+        !self.lock_subscribers().is_empty()
+    }
+
+    /// Send `event` to every subscribed connection, dropping closed ones.
+    pub fn broadcast(&self, event: &IpcEvent) {
+        let line = to_line(&RpcNotification::new(event));
+        self.lock_subscribers()
+            .retain(|subscriber| subscriber.send(line.clone()).is_ok());
+    }
+
+    fn lock_subscribers(&self) -> std::sync::MutexGuard<'_, Vec<Sender<String>>> {
+        self.subscribers
+            .lock()
+            .unwrap_or_else(|poisoned| poisoned.into_inner())
+    }
+}
+
+impl Drop for IpcServer {
+    fn drop(&mut self) {
+        if cfg!(unix) {
+            let _ = std::fs::remove_file(&self.endpoint);
+        }
+    }
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+struct RpcNotification {
+    jsonrpc: String,
+    method: String,
+    params: IpcEvent,
+}
+
+impl RpcNotification {
+    fn new(event: &IpcEvent) -> Self {
+        Self {
+            jsonrpc: JSONRPC_VERSION.into(),
+            method: EVENT_METHOD.into(),
+            params: event.clone(),
+        }
+    }
+}
+
+fn to_line(message: &impl Serialize) -> String {
+    serde_json::to_string(message).expect("IPC messages serialize")
+}
+
+/// Read requests line by line; responses and events go out through a writer thread.
+fn serve_connection(
+    stream: LocalSocketStream,
+    requests: Sender<IpcRequest>,
+    subscribers: Arc<Mutex<Vec<Sender<String>>>>,
+) {
+    let (receive, mut send) = stream.split();
+    let (out, lines) = channel::<String>();
+    std::thread::spawn(move || {
+        for line in lines {
+            if writeln!(send, "{line}")
+                .and_then(|()| send.flush())
+                .is_err()
+            {
+                break;
+            }
+        }
+    });
+    let respond = |id: Value, result: Result<Value, RpcError>| {
+        let _ = out.send(to_line(&RpcResponse::new(id, result)));
+    };
+    for line in BufReader::new(receive).lines() {
+        let Ok(line) = line else {
+            break;
+        };
+        if line.trim().is_empty() {
+            continue;
+        }
+        let request = match parse_request(&line) {
+            Ok(request) => request,
+            Err(error) => {
+                respond(Value::Null, Err(error));
+                continue;
+            }
+        };
+        if request.method == "subscribe" {
+            subscribers
+                .lock()
+                .unwrap_or_else(|poisoned| poisoned.into_inner())
+                .push(out.clone());
+            if let Some(id) = request.id {
+                respond(id, Ok(Value::Bool(true)));
+            }
+            continue;
+        }
+        let method = match IpcMethod::parse(&request.method, request.params) {
+            Ok(method) => method,
+            Err(error) => {
+                // Notifications get no response, not even an error.
+                if let Some(id) = request.id {
+                    respond(id, Err(error));
+                }
+                continue;
+            }
+        };
+        let request = IpcRequest {
+            method,
+            id: request.id,
+            reply: out.clone(),
+        };
+        if let Err(unsent) = requests.send(request) {
+            if let Some(id) = unsent.0.id {
+                respond(
+                    id,
+                    Err(RpcError::new(RpcError::UNAVAILABLE, "app is shutting down")),
+                );
+            }
+            break;
+        }
+    }
+}
+
+fn parse_request(line: &str) -> Result<RpcRequest, RpcError> {
+    let value: Value = serde_json::from_str(line)
+        .map_err(|err| RpcError::new(RpcError::PARSE_ERROR, err.to_string()))?;
+    let request: RpcRequest = serde_json::from_value(value)
+        .map_err(|err| RpcError::new(RpcError::INVALID_REQUEST, err.to_string()))?;
+    if request.jsonrpc != JSONRPC_VERSION {
+        return Err(RpcError::new(
+            RpcError::INVALID_REQUEST,
+            "expected \"jsonrpc\": \"2.0\"",
+        ));
+    }
+    Ok(request)
+}
+
+#[derive(Debug)]
+pub enum IpcError {
+    Io(io::Error),
+    Json(serde_json::Error),
+    Rpc(RpcError),
+    /// The server closed the connection.
+    Closed,
+}
+
+impl fmt::Display for IpcError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            IpcError::Io(err) => write!(f, "IPC connection error: {err}"),
+            IpcError::Json(err) => write!(f, "invalid IPC message: {err}"),
+            IpcError::Rpc(err) => write!(f, "{err}"),
+            IpcError::Closed => f.write_str("IPC connection closed"),
+        }
+    }
+}
+
+impl std::error::Error for IpcError {}
+
+impl From<io::Error> for IpcError {
+    fn from(err: io::Error) -> Self {
+        IpcError::Io(err)
+    }
+}
+
+impl From<serde_json::Error> for IpcError {
+    fn from(err: serde_json::Error) -> Self {
+        IpcError::Json(err)
+    }
+}
+
+/// Blocking client for a running instance.
+pub struct IpcClient {
+    reader: BufReader<RecvHalf>,
+    writer: SendHalf,
+    next_id: u64,
+    /// Events that arrived while waiting for a response.
+    pending: VecDeque<IpcEvent>,
+}
+
+impl IpcClient {
+    pub fn connect(endpoint: &Path) -> io::Result<Self> {
+        let (receive, send) = LocalSocketStream::connect(socket_name(endpoint)?)?.split();
+        Ok(Self {
+            reader: BufReader::new(receive),
+            writer: send,
+            next_id: 1,
+            pending: VecDeque::new(),
+        })
+    }
+
+    /// Send a raw call and wait for its result.
+    pub fn call(&mut self, method: &str, params: Value) -> Result<Value, IpcError> {
+        let id = self.next_id;
+        self.next_id += 1;
+        let request = RpcRequest {
+            jsonrpc: JSONRPC_VERSION.into(),
+            id: Some(id.into()),
+            method: method.into(),
+            params,
+        };
+        writeln!(self.writer, "{}", to_line(&request))?;
+        self.writer.flush()?;
+        loop {
+            let message = self.read_message()?;
+            if message.get("id").and_then(Value::as_u64) == Some(id) {
+                let response: RpcResponse = serde_json::from_value(message)?;
+                return match response.error {
+                    Some(error) => Err(IpcError::Rpc(error)),
+                    None => Ok(response.result.unwrap_or(Value::Null)),
+                };
+            }
+            if let Some(event) = parse_event(message)? {
+                self.pending.push_back(event);
+            }
+        }
+    }
+
+    pub fn request(&mut self, method: &IpcMethod) -> Result<Value, IpcError> {
+        self.call(method.name(), method.params())
+    }
+
+    pub fn status(&mut self) -> Result<IpcStatus, IpcError> {
+        Ok(serde_json::from_value(
+            self.request(&IpcMethod::GetStatus)?,
+        )?)
+    }
+
+    pub fn monitors(&mut self) -> Result<Vec<MonitorRect>, IpcError> {
+        Ok(serde_json::from_value(
+            self.request(&IpcMethod::ListMonitors)?,
+        )?)
+    }
+
+    pub fn subscribe(&mut self) -> Result<(), IpcError> {
+        self.call("subscribe", Value::Null).map(|_| ())
+    }
+
+    /// Wait for the next event after `subscribe`. `None` once the server has closed.
+    pub fn next_event(&mut self) -> Result<Option<IpcEvent>, IpcError> {
+        if let Some(event) = self.pending.pop_front() {
+            return Ok(Some(event));
+        }
+        loop {
+            let message = match self.read_message() {
+                Ok(message) => message,
+                Err(IpcError::Closed) => return Ok(None),
+                Err(err) => return Err(err),
+            };
+            if let Some(event) = parse_event(message)? {
+                return Ok(Some(event));
+            }
+        }
+    }
+
+    fn read_message(&mut self) -> Result<Value, IpcError> {
+        let mut line = String::new();
+        if self.reader.read_line(&mut line)? == 0 {
+            return Err(IpcError::Closed);
+        }
+        Ok(serde_json::from_str(&line)?)
+    }
+}
+
+fn parse_event(message: Value) -> Result<Option<IpcEvent>, IpcError> {
+    if message.get("method").and_then(Value::as_str) != Some(EVENT_METHOD) {
+        return Ok(None);
+    }
+    let notification: RpcNotification = serde_json::from_value(message)?;
+    Ok(Some(notification.params))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn status(preset: &str) -> IpcStatus {
+        IpcStatus {
+            enabled: true,
+            paused: false,
+            preset: preset.into(),
+            presets: vec![preset.into()],
+            live_particles: 0,
+            monitors: 1,
+        }
+    }
+
+    /// Answer requests the way the runtime would, with a single "Comet" preset.
+    fn serve(requests: Receiver<IpcRequest>) {
+        std::thread::spawn(move || {
+            for request in requests {
+                let result = match &request.method {
+                    IpcMethod::GetStatus => Ok(serde_json::to_value(status("Comet")).unwrap()),
+                    IpcMethod::SetPreset { name } => Err(RpcError::new(
+                        RpcError::UNKNOWN_PRESET,
+                        format!("no preset named '{name}'"),
+                    )),
+                    _ => Ok(Value::Null),
+                };
+                request.respond(result);
+            }
+        });
+    }
+
+    #[test]
+    fn client_round_trips_through_a_socket() {
+        let dir = tempfile::tempdir().unwrap();
+        let endpoint = dir.path().join("serpentines.sock");
+        let (server, requests) = IpcServer::bind(endpoint.clone()).unwrap();
+        serve(requests);
+        assert!(
+            IpcServer::bind(endpoint.clone()).is_err(),
+            "second instance must not bind"
+        );
+
+        let mut client = IpcClient::connect(&endpoint).unwrap();
+        assert_eq!(client.status().unwrap(), status("Comet"));
+        match client.request(&IpcMethod::SetPreset {
+            name: "Nope".into(),
+        }) {
+            Err(IpcError::Rpc(error)) => assert_eq!(error.code, RpcError::UNKNOWN_PRESET),
+            other => panic!("expected an RPC error, got {other:?}"),
+        }
+
+        client.subscribe().unwrap();
+        assert!(server.has_subscribers());
+        server.broadcast(&IpcEvent::StatusChanged(status("Comet")));
+        assert_eq!(
+            client.next_event().unwrap(),
+            Some(IpcEvent::StatusChanged(status("Comet")))
+        );
+
+        drop(server);
+        assert!(!endpoint.exists());
+    }
+
+    #[test]
+    fn notifications_get_no_response_and_bad_lines_get_errors() {
+        let dir = tempfile::tempdir().unwrap();
+        let endpoint = dir.path().join("serpentines.sock");
+        let (_server, requests) = IpcServer::bind(endpoint.clone()).unwrap();
+        serve(requests);
+        let mut client = IpcClient::connect(&endpoint).unwrap();
+
+        // Neither notification may be answered, so the first response is to the malformed line.
+        writeln!(
+            client.writer,
+            r#"{{"jsonrpc": "2.0", "method": "no_such_method"}}"#
+        )
+        .unwrap();
+        writeln!(client.writer, r#"{{"jsonrpc": "2.0", "method": "enable"}}"#).unwrap();
+        writeln!(client.writer, "{{not json").unwrap();
+        let response: RpcResponse = serde_json::from_value(client.read_message().unwrap()).unwrap();
+        assert_eq!(response.id, Value::Null);
+        assert_eq!(response.error.unwrap().code, RpcError::PARSE_ERROR);
+
+        writeln!(
+            client.writer,
+            r#"{{"jsonrpc": "1.0", "id": 7, "method": "enable"}}"#
+        )
+        .unwrap();
+        let response: RpcResponse = serde_json::from_value(client.read_message().unwrap()).unwrap();
+        assert_eq!(response.error.unwrap().code, RpcError::INVALID_REQUEST);
+
+        match client.call("no_such_method", Value::Null) {
+            Err(IpcError::Rpc(error)) => assert_eq!(error.code, RpcError::METHOD_NOT_FOUND),
+            other => panic!("expected an RPC error, got {other:?}"),
+        }
+        // The connection is still usable after all of the above.
+        assert_eq!(client.status().unwrap(), status("Comet"));
+    }
+}
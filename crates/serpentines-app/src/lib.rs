@@ -0,0 +1,14 @@
+//! Serpentines application: backend selection and the platform-neutral runtime.
+
+pub mod backends;
+pub mod cli;
+pub mod config_store;
+pub mod export;
+pub mod ipc;
+pub mod logging;
+pub mod runtime;
+pub mod tray_menu;
+pub use config_store::{ConfigStore, UserConfig};
+pub use ipc::{IpcClient, IpcEvent, IpcServer};
+pub use runtime::Runtime;
+pub use tray_menu::build_tray_menu;
@@ -0,0 +1,282 @@
+//! Logging setup: stdout, the in-app log buffer, and an optional rotating log file.
+
+use std::fmt::Write as _;
+use std::fs::{self, File, OpenOptions};
+use std::io::{self, Write};
+use std::path::{Path, PathBuf};
+use std::sync::Mutex;
+
+use serpentines_ui::LogBuffer;
+use tracing::field::{Field, Visit};
+use tracing::{Event, Subscriber};
+use tracing_subscriber::layer::{Context, SubscriberExt};
+use tracing_subscriber::util::SubscriberInitExt;
+use tracing_subscriber::{EnvFilter, Layer};
+
+/// Set to `1` or `true` to also log to a file in `log_dir()`.
+pub const LOG_FILE_ENV_VAR: &str = "SERPENTINES_LOG_FILE";
+const LOG_FILE_NAME: &str = "serpentines.log";
+/// Size at which the log file is rotated.
+const LOG_FILE_MAX_BYTES: u64 = 2 * 1024 * 1024;
+/// Rotated files kept next to the current one.
+const LOG_FILES_KEPT: usize = 3;
+
+/// Tracing layer that copies every event into a `LogBuffer`.
+pub struct LogBufferLayer {
+    buffer: LogBuffer,
+}
+
+impl LogBufferLayer {
+    pub fn new(buffer: LogBuffer) -> Self {
+        Self { buffer }
+    }
+}
+
+impl<S: Subscriber> Layer<S> for LogBufferLayer {
+    fn on_event(&self, event: &Event<'_>, _ctx: Context<'_, S>) {
+        let mut visitor = MessageVisitor::default();
+        event.record(&mut visitor);
+        let metadata = event.metadata();
+        self.buffer
+            .push(*metadata.level(), metadata.target(), visitor.finish());
+    }
+}
+
+/// Collects the `message` field first and the rest as `key=value`.
+#[derive(Default)]
+struct MessageVisitor {
+    message: String,
+    fields: String,
+}
+
+impl MessageVisitor {
+    fn finish(self) -> String {
+        match (self.message.is_empty(), self.fields.is_empty()) {
+            (_, true) => self.message,
+            (true, false) => self.fields,
+            (false, false) => format!("{} {}", self.message, self.fields),
+        }
+    }
+}
+
+impl Visit for MessageVisitor {
+    fn record_str(&mut self, field: &Field, value: &str) {
+        if field.name() == "message" {
+            self.message.push_str(value);
+        } else {
+            self.record_debug(field, &value);
+        }
+    }
+
+    fn record_debug(&mut self, field: &Field, value: &dyn std::fmt::Debug) {
+        if field.name() == "message" {
+            let _ = write!(self.message, "{value:?}");
+        } else {
+            if !self.fields.is_empty() {
+                self.fields.push(' ');
+            }
+            let _ = write!(self.fields, "{}={value:?}", field.name());
+        }
+    }
+}
+
+/// Append-only log file that is renamed to `<name>.1` (shifting older ones up) once it reaches
+/// `max_bytes`, keeping at most `keep` rotated files.
+pub struct RotatingFile {
+    path: PathBuf,
+    file: File,
+    written: u64,
+    max_bytes: u64,
+    keep: usize,
+}
+
+impl RotatingFile {
+    pub fn open(dir: &Path, name: &str, max_bytes: u64, keep: usize) -> io::Result<Self> {
+        fs::create_dir_all(dir)?;
+        let path = dir.join(name);
+        let file = OpenOptions::new().create(true).append(true).open(&path)?;
+        let written = file.metadata()?.len();
+        Ok(Self {
+            path,
+            file,
+            written,
+            max_bytes,
+            keep,
+        })
+    }
+
+    pub fn path(&self) -> &Path {
+        &self.path
+    }
+
+    fn rotated_path(&self, index: usize) -> PathBuf {
+        let mut name = self.path.as_os_str().to_owned();
+        name.push(format!(".{index}"));
+        PathBuf::from(name)
+    }
+
+    fn rotate(&mut self) -> io::Result<()> {
+        self.file.flush()?;
+        if self.keep == 0 {
+            self.file = File::create(&self.path)?;
+        } else {
+            let _ = fs::remove_file(self.rotated_path(self.keep));
+            for index in (1..self.keep).rev() {
+                let from = self.rotated_path(index);
+                if from.exists() {
+                    fs::rename(&from, self.rotated_path(index + 1))?;
+                }
+            }
+            fs::rename(&self.path, self.rotated_path(1))?;
+            self.file = OpenOptions::new()
+                .create(true)
+                .append(true)
+                .open(&self.path)?;
+        }
+        self.written = 0;
+        Ok(())
+    }
+}
+
+impl Write for RotatingFile {
+    fn write(&mut self, buf: &[u8]) -> io::Result<usize> {
+        if self.written > 0 && self.written + buf.len() as u64 > self.max_bytes {
+            self.rotate()?;
+        }
+        let written = self.file.write(buf)?;
+        self.written += written as u64;
+        Ok(written)
+    }
+
+    fn flush(&mut self) -> io::Result<()> {
+        self.file.flush()
+    }
+}
+
+/// Where log files go: the per-user local data directory.
+pub fn log_dir() -> Option<PathBuf> {
+    dirs::data_local_dir().map(|dir| dir.join("serpentines").join("logs"))
+}
+
+fn log_file_requested() -> bool {
+    std::env::var(LOG_FILE_ENV_VAR)
+        .map(|value| {
+            matches!(
+                value.trim().to_ascii_lowercase().as_str(),
+                "1" | "true" | "yes"
+            )
+        })
+        .unwrap_or(false)
+}
+
+/// Install a stderr-only subscriber, for subcommands whose stdout is their output.
+pub fn init_stderr(filter: &str) {
+    let _ = tracing_subscriber::registry()
+        .with(EnvFilter::new(filter))
+        .with(tracing_subscriber::fmt::layer().with_writer(io::stderr))
+        .try_init();
+}
+
+/// Install the global subscriber: stdout, `buffer`, and the rotating file when
+/// `LOG_FILE_ENV_VAR` asks for it. `filter` is an `EnvFilter` directive such as `"info"`.
+/// Returns the log file path, if one was opened.
+pub fn init(buffer: LogBuffer, filter: &str) -> Option<PathBuf> {
+    let file = if log_file_requested() {
+        match log_dir()
+            .map(|dir| RotatingFile::open(&dir, LOG_FILE_NAME, LOG_FILE_MAX_BYTES, LOG_FILES_KEPT))
+        {
+            Some(Ok(file)) => Some(file),
+            Some(Err(err)) => {
+                eprintln!("failed to open log file: {err}");
+                None
+            }
+            None => {
+                eprintln!("no data directory for the log file");
+                None
+            }
+        }
+    } else {
+        None
+    };
+    let path = file.as_ref().map(|file| file.path().to_path_buf());
+    let file_layer = file.map(|file| {
+        tracing_subscriber::fmt::layer()
+            .with_ansi(false)
+            .with_writer(Mutex::new(file))
+    });
+    let _ = tracing_subscriber::registry()
+        .with(EnvFilter::new(filter))
+        .with(tracing_subscriber::fmt::layer())
+        .with(LogBufferLayer::new(buffer))
+        .with(file_layer)
+        .try_init();
+    path
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn contents(path: &Path) -> Option<String> {
+        fs::read_to_string(path).ok()
+    }
+
+    #[test]
+    fn rotation_shifts_older_files_up() {
+        let dir = tempfile::tempdir().unwrap();
+        let mut file = RotatingFile::open(dir.path(), "test.log", 10, 2).unwrap();
+        for line in ["aaaaaaaaa\n", "bbbbbbbbb\n", "ccccccccc\n", "ddddddddd\n"] {
+            file.write_all(line.as_bytes()).unwrap();
+        }
+        file.flush().unwrap();
+
+        assert_eq!(contents(file.path()).as_deref(), Some("ddddddddd\n"));
+        assert_eq!(
+            contents(&file.rotated_path(1)).as_deref(),
+            Some("ccccccccc\n")
+        );
+        assert_eq!(
+            contents(&file.rotated_path(2)).as_deref(),
+            Some("bbbbbbbbb\n")
+        );
+        assert!(!file.rotated_path(3).exists());
+    }
+
+    #[test]
+    fn keeping_no_files_truncates_in_place() {
+        let dir = tempfile::tempdir().unwrap();
+        let mut file = RotatingFile::open(dir.path(), "test.log", 10, 0).unwrap();
+        file.write_all(b"first line\n").unwrap();
+        file.write_all(b"second\n").unwrap();
+        file.flush().unwrap();
+
+        assert_eq!(contents(file.path()).as_deref(), Some("second\n"));
+        assert!(!file.rotated_path(1).exists());
+    }
+
+    #[test]
+    fn rotates_only_past_the_size_limit() {
+        let dir = tempfile::tempdir().unwrap();
+        let mut file = RotatingFile::open(dir.path(), "test.log", 10, 1).unwrap();
+        // An oversized write to an empty file goes through rather than rotating forever.
+        file.write_all(b"much longer than ten bytes\n").unwrap();
+        assert!(!file.rotated_path(1).exists());
+        file.write_all(b"12345").unwrap();
+        file.write_all(b"67890").unwrap();
+        assert_eq!(
+            contents(&file.rotated_path(1)).as_deref(),
+            Some("much longer than ten bytes\n")
+        );
+        assert_eq!(contents(file.path()).as_deref(), Some("1234567890"));
+        drop(file);
+
+        // Reopening counts what is already on disk.
+        let mut file = RotatingFile::open(dir.path(), "test.log", 10, 1).unwrap();
+        file.write_all(b"x").unwrap();
+        assert_eq!(
+            contents(&file.rotated_path(1)).as_deref(),
+            Some("1234567890")
+        );
+        assert_eq!(contents(file.path()).as_deref(), Some("x"));
+    }
+}
@@ -1,18 +1,72 @@
-use tracing::{info, Level};
-use tracing_subscriber::FmtSubscriber;
+use std::process::ExitCode;
 
-use serpentines_win::run_app;
+use clap::Parser;
+use serpentines_app::cli::{self, Cli, CliResult, Command, RunArgs};
+use serpentines_app::{backends, logging, IpcServer, Runtime, UserConfig};
+use serpentines_platform::MonotonicClock;
+use serpentines_ui::{spawn_ui_thread, LogBuffer};
+use tracing::{info, warn};
 
-fn main() {
-    // Init logging
-    let subscriber = FmtSubscriber::builder()
-        .with_max_level(Level::INFO)
-        .with_env_filter("info")
-        .finish();
-    let _ = tracing::subscriber::set_global_default(subscriber);
+fn main() -> ExitCode {
+    let cli = Cli::parse();
+    let filter = cli.log_filter();
+    let result = match cli.command.unwrap_or(Command::Run(RunArgs::default())) {
+        Command::Run(args) => run(args, filter),
+        command => {
+            logging::init_stderr(filter);
+            cli::execute(command)
+        }
+    };
+    match result {
+        Ok(()) => ExitCode::SUCCESS,
+        Err(e) => {
+            eprintln!("Serpentines error: {e}");
+            ExitCode::FAILURE
+        }
+    }
+}
+
+fn run(args: RunArgs, filter: &str) -> CliResult<()> {
+    let logs = LogBuffer::default();
+    let log_file = logging::init(logs.clone(), filter);
 
     info!("Serpentines starting");
-    if let Err(e) = run_app() {
-        eprintln!("Serpentines error: {e}");
+    if let Some(path) = log_file {
+        info!("logging to {}", path.display());
+    }
+
+    let store = cli::open_store(args.config.as_deref());
+    let mut user = match &store {
+        Some(store) => store.load(),
+        None => {
+            warn!("no config directory; settings will not be saved");
+            UserConfig::default()
+        }
+    };
+    let library = user.library();
+    if let Some(name) = &args.preset {
+        let preset = library
+            .get(name)
+            .ok_or_else(|| format!("unknown preset '{name}'"))?;
+        user.engine.preset = preset.clone();
+    }
+
+    let platform = backends::create(args.backend.as_deref())?;
+
+    // Spawn egui UI on a separate thread
+    let ui_handles = spawn_ui_thread(logs);
+    let mut runtime = Runtime::new(platform, user.engine, MonotonicClock::new())
+        .with_presets(library)
+        .with_ui_settings(user.ui);
+    if let Some(store) = store {
+        runtime = runtime.with_store(store);
+    }
+    if !args.no_ipc {
+        match IpcServer::bind(cli::socket_path(args.socket)) {
+            Ok((server, requests)) => runtime = runtime.with_ipc(server, requests),
+            Err(err) => warn!("IPC disabled: {err}"),
+        }
     }
+    let mut runtime = runtime.with_ui(ui_handles);
+    runtime.run()
 }
@@ -0,0 +1,845 @@
+//! Platform-neutral app runtime: owns the trail engine, frame loop, config and UI channels, and
+//! talks to the OS only through the `serpentines-platform` traits.
+
+use std::collections::HashMap;
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::mpsc::{channel, Receiver, Sender};
+use std::sync::Arc;
+use std::time::Duration;
+
+use glam::Vec2;
+use serpentines_core::scheduler::DEFAULT_REFRESH_HZ;
+use serpentines_core::{
+    EngineConfig, EngineStats, FrameHistory, FrameScheduler, FrameTimings, FrameWait, HotkeyAction,
+    LibraryEdit, PresetLibrary, QualityController, TrailEngine, TrailPreset,
+};
+use serpentines_platform::{
+    DamageRect, DesktopLayout, FrameClock, HotkeyId, HudOverlay, InputEvent, MonitorEvent,
+    Platform, PowerMode, Result, ShellEvent, TrayAction, Waker,
+};
+use serpentines_ui::{UiCommand, UiEvent, UiHandles, UiSettings};
+use tracing::{info, warn};
+
+use crate::ipc::{IpcMethod, IpcRequest, IpcStatus, RpcError};
+use crate::{build_tray_menu, ConfigStore, IpcEvent, IpcServer, UserConfig};
+
+/// How often stats are pushed to the control panel and the HUD.
+const STATS_INTERVAL: Duration = Duration::from_millis(500);
+/// HUD offset from the top-left corner of the first monitor, in pixels.
+const HUD_MARGIN: i32 = 16;
+/// Quiet period after the last change before the config is written, so a slider drag is one save.
+const SAVE_DELAY: Duration = Duration::from_secs(1);
+
+/// Events produced off the main thread, merged into one channel.
+enum RuntimeEvent {
+    Input(InputEvent),
+    Ui(Box<UiEvent>),
+    Tray(TrayAction),
+    Hotkey(HotkeyId),
+    Ipc(IpcRequest),
+}
+
+pub struct Runtime {
+    platform: Platform,
+    engine: TrailEngine,
+    scheduler: FrameScheduler,
+    quality: QualityController,
+    presets: PresetLibrary,
+    /// Registered hotkeys and what they do.
+    hotkey_actions: HashMap<HotkeyId, HotkeyAction>,
+    /// Cursor input is ignored while paused.
+    paused: bool,
+    ui_commands: Option<crossbeam_channel::Sender<UiCommand>>,
+    /// Frames rendered since stats were last sent, and when that was.
+    stats_frames: u32,
+    stats_sent_at: Duration,
+    history: FrameHistory,
+    /// HUD currently handed to the renderer.
+    hud: Option<HudOverlay>,
+    /// HUD area to redraw with the next frame.
+    hud_damage: Option<DamageRect>,
+    ui_settings: UiSettings,
+    store: Option<ConfigStore>,
+    /// Clock time at which unsaved changes get written to `store`.
+    save_at: Option<Duration>,
+    ipc: Option<IpcServer>,
+    events: Receiver<RuntimeEvent>,
+    event_sender: Sender<RuntimeEvent>,
+    monitor_events: Receiver<MonitorEvent>,
+    shell_events: Receiver<ShellEvent>,
+    /// Set while the loop is parked waiting for input; tells forwarders to wake it.
+    parked: Arc<AtomicBool>,
+    running: bool,
+}
+
+impl Runtime {
+    pub fn new(
+        mut platform: Platform,
+        config: EngineConfig,
+        clock: impl FrameClock + 'static,
+    ) -> Self {
+        let (event_sender, events) = channel();
+        let parked = Arc::new(AtomicBool::new(false));
+        let waker = platform.event_loop.waker();
+        let input_events = platform.input.subscribe();
+        spawn_forwarder(
+            move || input_events.recv().ok(),
+            event_sender.clone(),
+            RuntimeEvent::Input,
+            Arc::clone(&parked),
+            waker,
+        );
+        if let Some(tray) = platform.tray.as_mut() {
+            let tray_actions = tray.subscribe();
+            spawn_forwarder(
+                move || tray_actions.recv().ok(),
+                event_sender.clone(),
+                RuntimeEvent::Tray,
+                Arc::clone(&parked),
+                platform.event_loop.waker(),
+            );
+        }
+        if let Some(hotkeys) = platform.hotkeys.as_mut() {
+            let pressed = hotkeys.subscribe();
+            spawn_forwarder(
+                move || pressed.recv().ok(),
+                event_sender.clone(),
+                RuntimeEvent::Hotkey,
+                Arc::clone(&parked),
+                platform.event_loop.waker(),
+            );
+        }
+        let monitor_events = platform.event_loop.overlays().subscribe();
+        let shell_events = platform.event_loop.subscribe_shell();
+
+        let mut scheduler = FrameScheduler::new(clock, DEFAULT_REFRESH_HZ);
+        scheduler.apply_power(&config.power);
+        let quality = QualityController::new(scheduler.frame_interval());
+        Self {
+            platform,
+            engine: TrailEngine::new(config),
+            scheduler,
+            quality,
+            presets: PresetLibrary::builtin(),
+            hotkey_actions: HashMap::new(),
+            paused: false,
+            ui_commands: None,
+            stats_frames: 0,
+            stats_sent_at: Duration::ZERO,
+            history: FrameHistory::default(),
+            hud: None,
+            hud_damage: None,
+            ui_settings: UiSettings::default(),
+            store: None,
+            save_at: None,
+            ipc: None,
+            events,
+            event_sender,
+            monitor_events,
+            shell_events,
+            parked,
+            running: true,
+        }
+    }
+
+    /// Connect the settings window: shell requests open it and its events reach the runtime.
+    pub fn with_ui(mut self, ui: UiHandles) -> Self {
+        let ui_events = ui.event_receiver;
+        spawn_forwarder(
+            move || ui_events.recv().ok(),
+            self.event_sender.clone(),
+            |event| RuntimeEvent::Ui(Box::new(event)),
+            Arc::clone(&self.parked),
+            self.platform.event_loop.waker(),
+        );
+        self.ui_commands = Some(ui.command_sender);
+        self.send_ui_config();
+        self
+    }
+
+    /// Presets offered in the tray instead of the built-in ones.
+    pub fn with_presets(mut self, presets: PresetLibrary) -> Self {
+        self.presets = presets;
+        self
+    }
+
+    /// Control panel preferences, sent to the panel on start.
+    pub fn with_ui_settings(mut self, settings: UiSettings) -> Self {
+        self.ui_settings = settings;
+        self
+    }
+
+    /// Save config, presets and panel settings to `store` shortly after they change and on shutdown.
+    pub fn with_store(mut self, store: ConfigStore) -> Self {
+        self.store = Some(store);
+        self
+    }
+
+    /// Answer calls from the control socket and push events to its subscribers.
+    pub fn with_ipc(
+        mut self,
+        server: IpcServer,
+        requests: std::sync::mpsc::Receiver<IpcRequest>,
+    ) -> Self {
+        spawn_forwarder(
+            move || requests.recv().ok(),
+            self.event_sender.clone(),
+            RuntimeEvent::Ipc,
+            Arc::clone(&self.parked),
+            self.platform.event_loop.waker(),
+        );
+        self.ipc = Some(server);
+        self
+    }
+
+    pub fn engine(&self) -> &TrailEngine {
+        &self.engine
+    }
+
+    pub fn scheduler(&self) -> &FrameScheduler {
+        &self.scheduler
+    }
+
+    pub fn quality(&self) -> &QualityController {
+        &self.quality
+    }
+
+    pub fn config(&self) -> &EngineConfig {
+        &self.engine.config
+    }
+
+    /// Swap in a new config; power settings take effect from the next frame. Hotkeys are only
+    /// re-registered when their bindings changed.
+    pub fn set_config(&mut self, config: EngineConfig) {
+        self.scheduler.apply_power(&config.power);
+        self.quality.set_budget(self.scheduler.frame_interval());
+        let hotkeys_changed = config.hotkeys != self.engine.config.hotkeys;
+        self.engine.config = config;
+        if !self.engine.config.show_hud {
+            self.set_hud(None);
+        }
+        self.config_changed();
+        if hotkeys_changed {
+            self.register_hotkeys();
+        }
+    }
+
+    pub fn presets(&self) -> &PresetLibrary {
+        &self.presets
+    }
+
+    pub fn ui_settings(&self) -> &UiSettings {
+        &self.ui_settings
+    }
+
+    /// What `with_store` persists.
+    pub fn user_config(&self) -> UserConfig {
+        UserConfig {
+            engine: self.engine.config.clone(),
+            ui: self.ui_settings.clone(),
+            presets: self.presets.presets().to_vec(),
+            ..UserConfig::default()
+        }
+    }
+
+    /// Changes are waiting for the save delay to pass.
+    pub fn has_unsaved_changes(&self) -> bool {
+        self.save_at.is_some()
+    }
+
+    /// Write pending changes to the store now.
+    pub fn save(&mut self) {
+        self.save_at = None;
+        let Some(store) = &self.store else {
+            return;
+        };
+        match store.save(&self.user_config()) {
+            Ok(()) => info!("saved config to {}", store.path().display()),
+            Err(err) => warn!("failed to save config: {err}"),
+        }
+    }
+
+    pub fn is_paused(&self) -> bool {
+        self.paused
+    }
+
+    pub fn is_running(&self) -> bool {
+        self.running
+    }
+
+    /// Stop after the current step, as if Quit had been picked from the tray.
+    pub fn request_quit(&mut self) {
+        self.running = false;
+    }
+
+    /// Create overlays, start input and initialise the renderer.
+    pub fn start(&mut self) -> Result<()> {
+        let overlays = self.platform.event_loop.overlays();
+        overlays.create_overlays()?;
+        let layout = DesktopLayout::from_manager(overlays)?;
+        // The snapshot already reflects everything published while the overlays were created.
+        while self.monitor_events.try_recv().is_ok() {}
+        info!("runtime started on {} displays", layout.monitors().len());
+        self.engine.set_layout(layout);
+        self.sync_refresh_rate();
+        self.platform.renderer.init()?;
+        self.platform.input.start()?;
+        self.refresh_tray();
+        self.send_ui_config();
+        self.send_ui(UiCommand::LoadUiSettings(self.ui_settings.clone()));
+        if self.ui_settings.start_hidden {
+            self.send_ui(UiCommand::Hide);
+        }
+        self.send_ui(UiCommand::MonitorsChanged(
+            self.engine.layout().monitors().to_vec(),
+        ));
+        self.register_hotkeys();
+        Ok(())
+    }
+
+    /// Pace frames for the fastest monitor, or the default rate when no backend reports one.
+    fn sync_refresh_rate(&mut self) {
+        let refresh_hz = self
+            .engine
+            .layout()
+            .monitors()
+            .iter()
+            .filter_map(|monitor| monitor.refresh_hz)
+            .reduce(f32::max)
+            .unwrap_or(DEFAULT_REFRESH_HZ);
+        self.scheduler.set_refresh_rate(refresh_hz);
+        self.quality.set_budget(self.scheduler.frame_interval());
+    }
+
+    /// Run one loop iteration: wait for OS events or the next frame, apply them, and render when a
+    /// frame is due. Returns false once quit was requested.
+    pub fn step(&mut self) -> Result<bool> {
+        self.wait()?;
+        self.handle_shell_events();
+        if !self.running {
+            return Ok(false);
+        }
+        let mut monitors_changed = false;
+        while let Ok(event) = self.monitor_events.try_recv() {
+            self.engine.handle_monitor_event(&event);
+            monitors_changed = true;
+        }
+        if monitors_changed {
+            self.sync_refresh_rate();
+            let monitors = self.engine.layout().monitors().to_vec();
+            self.broadcast(IpcEvent::MonitorsChanged(monitors.clone()));
+            self.send_ui(UiCommand::MonitorsChanged(monitors));
+        }
+        self.drain_events();
+        if !self.running {
+            return Ok(false);
+        }
+        if !self.scheduler.is_idle() && self.scheduler.time_until_next_frame().is_zero() {
+            self.frame();
+        }
+        self.send_stats_if_due();
+        if self
+            .save_at
+            .is_some_and(|at| self.scheduler.clock().now() >= at)
+        {
+            self.save();
+        }
+        // The loop is about to park, so a HUD change won't ride along with a frame.
+        if self.hud_damage.is_some() && self.engine.is_idle() {
+            if let Err(err) = self.render() {
+                warn!("render failed: {err}");
+            }
+        }
+        Ok(self.running)
+    }
+
+    /// Stop input and remove the overlays. Errors are logged so every step gets a chance to run.
+    pub fn shutdown(&mut self) {
+        if self.has_unsaved_changes() {
+            self.save();
+        }
+        self.broadcast(IpcEvent::Quitting);
+        self.ipc = None;
+        self.unregister_hotkeys();
+        if let Err(err) = self.platform.input.stop() {
+            warn!("failed to stop input: {err}");
+        }
+        if let Err(err) = self.platform.event_loop.overlays().destroy_overlays() {
+            warn!("failed to destroy overlays: {err}");
+        }
+    }
+
+    /// `start`, then `step` until quit, then `shutdown`.
+    pub fn run(&mut self) -> Result<()> {
+        self.start()?;
+        let result = loop {
+            match self.step() {
+                Ok(true) => {}
+                Ok(false) => break Ok(()),
+                Err(err) => break Err(err),
+            }
+        };
+        self.shutdown();
+        result
+    }
+
+    fn wait(&mut self) -> Result<()> {
+        match self.scheduler.next_wait(&self.engine) {
+            FrameWait::Timeout(timeout) => {
+                let timeout = self
+                    .time_until_save()
+                    .map_or(timeout, |save| save.min(timeout));
+                self.platform.event_loop.pump(Some(timeout))
+            }
+            FrameWait::Input => {
+                self.parked.store(true, Ordering::SeqCst);
+                // Events may have arrived between the last drain and parking.
+                if self.drain_events() {
+                    self.parked.store(false, Ordering::SeqCst);
+                    return Ok(());
+                }
+                let pumped = self.platform.event_loop.pump(self.time_until_save());
+                self.parked.store(false, Ordering::SeqCst);
+                pumped
+            }
+        }
+    }
+
+    fn time_until_save(&self) -> Option<Duration> {
+        self.save_at
+            .map(|at| at.saturating_sub(self.scheduler.clock().now()))
+    }
+
+    /// Schedule a save `SAVE_DELAY` from now, pushing back any pending one.
+    fn mark_dirty(&mut self) {
+        if self.store.is_some() {
+            self.save_at = Some(self.scheduler.clock().now() + SAVE_DELAY);
+        }
+    }
+
+    /// Apply queued input, UI and tray events. Returns true if there were any.
+    fn drain_events(&mut self) -> bool {
+        let mut saw_event = false;
+        let mut saw_input = false;
+        while let Ok(event) = self.events.try_recv() {
+            saw_event = true;
+            match event {
+                RuntimeEvent::Input(InputEvent::CursorMoved { .. }) if self.paused => {}
+                RuntimeEvent::Input(InputEvent::CursorMoved { x, y }) => {
+                    self.engine.set_cursor(Vec2::new(x, y));
+                    saw_input = true;
+                }
+                RuntimeEvent::Input(InputEvent::Button { .. }) => {}
+                RuntimeEvent::Ui(event) => self.handle_ui_event(*event),
+                RuntimeEvent::Tray(action) => self.handle_tray_action(action),
+                RuntimeEvent::Hotkey(id) => match self.hotkey_actions.get(&id) {
+                    Some(action) => self.handle_hotkey(*action),
+                    None => warn!("unknown hotkey id {id:?}"),
+                },
+                RuntimeEvent::Ipc(request) => self.handle_ipc_request(request),
+            }
+        }
+        if saw_input {
+            self.scheduler.wake();
+        }
+        saw_event
+    }
+
+    fn handle_ui_event(&mut self, event: UiEvent) {
+        info!("runtime: UI event {event:?}");
+        match event {
+            UiEvent::PresetSelected(name) => self.select_preset(&name),
+            UiEvent::ConfigEdited(config) => self.set_config(config),
+            UiEvent::PresetEdited(preset) => {
+                self.apply_edited_preset(preset);
+            }
+            UiEvent::PresetSaved(preset) => {
+                if self.apply_edited_preset(preset.clone()) {
+                    self.presets.insert(preset);
+                    self.send_ui_config();
+                }
+            }
+            UiEvent::LibraryEdited(edit) => self.edit_library(edit),
+            UiEvent::EnableToggled(enabled) => {
+                self.engine.config.enabled = enabled;
+                self.config_changed();
+            }
+            UiEvent::UiSettingsChanged(settings) => {
+                self.ui_settings = settings;
+                self.mark_dirty();
+            }
+            UiEvent::QuitRequested => {
+                info!("runtime: quit requested");
+                self.running = false;
+            }
+        }
+    }
+
+    fn handle_ipc_request(&mut self, request: IpcRequest) {
+        info!("runtime: IPC {}", request.method.name());
+        let result = match &request.method {
+            IpcMethod::Enable | IpcMethod::Disable => {
+                self.engine.config.enabled = request.method == IpcMethod::Enable;
+                self.config_changed();
+                Ok(self.status_json())
+            }
+            IpcMethod::SetPreset { name } => match self.presets.get(name) {
+                Some(_) => {
+                    self.select_preset(name);
+                    Ok(self.status_json())
+                }
+                None => Err(RpcError::new(
+                    RpcError::UNKNOWN_PRESET,
+                    format!("unknown preset '{name}'"),
+                )),
+            },
+            IpcMethod::GetStatus => Ok(self.status_json()),
+            IpcMethod::ListMonitors => {
+                Ok(serde_json::to_value(self.engine.layout().monitors()).unwrap_or_default())
+            }
+        };
+        request.respond(result);
+    }
+
+    /// What `get_status` reports over IPC.
+    pub fn status(&self) -> IpcStatus {
+        IpcStatus {
+            enabled: self.engine.config.enabled,
+            paused: self.paused,
+            preset: self.engine.config.preset.name.clone(),
+            presets: self.presets.names().map(String::from).collect(),
+            live_particles: self.engine.live_particles(),
+            monitors: self.engine.layout().monitors().len(),
+        }
+    }
+
+    fn status_json(&self) -> serde_json::Value {
+        serde_json::to_value(self.status()).unwrap_or_default()
+    }
+
+    fn broadcast(&self, event: IpcEvent) {
+        if let Some(ipc) = self.ipc.as_ref().filter(|ipc| ipc.has_subscribers()) {
+            ipc.broadcast(&event);
+        }
+    }
+
+    /// Make a preset from the editor active. The panel already shows it, so only the tray is told.
+    /// Returns false if the preset is invalid.
+    fn apply_edited_preset(&mut self, preset: TrailPreset) -> bool {
+        if let Err(problems) = preset.validate() {
+            for problem in problems {
+                warn!("rejected preset '{}': {problem}", preset.name);
+            }
+            return false;
+        }
+        self.engine.config.preset = preset;
+        self.refresh_tray();
+        self.mark_dirty();
+        true
+    }
+
+    /// Apply a browser edit, keeping the active preset in step with its library entry.
+    fn edit_library(&mut self, edit: LibraryEdit) {
+        let active = self.engine.config.preset.name.clone();
+        let renamed_to = match &edit {
+            LibraryEdit::Rename { from, to } if from.eq_ignore_ascii_case(&active) => {
+                Some(to.trim().to_string())
+            }
+            _ => None,
+        };
+        let mirrors_active = matches!(&edit,
+            LibraryEdit::SetFavorite { name, .. } | LibraryEdit::SetTags { name, .. }
+                if name.eq_ignore_ascii_case(&active));
+        if !self.presets.apply(edit) {
+            warn!("preset library edit rejected");
+            // Resend so the panel drops its optimistic view.
+            self.send_ui_config();
+            return;
+        }
+        if let Some(name) = renamed_to {
+            self.engine.config.preset.name = name;
+        }
+        if mirrors_active {
+            if let Some(preset) = self.presets.get(&active) {
+                self.engine.config.preset.favorite = preset.favorite;
+                self.engine.config.preset.tags = preset.tags.clone();
+            }
+        }
+        self.config_changed();
+    }
+
+    fn select_preset(&mut self, name: &str) {
+        match self.presets.get(name) {
+            Some(preset) => {
+                self.engine.config.preset = preset.clone();
+                self.config_changed();
+            }
+            None => warn!("unknown preset '{name}'"),
+        }
+    }
+
+    fn handle_tray_action(&mut self, action: TrayAction) {
+        info!("runtime: tray action {action:?}");
+        match action {
+            TrayAction::ToggleTrails => self.toggle_trails(),
+            TrayAction::SelectPreset(name) => self.select_preset(&name),
+            TrayAction::SetPowerMode(mode) => {
+                self.engine.config.power.low_power = mode == PowerMode::LowPower;
+                self.scheduler.apply_power(&self.engine.config.power);
+                self.quality.set_budget(self.scheduler.frame_interval());
+                self.config_changed();
+            }
+            TrayAction::OpenSettings => self.show_ui(),
+            TrayAction::Quit => {
+                info!("runtime: quit requested");
+                self.running = false;
+            }
+        }
+    }
+
+    fn handle_hotkey(&mut self, action: HotkeyAction) {
+        info!("runtime: hotkey {action}");
+        match action {
+            HotkeyAction::ToggleTrails => self.toggle_trails(),
+            HotkeyAction::NextPreset => self.cycle_preset(true),
+            HotkeyAction::PreviousPreset => self.cycle_preset(false),
+            HotkeyAction::Pause => {
+                self.paused = !self.paused;
+                self.broadcast(IpcEvent::StatusChanged(self.status()));
+            }
+            HotkeyAction::PanicOff => {
+                self.engine.config.enabled = false;
+                self.engine.clear();
+                // Render once more so the cleared particles are erased from the overlays.
+                self.scheduler.wake();
+                self.config_changed();
+            }
+            HotkeyAction::OpenSettings => self.show_ui(),
+        }
+    }
+
+    fn toggle_trails(&mut self) {
+        self.engine.config.enabled = !self.engine.config.enabled;
+        self.config_changed();
+    }
+
+    fn cycle_preset(&mut self, forward: bool) {
+        let Some(preset) = self.presets.cycle(&self.engine.config.preset.name, forward) else {
+            return;
+        };
+        self.engine.config.preset = preset.clone();
+        self.config_changed();
+    }
+
+    /// (Re-)register the configured hotkeys. Invalid or conflicting bindings are logged and skipped.
+    fn register_hotkeys(&mut self) {
+        self.unregister_hotkeys();
+        let Some(provider) = self.platform.hotkeys.as_mut() else {
+            return;
+        };
+        let config = &self.engine.config.hotkeys;
+        if !config.enabled {
+            return;
+        }
+        if let Err(problems) = config.validate() {
+            for problem in problems {
+                warn!("hotkey config: {problem}");
+            }
+        }
+        for (index, (action, chord)) in config.usable_bindings().into_iter().enumerate() {
+            let id = HotkeyId(index as u32 + 1);
+            match provider.register(id, &chord) {
+                Ok(()) => {
+                    self.hotkey_actions.insert(id, action);
+                }
+                Err(err) => warn!("failed to register {chord} for {action}: {err}"),
+            }
+        }
+        info!("registered {} hotkeys", self.hotkey_actions.len());
+    }
+
+    fn unregister_hotkeys(&mut self) {
+        let Some(provider) = self.platform.hotkeys.as_mut() else {
+            return;
+        };
+        for (id, action) in self.hotkey_actions.drain() {
+            if let Err(err) = provider.unregister(id) {
+                warn!("failed to unregister hotkey for {action}: {err}");
+            }
+        }
+    }
+
+    /// Tell the tray, the control panel and IPC subscribers about the current config, and
+    /// schedule a save.
+    fn config_changed(&mut self) {
+        self.refresh_tray();
+        self.send_ui_config();
+        self.mark_dirty();
+        self.broadcast(IpcEvent::StatusChanged(self.status()));
+    }
+
+    /// Push a menu reflecting the current config to the tray, if there is one.
+    fn refresh_tray(&mut self) {
+        let Some(tray) = self.platform.tray.as_mut() else {
+            return;
+        };
+        let menu = build_tray_menu(&self.engine.config, &self.presets);
+        if let Err(err) = tray.set_menu(&menu) {
+            warn!("failed to update tray menu: {err}");
+        }
+    }
+
+    fn handle_shell_events(&mut self) {
+        while let Ok(event) = self.shell_events.try_recv() {
+            match event {
+                ShellEvent::OpenSettings => self.show_ui(),
+                ShellEvent::Quit => {
+                    info!("runtime: quit requested");
+                    self.running = false;
+                }
+            }
+        }
+    }
+
+    fn show_ui(&self) {
+        self.send_ui(UiCommand::Show);
+    }
+
+    fn send_ui_config(&self) {
+        self.send_ui(UiCommand::LoadConfig {
+            config: Box::new(self.engine.config.clone()),
+            presets: self.presets.presets().to_vec(),
+        });
+    }
+
+    /// Report engine stats to the control panel and the HUD every `STATS_INTERVAL`.
+    fn send_stats_if_due(&mut self) {
+        let show_hud = self.engine.config.show_hud;
+        if self.ui_commands.is_none() && !show_hud {
+            return;
+        }
+        let now = self.scheduler.clock().now();
+        let elapsed = now.saturating_sub(self.stats_sent_at);
+        if elapsed < STATS_INTERVAL {
+            return;
+        }
+        let mut stats = EngineStats::from_engine(&self.engine);
+        stats.paused = self.paused;
+        stats.quality = self.quality.level();
+        stats.fps = self.stats_frames as f32 / elapsed.as_secs_f32();
+        stats.frame_cost_ms = self.quality.smoothed_cost().as_secs_f32() * 1000.0;
+        stats.renderer = self.platform.renderer.stats();
+        let stats = stats.with_history(&self.history);
+        self.stats_frames = 0;
+        self.stats_sent_at = now;
+        if show_hud {
+            let origin = self
+                .engine
+                .layout()
+                .monitors()
+                .first()
+                .map_or((0, 0), |monitor| (monitor.x, monitor.y));
+            self.set_hud(Some(HudOverlay {
+                x: origin.0 + HUD_MARGIN,
+                y: origin.1 + HUD_MARGIN,
+                lines: stats.hud_lines(),
+            }));
+        }
+        self.send_ui(UiCommand::UpdateStats(stats));
+    }
+
+    /// Hand a new HUD (or none) to the renderer; its area is redrawn with the next render.
+    fn set_hud(&mut self, hud: Option<HudOverlay>) {
+        if hud == self.hud {
+            return;
+        }
+        if let Err(err) = self.platform.renderer.set_hud(hud.as_ref()) {
+            warn!("failed to update HUD: {err}");
+            return;
+        }
+        let bounds = [self.hud.as_ref(), hud.as_ref()]
+            .into_iter()
+            .flatten()
+            .map(HudOverlay::bounds)
+            .reduce(|a, b| a.union(&b));
+        if let Some(bounds) = bounds {
+            self.hud_damage = Some(match self.hud_damage {
+                Some(pending) => pending.union(&bounds),
+                None => bounds,
+            });
+        }
+        self.hud = hud;
+    }
+
+    fn send_ui(&self, command: UiCommand) {
+        let Some(sender) = &self.ui_commands else {
+            return;
+        };
+        if sender.send(command).is_err() {
+            warn!("UI command channel closed");
+        }
+    }
+
+    fn frame(&mut self) {
+        self.scheduler.wait_for_next_frame();
+        let update_start = self.scheduler.clock().now();
+        self.scheduler.tick(&mut self.engine);
+        let render_start = self.scheduler.clock().now();
+        if let Err(err) = self.render() {
+            warn!("render failed: {err}");
+        }
+        let render_end = self.scheduler.clock().now();
+        self.stats_frames += 1;
+
+        let timings = FrameTimings {
+            update: render_start.saturating_sub(update_start),
+            render: render_end.saturating_sub(render_start),
+        };
+        self.history.record(timings);
+        if let Some(level) = self.quality.record(timings) {
+            info!("quality => {level}");
+            let settings = level.settings();
+            self.engine.set_quality(settings);
+            if let Err(err) = self
+                .platform
+                .renderer
+                .set_post_effects(settings.post_effects)
+            {
+                warn!("failed to toggle post effects: {err}");
+            }
+        }
+    }
+
+    fn render(&mut self) -> Result<()> {
+        let mut damage = self.engine.take_damage();
+        damage.extend(self.hud_damage.take());
+        if damage.is_empty() {
+            return Ok(());
+        }
+        let renderer = &mut self.platform.renderer;
+        renderer.set_damage(&damage)?;
+        renderer.set_particles(&self.engine.instances())?;
+        renderer.render_frame()
+    }
+}
+
+/// Forward events from `next` into the runtime channel on a helper thread, waking the event loop
+/// when the runtime is parked. Ends when `next` returns `None` or the runtime is gone.
+fn spawn_forwarder<T: 'static>(
+    mut next: impl FnMut() -> Option<T> + Send + 'static,
+    sender: Sender<RuntimeEvent>,
+    wrap: fn(T) -> RuntimeEvent,
+    parked: Arc<AtomicBool>,
+    waker: Waker,
+) {
+    std::thread::spawn(move || {
+        while let Some(event) = next() {
+            if sender.send(wrap(event)).is_err() {
+                break;
+            }
+            if parked.swap(false, Ordering::SeqCst) {
+                waker.wake();
+            }
+        }
+    });
+}
@@ -0,0 +1,43 @@
+//! Tray menu reflecting the current config.
+
+use serpentines_core::{EngineConfig, PresetLibrary};
+use serpentines_platform::{PowerMode, TrayAction, TrayItem, TrayMenu};
+
+pub fn build_tray_menu(config: &EngineConfig, library: &PresetLibrary) -> TrayMenu {
+    let presets = library
+        .names()
+        .map(|name| {
+            let selected = config.preset.name.eq_ignore_ascii_case(name);
+            TrayItem::check(name, selected, TrayAction::SelectPreset(name.to_string()))
+        })
+        .collect();
+    let low_power = config.power.low_power;
+    let modes = vec![
+        TrayItem::check(
+            "Normal",
+            !low_power,
+            TrayAction::SetPowerMode(PowerMode::Normal),
+        ),
+        TrayItem::check(
+            "Low Power",
+            low_power,
+            TrayAction::SetPowerMode(PowerMode::LowPower),
+        ),
+    ];
+    let tooltip = if config.enabled {
+        format!("Serpentines - {}", config.preset.name)
+    } else {
+        "Serpentines (trails off)".to_string()
+    };
+    TrayMenu {
+        tooltip,
+        items: vec![
+            TrayItem::check("Enable Trails", config.enabled, TrayAction::ToggleTrails),
+            TrayItem::submenu("Preset", presets),
+            TrayItem::submenu("Mode", modes),
+            TrayItem::Separator,
+            TrayItem::action("Open Settings", TrayAction::OpenSettings),
+            TrayItem::action("Exit", TrayAction::Quit),
+        ],
+    }
+}
@@ -0,0 +1,185 @@
+//! Drives the runtime against the mock platform and a manual clock.
+
+use std::time::Duration;
+
+use serpentines_app::Runtime;
+use serpentines_core::{EngineConfig, HotkeyAction};
+use serpentines_platform::mock::{self, MockCall, MockMethod, MockPlatform};
+use serpentines_platform::{ManualClock, MonitorRect, TrayAction};
+
+/// One 60 Hz frame; the mock event loop never blocks, so tests move the clock themselves.
+const FRAME: Duration = Duration::from_micros(16_667);
+
+struct Harness {
+    mock: MockPlatform,
+    clock: ManualClock,
+    runtime: Runtime,
+}
+
+impl Harness {
+    fn start(monitors: Vec<MonitorRect>) -> Self {
+        let mock = MockPlatform::with_monitors(monitors);
+        let clock = ManualClock::new();
+        let mut runtime = Runtime::new(mock.platform(), EngineConfig::default(), clock.clone());
+        runtime.start().unwrap();
+        Self {
+            mock,
+            clock,
+            runtime,
+        }
+    }
+
+    /// Step until `done` holds, a frame apart. Events reach the runtime through forwarder
+    /// threads, so this may take a few iterations.
+    fn step_until(&mut self, mut done: impl FnMut(&MockPlatform, &Runtime) -> bool) {
+        for _ in 0..500 {
+            if !self.step() {
+                panic!("runtime stopped");
+            }
+            if done(&self.mock, &self.runtime) {
+                return;
+            }
+        }
+        panic!("condition not reached");
+    }
+
+    /// One loop iteration. Returns false once the runtime quits.
+    fn step(&mut self) -> bool {
+        let running = self.runtime.step().unwrap();
+        self.clock.advance(FRAME);
+        std::thread::sleep(Duration::from_millis(1));
+        running
+    }
+}
+
+#[test]
+fn start_sets_up_the_platform() {
+    let Harness { mock, runtime, .. } = Harness::start(vec![mock::monitor("main", 0, 0)]);
+
+    assert!(mock.overlays.overlays_created());
+    assert!(mock.input.is_running());
+    assert_eq!(
+        runtime.engine().layout().monitors(),
+        [mock::monitor("main", 0, 0)]
+    );
+    assert!(mock.tray.menu().is_some());
+    assert_eq!(
+        mock.hotkeys.registered().len(),
+        runtime.config().hotkeys.usable_bindings().len()
+    );
+    let calls = mock.log.calls();
+    let position = |call: MockCall| calls.iter().position(|logged| *logged == call).unwrap();
+    assert!(position(MockCall::CreateOverlays) < position(MockCall::RendererInit));
+    assert!(position(MockCall::RendererInit) < position(MockCall::InputStart));
+}
+
+#[test]
+fn monitor_hot_plug_updates_the_layout() {
+    let mut harness = Harness::start(vec![mock::monitor("left", 0, 0)]);
+
+    harness
+        .mock
+        .overlays
+        .add_monitor(mock::monitor("right", 1920, 0));
+    harness.step_until(|_, runtime| runtime.engine().layout().monitors().len() == 2);
+    assert_eq!(harness.runtime.status().monitors, 2);
+
+    harness
+        .mock
+        .overlays
+        .remove_monitor(mock::monitor("left", 0, 0).id);
+    harness.step_until(|_, runtime| runtime.engine().layout().monitors().len() == 1);
+    assert_eq!(
+        harness.runtime.engine().layout().monitors(),
+        [mock::monitor("right", 1920, 0)]
+    );
+}
+
+#[test]
+fn frames_are_paced_for_the_fastest_monitor() {
+    let with_refresh = |key, x, refresh_hz| MonitorRect {
+        refresh_hz,
+        ..mock::monitor(key, x, 0)
+    };
+    let mut harness = Harness::start(vec![with_refresh("left", 0, None)]);
+    assert_eq!(
+        harness.runtime.scheduler().frame_interval(),
+        Duration::from_secs_f32(1.0 / 60.0)
+    );
+
+    harness
+        .mock
+        .overlays
+        .add_monitor(with_refresh("right", 1920, Some(144.0)));
+    harness.step_until(|_, runtime| {
+        runtime.scheduler().frame_interval() == Duration::from_secs_f32(1.0 / 144.0)
+    });
+    assert_eq!(
+        harness.runtime.quality().budget(),
+        harness.runtime.scheduler().frame_interval()
+    );
+}
+
+#[test]
+fn tray_and_hotkeys_dispatch_actions() {
+    let mut harness = Harness::start(vec![mock::monitor("main", 0, 0)]);
+    assert!(harness.runtime.config().enabled);
+
+    harness.mock.tray.click(TrayAction::ToggleTrails);
+    harness.step_until(|_, runtime| !runtime.config().enabled);
+
+    let toggle = harness.runtime.config().hotkeys.bindings[&HotkeyAction::ToggleTrails];
+    assert!(harness.mock.hotkeys.press(&toggle));
+    harness.step_until(|_, runtime| runtime.config().enabled);
+
+    let pause = harness.runtime.config().hotkeys.bindings[&HotkeyAction::Pause];
+    assert!(harness.mock.hotkeys.press(&pause));
+    harness.step_until(|_, runtime| runtime.is_paused());
+
+    harness.mock.tray.click(TrayAction::Quit);
+    assert!((0..500).any(|_| !harness.step()), "tray quit ignored");
+}
+
+#[test]
+fn hotkeys_are_reregistered_only_when_bindings_change() {
+    let Harness {
+        mock, mut runtime, ..
+    } = Harness::start(vec![mock::monitor("main", 0, 0)]);
+    let registrations = || {
+        mock.log
+            .count(|call| matches!(call, MockCall::RegisterHotkey { .. }))
+    };
+    let initial = registrations();
+
+    let mut config = runtime.config().clone();
+    config.show_hud = !config.show_hud;
+    runtime.set_config(config.clone());
+    assert_eq!(registrations(), initial);
+
+    config.hotkeys.bindings.remove(&HotkeyAction::Pause);
+    runtime.set_config(config);
+    assert_eq!(registrations(), initial * 2 - 1);
+    assert_eq!(mock.hotkeys.registered().len(), initial - 1);
+}
+
+#[test]
+fn render_errors_do_not_stop_the_loop() {
+    let mut harness = Harness::start(vec![mock::monitor("main", 0, 0)]);
+    harness
+        .mock
+        .log
+        .fail_next(MockMethod::RenderFrame, "device lost");
+    harness
+        .mock
+        .log
+        .fail_next(MockMethod::SetParticles, "out of memory");
+
+    let mut x = 100.0;
+    harness.step_until(|mock, _| {
+        x += 25.0;
+        mock.input.move_cursor(x, 300.0);
+        mock.renderer.frames_rendered() >= 3
+    });
+    assert!(harness.runtime.engine().live_particles() > 0);
+    assert!(harness.runtime.is_running());
+}
@@ -0,0 +1,429 @@
+//! Per-lifetime properties: multi-stop color gradients and keyframed float curves, both sampled
+//! over a particle's normalized age (0 at emission, 1 at death).
+
+use glam::Vec4;
+use serde::{Deserialize, Serialize};
+
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+pub struct GradientStop {
+    /// Position along the particle's life, 0..=1.
+    pub t: f32,
+    pub color: Vec4,
+}
+
+/// Color stops, kept sorted by `t`. Colors between stops are linearly interpolated; before the
+/// first and after the last stop the end colors hold. Serialized as the list of stops.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(from = "Vec<GradientStop>", into = "Vec<GradientStop>")]
+pub struct ColorGradient {
+    stops: Vec<GradientStop>,
+}
+
+impl ColorGradient {
+    /// Stops with a non-finite `t` are dropped; the rest are clamped to 0..=1.
+    pub fn new(stops: Vec<GradientStop>) -> Self {
+        let stops = stops
+            .into_iter()
+            .filter_map(|stop| {
+                Some(GradientStop {
+                    t: clamp_position(stop.t)?,
+                    ..stop
+                })
+            })
+            .collect();
+        let mut gradient = Self { stops };
+        gradient.sort();
+        gradient
+    }
+
+    /// `start` at birth fading linearly to `end` at death.
+    pub fn two_stop(start: Vec4, end: Vec4) -> Self {
+        Self::new(vec![
+            GradientStop {
+                t: 0.0,
+                color: start,
+            },
+            GradientStop { t: 1.0, color: end },
+        ])
+    }
+
+    pub fn stops(&self) -> &[GradientStop] {
+        &self.stops
+    }
+
+    pub fn sample(&self, t: f32) -> Vec4 {
+        let (Some(first), Some(last)) = (self.stops.first(), self.stops.last()) else {
+            return Vec4::ZERO;
+        };
+        if t <= first.t {
+            return first.color;
+        }
+        if t >= last.t {
+            return last.color;
+        }
+        let next = self.stops.partition_point(|stop| stop.t <= t);
+        // Only reachable with a NaN `t`; stop positions are always finite.
+        if next == 0 || next == self.stops.len() {
+            return first.color;
+        }
+        let (a, b) = (self.stops[next - 1], self.stops[next]);
+        let span = b.t - a.t;
+        if span <= f32::EPSILON {
+            return b.color;
+        }
+        a.color.lerp(b.color, (t - a.t) / span)
+    }
+
+    /// Add a stop and return its index. `t` is clamped to 0..=1; a non-finite `t` becomes 0.
+    pub fn insert(&mut self, mut stop: GradientStop) -> usize {
+        stop.t = clamp_position(stop.t).unwrap_or(0.0);
+        let index = self.stops.partition_point(|existing| existing.t <= stop.t);
+        self.stops.insert(index, stop);
+        index
+    }
+
+    /// Remove the stop at `index`. The last remaining stop can't be removed.
+    pub fn remove(&mut self, index: usize) -> Option<GradientStop> {
+        (self.stops.len() > 1 && index < self.stops.len()).then(|| self.stops.remove(index))
+    }
+
+    /// Move the stop at `index` to `t`, returning its new index.
+    pub fn move_stop(&mut self, index: usize, t: f32) -> usize {
+        let mut stop = self.stops.remove(index);
+        stop.t = t;
+        self.insert(stop)
+    }
+
+    pub fn set_color(&mut self, index: usize, color: Vec4) {
+        if let Some(stop) = self.stops.get_mut(index) {
+            stop.color = color;
+        }
+    }
+
+    fn sort(&mut self) {
+        self.stops.sort_by(|a, b| a.t.total_cmp(&b.t));
+    }
+}
+
+/// A key on a `FloatCurve`. Tangents are slopes (value per unit of `t`).
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+pub struct CurveKey {
+    pub t: f32,
+    pub value: f32,
+    #[serde(default)]
+    pub in_tangent: f32,
+    #[serde(default)]
+    pub out_tangent: f32,
+}
+
+impl CurveKey {
+    pub fn new(t: f32, value: f32) -> Self {
+        Self {
+            t,
+            value,
+            in_tangent: 0.0,
+            out_tangent: 0.0,
+        }
+    }
+}
+
+/// Keys kept sorted by `t`, joined by cubic Hermite segments. Flat before the first and after
+/// the last key. Serialized as the list of keys.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(from = "Vec<CurveKey>", into = "Vec<CurveKey>")]
+pub struct FloatCurve {
+    keys: Vec<CurveKey>,
+}
+
+impl FloatCurve {
+    /// Keys with a non-finite `t` are dropped; the rest are clamped to 0..=1.
+    pub fn new(keys: Vec<CurveKey>) -> Self {
+        let keys = keys
+            .into_iter()
+            .filter_map(|key| {
+                Some(CurveKey {
+                    t: clamp_position(key.t)?,
+                    ..key
+                })
+            })
+            .collect();
+        let mut curve = Self { keys };
+        curve.sort();
+        curve
+    }
+
+    pub fn constant(value: f32) -> Self {
+        Self::new(vec![CurveKey::new(0.0, value)])
+    }
+
+    /// Straight line from `start` at 0 to `end` at 1.
+    pub fn linear(start: f32, end: f32) -> Self {
+        let slope = end - start;
+        Self::new(vec![
+            CurveKey {
+                t: 0.0,
+                value: start,
+                in_tangent: slope,
+                out_tangent: slope,
+            },
+            CurveKey {
+                t: 1.0,
+                value: end,
+                in_tangent: slope,
+                out_tangent: slope,
+            },
+        ])
+    }
+
+    pub fn keys(&self) -> &[CurveKey] {
+        &self.keys
+    }
+
+    pub fn sample(&self, t: f32) -> f32 {
+        let (Some(first), Some(last)) = (self.keys.first(), self.keys.last()) else {
+            return 0.0;
+        };
+        if t <= first.t {
+            return first.value;
+        }
+        if t >= last.t {
+            return last.value;
+        }
+        let next = self.keys.partition_point(|key| key.t <= t);
+        // Only reachable with a NaN `t`; key positions are always finite.
+        if next == 0 || next == self.keys.len() {
+            return first.value;
+        }
+        let (a, b) = (self.keys[next - 1], self.keys[next]);
+        let span = b.t - a.t;
+        if span <= f32::EPSILON {
+            return b.value;
+        }
+        let s = (t - a.t) / span;
+        let (s2, s3) = (s * s, s * s * s);
+        let h00 = 2.0 * s3 - 3.0 * s2 + 1.0;
+        let h10 = s3 - 2.0 * s2 + s;
+        let h01 = -2.0 * s3 + 3.0 * s2;
+        let h11 = s3 - s2;
+        h00 * a.value + h10 * span * a.out_tangent + h01 * b.value + h11 * span * b.in_tangent
+    }
+
+    /// Largest value the curve reaches, checked at the keys and at a fine sampling in between.
+    pub fn max_value(&self) -> f32 {
+        const SAMPLES: usize = 64;
+        let sampled = (0..=SAMPLES).map(|i| self.sample(i as f32 / SAMPLES as f32));
+        self.keys
+            .iter()
+            .map(|key| key.value)
+            .chain(sampled)
+            .fold(f32::MIN, f32::max)
+    }
+
+    /// Add a key and return its index. `t` is clamped to 0..=1; a non-finite `t` becomes 0.
+    pub fn insert(&mut self, mut key: CurveKey) -> usize {
+        key.t = clamp_position(key.t).unwrap_or(0.0);
+        let index = self.keys.partition_point(|existing| existing.t <= key.t);
+        self.keys.insert(index, key);
+        index
+    }
+
+    /// Remove the key at `index`. The last remaining key can't be removed.
+    pub fn remove(&mut self, index: usize) -> Option<CurveKey> {
+        (self.keys.len() > 1 && index < self.keys.len()).then(|| self.keys.remove(index))
+    }
+
+    /// Move the key at `index` to (`t`, `value`), returning its new index.
+    pub fn move_key(&mut self, index: usize, t: f32, value: f32) -> usize {
+        let mut key = self.keys.remove(index);
+        key.t = t;
+        key.value = value;
+        self.insert(key)
+    }
+
+    pub fn set_tangents(&mut self, index: usize, in_tangent: f32, out_tangent: f32) {
+        if let Some(key) = self.keys.get_mut(index) {
+            key.in_tangent = in_tangent;
+            key.out_tangent = out_tangent;
+        }
+    }
+
+    fn sort(&mut self) {
+        self.keys.sort_by(|a, b| a.t.total_cmp(&b.t));
+    }
+}
+
+/// `t` clamped to a particle's life, or `None` if it isn't finite.
+fn clamp_position(t: f32) -> Option<f32> {
+    t.is_finite().then(|| t.clamp(0.0, 1.0))
+}
+
+impl From<Vec<GradientStop>> for ColorGradient {
+    fn from(stops: Vec<GradientStop>) -> Self {
+        Self::new(stops)
+    }
+}
+
+impl From<ColorGradient> for Vec<GradientStop> {
+    fn from(gradient: ColorGradient) -> Self {
+        gradient.stops
+    }
+}
+
+impl From<Vec<CurveKey>> for FloatCurve {
+    fn from(keys: Vec<CurveKey>) -> Self {
+        Self::new(keys)
+    }
+}
+
+impl From<FloatCurve> for Vec<CurveKey> {
+    fn from(curve: FloatCurve) -> Self {
+        curve.keys
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{PresetPack, TrailPreset};
+
+    fn stop(t: f32, value: f32) -> GradientStop {
+        GradientStop {
+            t,
+            color: Vec4::splat(value),
+        }
+    }
+
+    fn stop_positions(gradient: &ColorGradient) -> Vec<f32> {
+        gradient.stops().iter().map(|stop| stop.t).collect()
+    }
+
+    #[test]
+    fn gradient_sampling_interpolates_and_holds_the_ends() {
+        let gradient = ColorGradient::new(vec![stop(0.8, 1.0), stop(0.2, 0.0), stop(0.5, 0.6)]);
+        assert_eq!(stop_positions(&gradient), [0.2, 0.5, 0.8]);
+        assert_eq!(gradient.sample(0.0), Vec4::splat(0.0));
+        assert!(
+            (gradient.sample(0.35) - Vec4::splat(0.3))
+                .abs()
+                .max_element()
+                < 1e-6
+        );
+        assert_eq!(gradient.sample(0.5), Vec4::splat(0.6));
+        assert!(
+            (gradient.sample(0.65) - Vec4::splat(0.8))
+                .abs()
+                .max_element()
+                < 1e-6
+        );
+        assert_eq!(gradient.sample(1.0), Vec4::splat(1.0));
+        assert_eq!(gradient.sample(f32::NAN), Vec4::splat(0.0));
+        assert_eq!(ColorGradient::new(Vec::new()).sample(0.5), Vec4::ZERO);
+    }
+
+    #[test]
+    fn gradient_sanitizes_positions() {
+        let gradient = ColorGradient::new(vec![
+            stop(f32::NAN, 0.5),
+            stop(-1.0, 0.0),
+            stop(f32::INFINITY, 0.5),
+            stop(2.0, 1.0),
+        ]);
+        assert_eq!(stop_positions(&gradient), [0.0, 1.0]);
+        assert_eq!(gradient.sample(0.5), Vec4::splat(0.5));
+    }
+
+    #[test]
+    fn gradient_edits_keep_stops_sorted() {
+        let mut gradient = ColorGradient::two_stop(Vec4::ZERO, Vec4::ONE);
+        assert_eq!(gradient.insert(stop(0.5, 0.2)), 1);
+        assert_eq!(gradient.insert(stop(f32::NAN, 0.2)), 1);
+        assert_eq!(stop_positions(&gradient), [0.0, 0.0, 0.5, 1.0]);
+        assert_eq!(gradient.move_stop(2, 1.5), 3);
+        assert_eq!(stop_positions(&gradient), [0.0, 0.0, 1.0, 1.0]);
+        assert_eq!(gradient.move_stop(3, 0.25), 2);
+        gradient.set_color(2, Vec4::splat(0.7));
+        assert_eq!(gradient.stops()[2], stop(0.25, 0.7));
+
+        assert!(gradient.remove(9).is_none());
+        while gradient.stops().len() > 1 {
+            assert!(gradient.remove(0).is_some());
+        }
+        assert!(gradient.remove(0).is_none(), "the last stop stays");
+    }
+
+    #[test]
+    fn curve_sampling_follows_hermite_segments() {
+        let linear = FloatCurve::linear(1.0, 3.0);
+        for t in [0.0, 0.25, 0.5, 0.9, 1.0] {
+            assert!((linear.sample(t) - (1.0 + 2.0 * t)).abs() < 1e-5, "t = {t}");
+        }
+        let eased = FloatCurve::new(vec![CurveKey::new(0.0, 0.0), CurveKey::new(1.0, 1.0)]);
+        assert_eq!(eased.sample(0.5), 0.5);
+        assert!(eased.sample(0.1) < 0.1, "flat tangents ease in");
+        assert_eq!(eased.sample(-1.0), 0.0);
+        assert_eq!(eased.sample(2.0), 1.0);
+        assert_eq!(eased.sample(f32::NAN), 0.0);
+        assert_eq!(FloatCurve::constant(2.5).sample(0.7), 2.5);
+        assert_eq!(FloatCurve::new(Vec::new()).sample(0.5), 0.0);
+        assert!((FloatCurve::linear(0.5, 2.0).max_value() - 2.0).abs() < 1e-6);
+    }
+
+    #[test]
+    fn curve_edits_keep_keys_sorted() {
+        let mut curve = FloatCurve::linear(0.0, 1.0);
+        assert_eq!(curve.insert(CurveKey::new(0.5, 2.0)), 1);
+        // A key moved onto another's position lands after it.
+        assert_eq!(curve.move_key(1, -0.5, 3.0), 1);
+        assert_eq!(curve.keys()[1], CurveKey::new(0.0, 3.0));
+        curve.set_tangents(1, 1.0, -1.0);
+        assert_eq!(
+            (curve.keys()[1].in_tangent, curve.keys()[1].out_tangent),
+            (1.0, -1.0)
+        );
+        assert_eq!(curve.remove(0).map(|key| key.value), Some(0.0));
+        assert_eq!(curve.remove(0).map(|key| key.value), Some(3.0));
+        assert!(curve.remove(0).is_none(), "the last key stays");
+    }
+
+    #[test]
+    fn serde_round_trips_as_lists() {
+        let preset = TrailPreset {
+            color_over_life: Some(ColorGradient::new(vec![
+                stop(0.0, 1.0),
+                stop(0.4, 0.5),
+                stop(1.0, 0.0),
+            ])),
+            size_over_life: Some(FloatCurve::linear(1.0, 0.25)),
+            ..TrailPreset::default()
+        };
+        let text = toml::to_string(&preset).unwrap();
+        assert!(text.contains("[[color_over_life]]"), "{text}");
+        assert_eq!(toml::from_str::<TrailPreset>(&text).unwrap(), preset);
+        let json = serde_json::to_string(&preset).unwrap();
+        assert_eq!(serde_json::from_str::<TrailPreset>(&json).unwrap(), preset);
+    }
+
+    #[test]
+    fn imported_nan_positions_are_dropped() {
+        let pack = PresetPack::from_toml(
+            r#"
+            name = "Shared"
+
+            [[preset]]
+            name = "Broken"
+            color_over_life = [
+                { t = -nan, color = [1.0, 0.0, 0.0, 1.0] },
+                { t = 1.0, color = [0.0, 0.0, 1.0, 1.0] },
+            ]
+            size_over_life = [{ t = nan, value = 1.0 }, { t = 0.0, value = 2.0 }]
+            "#,
+        )
+        .unwrap();
+        let preset = &pack.presets[0];
+        let gradient = preset.color_over_life.as_ref().unwrap();
+        assert_eq!(stop_positions(gradient), [1.0]);
+        assert_eq!(gradient.sample(0.5), Vec4::new(0.0, 0.0, 1.0, 1.0));
+        assert_eq!(preset.size_over_life.as_ref().unwrap().sample(0.5), 2.0);
+        assert_eq!(preset.validate(), Ok(()));
+    }
+}
@@ -0,0 +1,384 @@
+//! Key bindings for quick toggles.
+
+use std::collections::BTreeMap;
+use std::fmt;
+
+use serde::de::IntoDeserializer;
+use serde::{Deserialize, Serialize};
+use serpentines_platform::{ChordError, KeyChord};
+use tracing::warn;
+
+/// What a hotkey does.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, PartialOrd, Ord, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum HotkeyAction {
+    ToggleTrails,
+    NextPreset,
+    PreviousPreset,
+    /// Stop emitting without changing the config; existing particles fade out.
+    Pause,
+    /// Turn trails off and clear every particle immediately.
+    PanicOff,
+    OpenSettings,
+}
+
+impl HotkeyAction {
+    pub const ALL: [HotkeyAction; 6] = [
+        HotkeyAction::ToggleTrails,
+        HotkeyAction::NextPreset,
+        HotkeyAction::PreviousPreset,
+        HotkeyAction::Pause,
+        HotkeyAction::PanicOff,
+        HotkeyAction::OpenSettings,
+    ];
+
+    pub fn label(self) -> &'static str {
+        match self {
+            HotkeyAction::ToggleTrails => "Toggle trails",
+            HotkeyAction::NextPreset => "Next preset",
+            HotkeyAction::PreviousPreset => "Previous preset",
+            HotkeyAction::Pause => "Pause",
+            HotkeyAction::PanicOff => "Panic off",
+            HotkeyAction::OpenSettings => "Open settings",
+        }
+    }
+}
+
+impl fmt::Display for HotkeyAction {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(self.label())
+    }
+}
+
+/// A binding that can't be registered as configured.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum HotkeyProblem {
+    Invalid {
+        action: HotkeyAction,
+        error: ChordError,
+    },
+    /// The same chord is bound to several actions.
+    Conflict {
+        chord: KeyChord,
+        actions: Vec<HotkeyAction>,
+    },
+    /// A config file entry naming an unknown action or an unparseable chord.
+    Unparsed { name: String, chord: String },
+}
+
+impl fmt::Display for HotkeyProblem {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            HotkeyProblem::Invalid { action, error } => write!(f, "{action}: {error}"),
+            HotkeyProblem::Conflict { chord, actions } => {
+                let names: Vec<&str> = actions.iter().map(|action| action.label()).collect();
+                write!(f, "{chord} is bound to {}", names.join(", "))
+            }
+            HotkeyProblem::Unparsed { name, chord } => {
+                write!(
+                    f,
+                    "{name} = \"{chord}\" is not a valid binding and is ignored"
+                )
+            }
+        }
+    }
+}
+
+/// Bindings are read leniently: an entry with an unknown action or a chord that doesn't parse is
+/// logged, kept in `unparsed` so saving writes it back unchanged, and never registered. One typo
+/// must not fail the whole config file.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(from = "RawHotkeyConfig", into = "RawHotkeyConfig")]
+pub struct HotkeyConfig {
+    pub enabled: bool,
+    pub bindings: BTreeMap<HotkeyAction, KeyChord>,
+    /// Config file entries (`name = "chord"`) that didn't parse.
+    pub unparsed: BTreeMap<String, String>,
+}
+
+/// On-disk form of `HotkeyConfig`, with bindings as plain strings.
+#[derive(Serialize, Deserialize)]
+#[serde(default)]
+struct RawHotkeyConfig {
+    enabled: bool,
+    bindings: BTreeMap<String, String>,
+}
+
+impl Default for RawHotkeyConfig {
+    fn default() -> Self {
+        HotkeyConfig::default().into()
+    }
+}
+
+impl From<RawHotkeyConfig> for HotkeyConfig {
+    fn from(raw: RawHotkeyConfig) -> Self {
+        let mut bindings = BTreeMap::new();
+        let mut unparsed = BTreeMap::new();
+        for (name, chord) in raw.bindings {
+            match parse_binding(&name, &chord) {
+                Ok((action, chord)) => {
+                    bindings.insert(action, chord);
+                }
+                Err(err) => {
+                    warn!("ignoring hotkey binding {name} = \"{chord}\": {err}");
+                    unparsed.insert(name, chord);
+                }
+            }
+        }
+        Self {
+            enabled: raw.enabled,
+            bindings,
+            unparsed,
+        }
+    }
+}
+
+fn parse_binding(name: &str, chord: &str) -> Result<(HotkeyAction, KeyChord), String> {
+    let action = HotkeyAction::deserialize(name.into_deserializer())
+        .map_err(|err: serde::de::value::Error| err.to_string())?;
+    let chord = chord.parse().map_err(|err: ChordError| err.to_string())?;
+    Ok((action, chord))
+}
+
+/// The action's config file key, e.g. `toggle_trails`.
+fn action_name(action: HotkeyAction) -> String {
+    match serde_json::to_value(action) {
+        Ok(serde_json::Value::String(name)) => name,
+        _ => action.label().to_string(),
+    }
+}
+
+impl From<HotkeyConfig> for RawHotkeyConfig {
+    fn from(config: HotkeyConfig) -> Self {
+        let mut bindings: BTreeMap<String, String> = config.unparsed;
+        for (action, chord) in config.bindings {
+            bindings.insert(action_name(action), chord.to_string());
+        }
+        Self {
+            enabled: config.enabled,
+            bindings,
+        }
+    }
+}
+
+impl Default for HotkeyConfig {
+    fn default() -> Self {
+        let defaults = [
+            (HotkeyAction::ToggleTrails, "Ctrl+Alt+T"),
+            (HotkeyAction::NextPreset, "Ctrl+Alt+Right"),
+            (HotkeyAction::PreviousPreset, "Ctrl+Alt+Left"),
+            (HotkeyAction::Pause, "Ctrl+Alt+P"),
+            (HotkeyAction::PanicOff, "Ctrl+Alt+Shift+X"),
+            (HotkeyAction::OpenSettings, "Ctrl+Alt+S"),
+        ];
+        Self {
+            enabled: true,
+            bindings: defaults
+                .into_iter()
+                .filter_map(|(action, chord)| Some((action, chord.parse().ok()?)))
+                .collect(),
+            unparsed: BTreeMap::new(),
+        }
+    }
+}
+
+impl HotkeyConfig {
+    /// Actions sharing a chord, each group in action order.
+    pub fn conflicts(&self) -> Vec<HotkeyProblem> {
+        let mut by_chord: BTreeMap<KeyChord, Vec<HotkeyAction>> = BTreeMap::new();
+        for (action, chord) in &self.bindings {
+            by_chord.entry(*chord).or_default().push(*action);
+        }
+        by_chord
+            .into_iter()
+            .filter(|(_, actions)| actions.len() > 1)
+            .map(|(chord, actions)| HotkeyProblem::Conflict { chord, actions })
+            .collect()
+    }
+
+    /// Every invalid chord and every conflict.
+    pub fn validate(&self) -> Result<(), Vec<HotkeyProblem>> {
+        let mut problems: Vec<HotkeyProblem> = self
+            .bindings
+            .iter()
+            .filter_map(|(action, chord)| {
+                let error = chord.validate().err()?;
+                Some(HotkeyProblem::Invalid {
+                    action: *action,
+                    error,
+                })
+            })
+            .collect();
+        problems.extend(self.conflicts());
+        problems.extend(
+            self.unparsed
+                .iter()
+                .map(|(name, chord)| HotkeyProblem::Unparsed {
+                    name: name.clone(),
+                    chord: chord.clone(),
+                }),
+        );
+        if problems.is_empty() {
+            Ok(())
+        } else {
+            Err(problems)
+        }
+    }
+
+    /// Bindings that can be registered: valid chords, and for a conflicting chord only the first
+    /// action in action order.
+    pub fn usable_bindings(&self) -> Vec<(HotkeyAction, KeyChord)> {
+        let mut taken = Vec::new();
+        let mut usable = Vec::new();
+        for (action, chord) in &self.bindings {
+            if chord.validate().is_err() || taken.contains(chord) {
+                continue;
+            }
+            taken.push(*chord);
+            usable.push((*action, *chord));
+        }
+        usable
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn config(bindings: &[(HotkeyAction, &str)]) -> HotkeyConfig {
+        HotkeyConfig {
+            enabled: true,
+            bindings: bindings
+                .iter()
+                .map(|(action, chord)| (*action, chord.parse().unwrap()))
+                .collect(),
+            unparsed: BTreeMap::new(),
+        }
+    }
+
+    #[test]
+    fn defaults_are_valid_and_all_usable() {
+        let config = HotkeyConfig::default();
+        assert_eq!(config.validate(), Ok(()));
+        assert_eq!(config.bindings.len(), HotkeyAction::ALL.len());
+        assert_eq!(config.usable_bindings().len(), HotkeyAction::ALL.len());
+    }
+
+    #[test]
+    fn bindings_round_trip_through_toml_as_canonical_strings() {
+        let config: HotkeyConfig =
+            toml::from_str("[bindings]\npause = \"shift+ctrl+p\"\n").unwrap();
+        assert!(config.enabled);
+        let text = toml::to_string(&config).unwrap();
+        assert!(text.contains("pause = \"Ctrl+Shift+P\""), "{text}");
+        assert_eq!(toml::from_str::<HotkeyConfig>(&text).unwrap(), config);
+    }
+
+    #[test]
+    fn unparseable_bindings_are_kept_but_not_used() {
+        let text = "[bindings]\npause = \"Ctrl+Ctrl+P\"\nopen_settings = \"Ctrl+Alt+S\"\nfly = \"Ctrl+F\"\n";
+        let config: HotkeyConfig = toml::from_str(text).unwrap();
+        assert_eq!(config.bindings.len(), 1);
+        assert_eq!(config.usable_bindings().len(), 1);
+        assert_eq!(config.unparsed.len(), 2);
+        assert_eq!(
+            config.validate().unwrap_err(),
+            vec![
+                HotkeyProblem::Unparsed {
+                    name: "fly".into(),
+                    chord: "Ctrl+F".into(),
+                },
+                HotkeyProblem::Unparsed {
+                    name: "pause".into(),
+                    chord: "Ctrl+Ctrl+P".into(),
+                },
+            ]
+        );
+        let saved = toml::to_string(&config).unwrap();
+        assert!(saved.contains("pause = \"Ctrl+Ctrl+P\""), "{saved}");
+        assert_eq!(toml::from_str::<HotkeyConfig>(&saved).unwrap(), config);
+
+        // Fixing the binding replaces the bad entry.
+        let mut fixed = config.clone();
+        fixed
+            .bindings
+            .insert(HotkeyAction::Pause, "Ctrl+Alt+P".parse().unwrap());
+        let saved = toml::to_string(&fixed).unwrap();
+        assert!(saved.contains("pause = \"Ctrl+Alt+P\""), "{saved}");
+    }
+
+    #[test]
+    fn conflicts_group_actions_sharing_a_chord() {
+        let config = config(&[
+            (HotkeyAction::OpenSettings, "Ctrl+Alt+T"),
+            (HotkeyAction::ToggleTrails, "ctrl+alt+t"),
+            (HotkeyAction::Pause, "Ctrl+Alt+P"),
+            (HotkeyAction::NextPreset, "Ctrl+Alt+P"),
+            (HotkeyAction::PanicOff, "Ctrl+Alt+X"),
+        ]);
+        assert_eq!(
+            config.conflicts(),
+            vec![
+                HotkeyProblem::Conflict {
+                    chord: "Ctrl+Alt+P".parse().unwrap(),
+                    actions: vec![HotkeyAction::NextPreset, HotkeyAction::Pause],
+                },
+                HotkeyProblem::Conflict {
+                    chord: "Ctrl+Alt+T".parse().unwrap(),
+                    actions: vec![HotkeyAction::ToggleTrails, HotkeyAction::OpenSettings],
+                },
+            ]
+        );
+        assert_eq!(config.validate().unwrap_err().len(), 2);
+    }
+
+    #[test]
+    fn validate_reports_invalid_chords() {
+        let config = config(&[
+            (HotkeyAction::ToggleTrails, "T"),
+            (HotkeyAction::PanicOff, "Ctrl+Alt+Delete"),
+            (HotkeyAction::Pause, "F9"),
+        ]);
+        let problems = config.validate().unwrap_err();
+        assert_eq!(
+            problems,
+            vec![
+                HotkeyProblem::Invalid {
+                    action: HotkeyAction::ToggleTrails,
+                    error: ChordError::NoModifier,
+                },
+                HotkeyProblem::Invalid {
+                    action: HotkeyAction::PanicOff,
+                    error: ChordError::Reserved("Ctrl+Alt+Delete".parse().unwrap()),
+                },
+            ]
+        );
+        assert_eq!(
+            problems[0].to_string(),
+            "Toggle trails: global hotkeys need at least one modifier"
+        );
+    }
+
+    #[test]
+    fn usable_bindings_skip_invalid_chords_and_later_conflicts() {
+        let config = config(&[
+            (HotkeyAction::ToggleTrails, "Ctrl+Alt+T"),
+            (HotkeyAction::NextPreset, "Ctrl+Alt+T"),
+            (HotkeyAction::PreviousPreset, "Left"),
+            (HotkeyAction::Pause, "F9"),
+            (HotkeyAction::OpenSettings, "Ctrl+Alt+T"),
+        ]);
+        let usable: Vec<(HotkeyAction, String)> = config
+            .usable_bindings()
+            .into_iter()
+            .map(|(action, chord)| (action, chord.to_string()))
+            .collect();
+        assert_eq!(
+            usable,
+            vec![
+                (HotkeyAction::ToggleTrails, "Ctrl+Alt+T".to_string()),
+                (HotkeyAction::Pause, "F9".to_string()),
+            ]
+        );
+    }
+}
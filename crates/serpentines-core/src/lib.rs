@@ -1,7 +1,29 @@
 //! Serpentines core engine: platform-agnostic logic for trails, particles, and presets.
 
+use std::collections::BTreeMap;
+
 use glam::{Vec2, Vec4};
 use serde::{Deserialize, Serialize};
+use serpentines_platform::{
+    scale_factor, DamageRect, DesktopLayout, MonitorEvent, MonitorId, ParticleInstance,
+};
+
+pub mod curves;
+pub mod hotkeys;
+pub mod pack;
+pub mod presets;
+pub mod quality;
+pub mod scheduler;
+pub mod stats;
+pub mod thumbnail;
+pub use curves::{ColorGradient, CurveKey, FloatCurve, GradientStop};
+pub use hotkeys::{HotkeyAction, HotkeyConfig, HotkeyProblem};
+pub use pack::{PackError, PresetPack};
+pub use presets::{LibraryEdit, PresetFilter, PresetLibrary, PresetProblem};
+pub use quality::{FrameTimings, QualityController, QualityLevel, QualitySettings};
+pub use scheduler::{FrameScheduler, FrameTick, FrameWait};
+pub use stats::{EngineStats, FrameHistory, FrameTimePercentiles};
+pub use thumbnail::{render_thumbnail, Image, PresetAnimation};
 
 #[derive(Debug, Clone, Copy, Serialize, Deserialize)]
 pub struct Particle {
@@ -9,9 +31,25 @@ pub struct Particle {
     pub vel: Vec2,
     pub age: f32,
     pub lifetime: f32,
+    /// Diameter in physical pixels, scaled for the DPI of the monitor it was emitted on.
+    pub size: f32,
+    /// `size` at emission, before the preset's size-over-life curve.
+    pub base_size: f32,
 }
 
-#[derive(Debug, Clone, Serialize, Deserialize)]
+impl Particle {
+    /// Normalized age: 0 at emission, 1 at death.
+    pub fn life(&self) -> f32 {
+        if self.lifetime > 0.0 {
+            (self.age / self.lifetime).clamp(0.0, 1.0)
+        } else {
+            1.0
+        }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(default)]
 pub struct TrailPreset {
     pub name: String,
     pub max_particles: u32,
@@ -19,6 +57,17 @@ pub struct TrailPreset {
     pub decay_seconds: f32,
     pub color_start: Vec4,
     pub color_end: Vec4,
+    /// Particle diameter in logical pixels.
+    pub particle_size: f32,
+    /// Color over the particle's life; replaces the start/end fade when set.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub color_over_life: Option<ColorGradient>,
+    /// Multiplier on `particle_size` over the particle's life.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub size_over_life: Option<FloatCurve>,
+    /// Free-form labels for filtering in the preset browser.
+    pub tags: Vec<String>,
+    pub favorite: bool,
 }
 
 impl Default for TrailPreset {
@@ -30,34 +79,657 @@ impl Default for TrailPreset {
             decay_seconds: 0.6,
             color_start: Vec4::new(1.0, 1.0, 1.0, 1.0),
             color_end: Vec4::new(1.0, 1.0, 1.0, 0.0),
+            particle_size: 6.0,
+            color_over_life: None,
+            size_over_life: None,
+            tags: vec!["classic".into()],
+            favorite: false,
+        }
+    }
+}
+
+impl TrailPreset {
+    /// Color at normalized age `life` (0..=1).
+    pub fn color_at(&self, life: f32) -> Vec4 {
+        match &self.color_over_life {
+            Some(gradient) => gradient.sample(life),
+            None => self.color_start.lerp(self.color_end, life),
+        }
+    }
+
+    /// Size multiplier at normalized age `life` (0..=1).
+    pub fn size_scale_at(&self, life: f32) -> f32 {
+        self.size_over_life
+            .as_ref()
+            .map_or(1.0, |curve| curve.sample(life).max(0.0))
+    }
+}
+
+/// Power-saving knobs. Low-power mode trades smoothness for CPU/GPU time.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(default)]
+pub struct PowerConfig {
+    pub low_power: bool,
+    pub low_power_fps: f32,
+    pub low_power_max_particles: u32,
+}
+
+impl Default for PowerConfig {
+    fn default() -> Self {
+        Self {
+            low_power: false,
+            low_power_fps: 30.0,
+            low_power_max_particles: 512,
+        }
+    }
+}
+
+/// Settings that apply only while the cursor is on one particular monitor.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(default)]
+pub struct MonitorOverride {
+    pub enabled: bool,
+    /// Preset used on this monitor instead of `EngineConfig::preset`.
+    pub preset: Option<TrailPreset>,
+}
+
+impl Default for MonitorOverride {
+    fn default() -> Self {
+        Self {
+            enabled: true,
+            preset: None,
         }
     }
 }
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct EngineConfig {
+    /// Master switch for trails on every monitor.
+    #[serde(default = "default_enabled")]
+    pub enabled: bool,
     pub preset: TrailPreset,
+    #[serde(default)]
+    pub power: PowerConfig,
+    #[serde(default)]
+    pub hotkeys: HotkeyConfig,
+    /// Draw the performance HUD on the overlay.
+    #[serde(default)]
+    pub show_hud: bool,
+    /// Per-monitor overrides keyed by stable monitor id.
+    #[serde(default)]
+    pub monitors: BTreeMap<MonitorId, MonitorOverride>,
+}
+
+fn default_enabled() -> bool {
+    true
 }
 
 impl Default for EngineConfig {
     fn default() -> Self {
         Self {
+            enabled: true,
             preset: TrailPreset::default(),
+            power: PowerConfig::default(),
+            hotkeys: HotkeyConfig::default(),
+            show_hud: false,
+            monitors: BTreeMap::new(),
+        }
+    }
+}
+
+impl EngineConfig {
+    /// Preset in effect on `monitor`, or `None` when trails are disabled there (or everywhere).
+    pub fn preset_for(&self, monitor: Option<MonitorId>) -> Option<&TrailPreset> {
+        if !self.enabled {
+            return None;
+        }
+        match monitor.and_then(|id| self.monitors.get(&id)) {
+            Some(entry) if !entry.enabled => None,
+            Some(entry) => Some(entry.preset.as_ref().unwrap_or(&self.preset)),
+            None => Some(&self.preset),
         }
     }
 }
 
-/// Basic engine state placeholder. Later: particle pool, spline smoothing, etc.
+/// Fraction of the cursor velocity inherited by newly emitted particles.
+const INHERITED_VELOCITY: f32 = 0.1;
+/// Per-second velocity damping applied to live particles.
+const VELOCITY_DAMPING: f32 = 4.0;
+/// Extra pixels around particle bounds to cover anti-aliased edges.
+const DAMAGE_MARGIN: f32 = 1.0;
+/// Longest gap between cursor samples that still counts as continuous movement (seconds).
+const MAX_EMIT_INTERVAL: f32 = 0.1;
+
+/// Particle pool fed by cursor movement. Later: spline smoothing, etc.
 pub struct TrailEngine {
     pub config: EngineConfig,
+    particles: Vec<Particle>,
+    cursor: Option<Vec2>,
+    last_emit_cursor: Option<Vec2>,
+    cursor_moved: bool,
+    emission_carry: f32,
+    /// Simulated time since particles were last emitted for a cursor sample.
+    since_emit: f32,
+    quality: QualitySettings,
+    previous_footprint: Option<DamageRect>,
+    layout: DesktopLayout,
+    active_monitor: Option<MonitorId>,
 }
 
 impl TrailEngine {
     pub fn new(config: EngineConfig) -> Self {
-        Self { config }
+        Self {
+            config,
+            particles: Vec::new(),
+            cursor: None,
+            last_emit_cursor: None,
+            cursor_moved: false,
+            emission_carry: 0.0,
+            since_emit: 0.0,
+            quality: QualitySettings::default(),
+            previous_footprint: None,
+            layout: DesktopLayout::default(),
+            active_monitor: None,
+        }
+    }
+
+    pub fn layout(&self) -> &DesktopLayout {
+        &self.layout
+    }
+
+    /// Replace the monitor layout used for DPI scaling and per-monitor settings.
+    pub fn set_layout(&mut self, layout: DesktopLayout) {
+        self.layout = layout;
+        self.refresh_active_monitor();
+    }
+
+    /// Monitor the cursor is currently on, which selects the per-monitor override.
+    pub fn active_monitor(&self) -> Option<MonitorId> {
+        self.active_monitor
+    }
+
+    /// Preset driving emission right now, or `None` when trails are off on the active monitor.
+    pub fn active_preset(&self) -> Option<&TrailPreset> {
+        self.config.preset_for(self.active_monitor)
+    }
+
+    fn refresh_active_monitor(&mut self) {
+        self.active_monitor = self
+            .cursor
+            .and_then(|pos| self.layout.monitor_at(pos))
+            .map(|monitor| monitor.id);
+    }
+
+    pub fn quality(&self) -> QualitySettings {
+        self.quality
+    }
+
+    /// Apply quality multipliers from a `QualityController`. Excess particles are dropped oldest first.
+    pub fn set_quality(&mut self, quality: QualitySettings) {
+        self.quality = quality;
+        if self.active_preset().is_none() {
+            // Trails are off here; let existing particles fade out rather than cutting them.
+            return;
+        }
+        let cap = self.effective_max_particles() as usize;
+        if self.particles.len() > cap {
+            let excess = self.particles.len() - cap;
+            self.particles.drain(..excess);
+        }
+    }
+
+    /// Feed the latest cursor position in desktop coordinates.
+    pub fn set_cursor(&mut self, pos: Vec2) {
+        if self.cursor != Some(pos) {
+            self.cursor = Some(pos);
+            self.cursor_moved = true;
+            self.refresh_active_monitor();
+        }
+    }
+
+    pub fn particles(&self) -> &[Particle] {
+        &self.particles
+    }
+
+    /// Particles ready for the renderer, colored by the preset of the monitor each one is on.
+    pub fn instances(&self) -> Vec<ParticleInstance> {
+        self.particles
+            .iter()
+            .map(|particle| {
+                let monitor = self
+                    .layout
+                    .monitor_at(particle.pos)
+                    .map(|monitor| monitor.id);
+                let preset = self
+                    .config
+                    .preset_for(monitor)
+                    .unwrap_or(&self.config.preset);
+                ParticleInstance {
+                    x: particle.pos.x,
+                    y: particle.pos.y,
+                    size: particle.size,
+                    color: preset.color_at(particle.life()).to_array(),
+                }
+            })
+            .collect()
+    }
+
+    pub fn live_particles(&self) -> usize {
+        self.particles.len()
+    }
+
+    /// Particle cap after power and quality settings are applied.
+    pub fn effective_max_particles(&self) -> u32 {
+        let preset_cap = self
+            .active_preset()
+            .map_or(0, |preset| preset.max_particles);
+        let cap = if self.config.power.low_power {
+            preset_cap.min(self.config.power.low_power_max_particles)
+        } else {
+            preset_cap
+        };
+        (cap as f32 * self.quality.particle_scale).round() as u32
     }
 
-    pub fn update(&mut self, _dt: f32) {
-        // TODO: advance particles
+    /// Emission rate after quality settings are applied.
+    pub fn effective_emission_rate(&self) -> f32 {
+        let rate = self
+            .active_preset()
+            .map_or(0.0, |preset| preset.emission_rate);
+        rate.max(0.0) * self.quality.emission_scale
+    }
+
+    /// True when the cursor hasn't moved since the last update, nothing is left to draw, and the
+    /// last drawn particles have been cleared from the screen.
+    pub fn is_idle(&self) -> bool {
+        !self.cursor_moved && self.particles.is_empty() && self.previous_footprint.is_none()
+    }
+
+    /// Bounding box of all live particles, including their size, in desktop pixels.
+    pub fn footprint(&self) -> Option<DamageRect> {
+        let first = self.particles.first()?;
+        let (min, max) =
+            self.particles
+                .iter()
+                .fold((first.pos, first.pos), |(min, max), particle| {
+                    let extent = Vec2::splat(particle.size * 0.5 + DAMAGE_MARGIN);
+                    (
+                        min.min(particle.pos - extent),
+                        max.max(particle.pos + extent),
+                    )
+                });
+        Some(DamageRect::from_bounds(min.x, min.y, max.x, max.y))
+    }
+
+    /// Regions to redraw for the frame about to be presented: where particles are now plus where
+    /// they were last frame (so vacated pixels get cleared). Call once per rendered frame.
+    pub fn take_damage(&mut self) -> Vec<DamageRect> {
+        let current = self.footprint();
+        let previous = std::mem::replace(&mut self.previous_footprint, current);
+        match (previous, current) {
+            (Some(previous), Some(current)) => {
+                let merged = previous.union(&current);
+                if merged.area() <= previous.area() + current.area() {
+                    vec![merged]
+                } else {
+                    vec![previous, current]
+                }
+            }
+            (Some(rect), None) | (None, Some(rect)) => vec![rect],
+            (None, None) => Vec::new(),
+        }
+    }
+
+    /// Keep in-flight particles consistent with a monitor topology change: particles on a removed
+    /// display are dropped, particles on a moved display follow it (and are clipped if it shrank),
+    /// and particles on a re-scaled display are resized.
+    pub fn handle_monitor_event(&mut self, event: &MonitorEvent) {
+        match event {
+            MonitorEvent::Added { .. } => {}
+            MonitorEvent::Removed { old } => {
+                self.particles
+                    .retain(|particle| !old.contains(particle.pos));
+            }
+            MonitorEvent::Moved { old, new } => {
+                let offset = Vec2::new((new.x - old.x) as f32, (new.y - old.y) as f32);
+                self.particles.retain_mut(|particle| {
+                    if !old.contains(particle.pos) {
+                        return true;
+                    }
+                    particle.pos += offset;
+                    new.contains(particle.pos)
+                });
+            }
+            MonitorEvent::DpiChanged { old, new } => {
+                let ratio = scale_factor(new) / scale_factor(old);
+                for particle in self.particles.iter_mut() {
+                    if old.contains(particle.pos) {
+                        particle.size *= ratio;
+                        particle.base_size *= ratio;
+                    }
+                }
+            }
+        }
+        self.layout.apply_event(event);
+        self.refresh_active_monitor();
+    }
+
+    pub fn clear(&mut self) {
+        self.particles.clear();
+        self.emission_carry = 0.0;
+        self.since_emit = 0.0;
+    }
+
+    pub fn update(&mut self, dt: f32) {
+        let damping = (1.0 - VELOCITY_DAMPING * dt).max(0.0);
+        for particle in self.particles.iter_mut() {
+            particle.age += dt;
+            particle.pos += particle.vel * dt;
+            particle.vel *= damping;
+            let monitor = self
+                .layout
+                .monitor_at(particle.pos)
+                .map(|monitor| monitor.id);
+            let preset = self
+                .config
+                .preset_for(monitor)
+                .unwrap_or(&self.config.preset);
+            particle.size = particle.base_size * preset.size_scale_at(particle.life());
+        }
+        self.particles
+            .retain(|particle| particle.age < particle.lifetime);
+
+        // The cursor is sampled about once per frame while the simulation takes several steps, so
+        // each sample accounts for all the time since the previous one.
+        let resting = self.since_emit > MAX_EMIT_INTERVAL;
+        self.since_emit += dt;
+        if self.cursor_moved {
+            let elapsed = if resting { dt } else { self.since_emit };
+            self.emit(elapsed);
+            self.cursor_moved = false;
+            self.since_emit = 0.0;
+            self.last_emit_cursor = self.cursor;
+        } else if self.since_emit > MAX_EMIT_INTERVAL {
+            self.emission_carry = 0.0;
+            self.last_emit_cursor = self.cursor;
+        }
+    }
+
+    /// Spawn particles along the segment the cursor travelled over the last `dt` seconds.
+    fn emit(&mut self, dt: f32) {
+        let (Some(to), Some(preset)) = (self.cursor, self.active_preset()) else {
+            return;
+        };
+        let lifetime = preset.decay_seconds.max(0.0);
+        let logical_size = preset.particle_size.max(0.0);
+        let birth_scale = preset.size_scale_at(0.0);
+        let from = self.last_emit_cursor.unwrap_or(to);
+        self.emission_carry += self.effective_emission_rate() * dt;
+        let count = self.emission_carry.floor() as usize;
+        self.emission_carry -= count as f32;
+
+        let capacity = self.effective_max_particles() as usize;
+        let count = count.min(capacity.saturating_sub(self.particles.len()));
+        if count == 0 {
+            return;
+        }
+        let vel = if dt > 0.0 {
+            (to - from) / dt * INHERITED_VELOCITY
+        } else {
+            Vec2::ZERO
+        };
+        for index in 0..count {
+            let t = (index + 1) as f32 / count as f32;
+            let pos = from.lerp(to, t);
+            let base_size = self.layout.scale_size_at(pos, logical_size);
+            self.particles.push(Particle {
+                pos,
+                vel,
+                age: 0.0,
+                lifetime,
+                size: base_size * birth_scale,
+                base_size,
+            });
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::sync::mpsc::Receiver;
+
+    use serpentines_platform::mock::{monitor, MockOverlayManager};
+    use serpentines_platform::{MonitorRect, OverlayManager};
+
+    use super::*;
+
+    /// Engine laid out on `overlays`, plus the topology events they publish from now on.
+    fn engine_on(overlays: &mut MockOverlayManager) -> (TrailEngine, Receiver<MonitorEvent>) {
+        let mut engine = TrailEngine::new(EngineConfig::default());
+        engine.set_layout(DesktopLayout::from_manager(overlays).unwrap());
+        (engine, overlays.subscribe())
+    }
+
+    fn apply_events(engine: &mut TrailEngine, events: &Receiver<MonitorEvent>) {
+        while let Ok(event) = events.try_recv() {
+            engine.handle_monitor_event(&event);
+        }
+    }
+
+    fn spawn(engine: &mut TrailEngine, x: f32, y: f32) {
+        engine.particles.push(Particle {
+            pos: Vec2::new(x, y),
+            vel: Vec2::ZERO,
+            age: 0.0,
+            lifetime: 10.0,
+            size: 8.0,
+            base_size: 8.0,
+        });
+    }
+
+    fn positions(engine: &TrailEngine) -> Vec<Vec2> {
+        engine
+            .particles()
+            .iter()
+            .map(|particle| particle.pos)
+            .collect()
+    }
+
+    fn named(name: &str) -> TrailPreset {
+        TrailPreset {
+            name: name.into(),
+            ..TrailPreset::default()
+        }
+    }
+
+    /// "left" uses its own preset, "right" has trails off, "centre" overrides nothing.
+    fn config_with_overrides() -> EngineConfig {
+        let mut config = EngineConfig {
+            preset: named("Global"),
+            ..EngineConfig::default()
+        };
+        config.monitors.insert(
+            MonitorId::from_key("left"),
+            MonitorOverride {
+                enabled: true,
+                preset: Some(named("Left")),
+            },
+        );
+        config.monitors.insert(
+            MonitorId::from_key("right"),
+            MonitorOverride {
+                enabled: false,
+                preset: Some(named("Unused")),
+            },
+        );
+        config
+            .monitors
+            .insert(MonitorId::from_key("centre"), MonitorOverride::default());
+        config
+    }
+
+    #[test]
+    fn preset_for_applies_overrides() {
+        let config = config_with_overrides();
+        let name_on = |key: &str| {
+            config
+                .preset_for(Some(MonitorId::from_key(key)))
+                .map(|preset| preset.name.as_str())
+        };
+        assert_eq!(name_on("left"), Some("Left"));
+        assert_eq!(name_on("right"), None);
+        assert_eq!(name_on("centre"), Some("Global"));
+        assert_eq!(name_on("unknown"), Some("Global"));
+        assert_eq!(
+            config.preset_for(None).map(|preset| preset.name.as_str()),
+            Some("Global")
+        );
+
+        let off = EngineConfig {
+            enabled: false,
+            ..config_with_overrides()
+        };
+        assert!(off.preset_for(Some(MonitorId::from_key("left"))).is_none());
+        assert!(off.preset_for(None).is_none());
+    }
+
+    #[test]
+    fn removed_monitor_falls_back_to_the_global_preset() {
+        let mut overlays = MockOverlayManager::with_monitors(vec![
+            monitor("main", 0, 0),
+            monitor("left", -1920, 0),
+        ]);
+        let mut engine = TrailEngine::new(config_with_overrides());
+        engine.set_layout(DesktopLayout::from_manager(&overlays).unwrap());
+        let events = overlays.subscribe();
+        engine.set_cursor(Vec2::new(-100.0, 100.0));
+        assert_eq!(engine.active_monitor(), Some(MonitorId::from_key("left")));
+        assert_eq!(engine.active_preset().unwrap().name, "Left");
+
+        overlays.remove_monitor(MonitorId::from_key("left"));
+        apply_events(&mut engine, &events);
+        assert_eq!(engine.active_monitor(), None);
+        assert_eq!(engine.active_preset().unwrap().name, "Global");
+        // The override is kept for when the monitor comes back.
+        assert!(engine
+            .config
+            .monitors
+            .contains_key(&MonitorId::from_key("left")));
+
+        overlays.add_monitor(monitor("left", -1920, 0));
+        apply_events(&mut engine, &events);
+        assert_eq!(engine.active_preset().unwrap().name, "Left");
+    }
+
+    #[test]
+    fn damage_is_empty_without_particles() {
+        let mut engine = TrailEngine::new(EngineConfig::default());
+        assert!(engine.take_damage().is_empty());
+        assert!(engine.is_idle());
+    }
+
+    #[test]
+    fn damage_includes_the_previous_footprint() {
+        let mut engine = TrailEngine::new(EngineConfig::default());
+        spawn(&mut engine, 100.0, 100.0);
+        // Half the size plus the margin on each side.
+        let first = DamageRect::new(95, 95, 10, 10);
+        assert_eq!(engine.take_damage(), [first]);
+
+        engine.particles[0].pos.x = 104.0;
+        assert_eq!(engine.take_damage(), [DamageRect::new(95, 95, 14, 10)]);
+
+        engine.clear();
+        assert!(!engine.is_idle(), "vacated pixels still need clearing");
+        assert_eq!(engine.take_damage(), [DamageRect::new(99, 95, 10, 10)]);
+        assert!(engine.is_idle());
+        assert!(engine.take_damage().is_empty());
+    }
+
+    #[test]
+    fn distant_footprints_are_not_merged() {
+        let mut engine = TrailEngine::new(EngineConfig::default());
+        spawn(&mut engine, 100.0, 100.0);
+        engine.take_damage();
+
+        engine.particles[0].pos = Vec2::new(1000.0, 800.0);
+        assert_eq!(
+            engine.take_damage(),
+            [
+                DamageRect::new(95, 95, 10, 10),
+                DamageRect::new(995, 795, 10, 10)
+            ]
+        );
+    }
+
+    #[test]
+    fn footprint_spans_every_particle() {
+        let mut engine = TrailEngine::new(EngineConfig::default());
+        spawn(&mut engine, 100.0, 100.0);
+        spawn(&mut engine, 300.0, 50.0);
+        assert_eq!(engine.footprint(), Some(DamageRect::new(95, 45, 210, 60)));
+    }
+
+    #[test]
+    fn removed_monitor_drops_its_particles() {
+        let mut overlays = MockOverlayManager::with_monitors(vec![
+            monitor("left", 0, 0),
+            monitor("right", 1920, 0),
+        ]);
+        let (mut engine, events) = engine_on(&mut overlays);
+        spawn(&mut engine, 100.0, 100.0);
+        spawn(&mut engine, 2000.0, 100.0);
+
+        overlays.remove_monitor(monitor("right", 1920, 0).id);
+        apply_events(&mut engine, &events);
+        assert_eq!(positions(&engine), [Vec2::new(100.0, 100.0)]);
+        assert_eq!(engine.layout().monitors(), [monitor("left", 0, 0)]);
+    }
+
+    #[test]
+    fn moved_monitor_carries_its_particles() {
+        let mut overlays = MockOverlayManager::with_monitors(vec![
+            monitor("left", 0, 0),
+            monitor("right", 1920, 0),
+        ]);
+        let (mut engine, events) = engine_on(&mut overlays);
+        spawn(&mut engine, 100.0, 100.0);
+        spawn(&mut engine, 2000.0, 100.0);
+        spawn(&mut engine, 3800.0, 100.0);
+
+        // The right monitor moves above the left one and shrinks to 1280 wide.
+        overlays.update_monitor(MonitorRect {
+            width: 1280,
+            ..monitor("right", 0, -1080)
+        });
+        apply_events(&mut engine, &events);
+        assert_eq!(
+            positions(&engine),
+            [Vec2::new(100.0, 100.0), Vec2::new(80.0, -980.0)]
+        );
+    }
+
+    #[test]
+    fn rescaled_monitor_resizes_its_particles() {
+        let mut overlays = MockOverlayManager::with_monitors(vec![
+            monitor("left", 0, 0),
+            monitor("right", 1920, 0),
+        ]);
+        let (mut engine, events) = engine_on(&mut overlays);
+        spawn(&mut engine, 100.0, 100.0);
+        spawn(&mut engine, 2000.0, 100.0);
+
+        overlays.update_monitor(MonitorRect {
+            dpi: 192,
+            ..monitor("right", 1920, 0)
+        });
+        apply_events(&mut engine, &events);
+        let sizes: Vec<(f32, f32)> = engine
+            .particles()
+            .iter()
+            .map(|particle| (particle.size, particle.base_size))
+            .collect();
+        assert_eq!(sizes, [(8.0, 8.0), (16.0, 16.0)]);
+        assert_eq!(engine.layout().monitors()[1].dpi, 192);
     }
 }
@@ -0,0 +1,170 @@
+//! Preset packs: shareable TOML files holding one or more presets.
+//!
+//! ```toml
+//! name = "Neon"
+//! author = "someone"
+//!
+//! [[preset]]
+//! name = "Neon Pink"
+//! emission_rate = 150.0
+//! color_start = [1.0, 0.2, 0.8, 1.0]
+//! tags = ["bright"]
+//! ```
+//!
+//! Fields left out of a `[[preset]]` table take their defaults.
+
+use std::fmt;
+use std::path::Path;
+
+use serde::{Deserialize, Serialize};
+
+use crate::{PresetProblem, TrailPreset};
+
+/// File extension used for packs.
+pub const PACK_EXTENSION: &str = "toml";
+
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+pub struct PresetPack {
+    pub name: String,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub author: Option<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub description: Option<String>,
+    #[serde(default, rename = "preset")]
+    pub presets: Vec<TrailPreset>,
+}
+
+#[derive(Debug)]
+pub enum PackError {
+    Io(std::io::Error),
+    Parse(toml::de::Error),
+    Serialize(toml::ser::Error),
+    /// The pack has no presets.
+    Empty,
+    /// A preset in the pack failed validation.
+    Invalid {
+        preset: String,
+        problems: Vec<PresetProblem>,
+    },
+}
+
+impl fmt::Display for PackError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            PackError::Io(err) => write!(f, "failed to read pack: {err}"),
+            PackError::Parse(err) => write!(f, "invalid pack: {err}"),
+            PackError::Serialize(err) => write!(f, "failed to write pack: {err}"),
+            PackError::Empty => f.write_str("pack contains no presets"),
+            PackError::Invalid { preset, problems } => {
+                let problems: Vec<String> = problems.iter().map(ToString::to_string).collect();
+                write!(f, "preset '{preset}': {}", problems.join("; "))
+            }
+        }
+    }
+}
+
+impl std::error::Error for PackError {}
+
+impl PresetPack {
+    /// Parse and validate a pack.
+    pub fn from_toml(text: &str) -> Result<Self, PackError> {
+        let pack: PresetPack = toml::from_str(text).map_err(PackError::Parse)?;
+        pack.validate()?;
+        Ok(pack)
+    }
+
+    pub fn load(path: &Path) -> Result<Self, PackError> {
+        let text = std::fs::read_to_string(path).map_err(PackError::Io)?;
+        Self::from_toml(&text)
+    }
+
+    pub fn to_toml(&self) -> Result<String, PackError> {
+        toml::to_string_pretty(self).map_err(PackError::Serialize)
+    }
+
+    /// Fails on an empty pack or on the first invalid preset.
+    pub fn validate(&self) -> Result<(), PackError> {
+        if self.presets.is_empty() {
+            return Err(PackError::Empty);
+        }
+        for preset in &self.presets {
+            preset.validate().map_err(|problems| PackError::Invalid {
+                preset: preset.name.clone(),
+                problems,
+            })?;
+        }
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn packs_round_trip_and_fill_in_defaults() {
+        let pack = PresetPack::from_toml(
+            r#"
+                name = "Neon"
+
+                [[preset]]
+                name = "Neon Pink"
+                emission_rate = 150.0
+            "#,
+        )
+        .unwrap();
+        assert_eq!(pack.presets.len(), 1);
+        assert_eq!(pack.presets[0].emission_rate, 150.0);
+        assert_eq!(
+            pack.presets[0].decay_seconds,
+            TrailPreset::default().decay_seconds
+        );
+        assert_eq!(
+            PresetPack::from_toml(&pack.to_toml().unwrap()).unwrap(),
+            pack
+        );
+    }
+
+    #[test]
+    fn empty_packs_are_rejected() {
+        assert!(matches!(
+            PresetPack::from_toml(r#"name = "Nothing""#),
+            Err(PackError::Empty)
+        ));
+        assert!(matches!(
+            PresetPack::default().validate(),
+            Err(PackError::Empty)
+        ));
+    }
+
+    #[test]
+    fn invalid_presets_name_the_culprit() {
+        let text = r#"
+            name = "Broken"
+
+            [[preset]]
+            name = "Fine"
+
+            [[preset]]
+            name = "Huge"
+            particle_size = 500.0
+        "#;
+        match PresetPack::from_toml(text) {
+            Err(PackError::Invalid { preset, problems }) => {
+                assert_eq!(preset, "Huge");
+                assert!(matches!(
+                    problems[..],
+                    [PresetProblem::OutOfRange {
+                        field: "particle size",
+                        ..
+                    }]
+                ));
+            }
+            other => panic!("expected an invalid preset, got {other:?}"),
+        }
+        assert!(matches!(
+            PresetPack::from_toml("[[preset]"),
+            Err(PackError::Parse(_))
+        ));
+    }
+}
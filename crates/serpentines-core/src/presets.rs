@@ -0,0 +1,552 @@
+//! Named trail presets the user can switch between.
+
+use std::collections::BTreeSet;
+use std::fmt;
+use std::ops::RangeInclusive;
+
+use glam::Vec4;
+use serde::{Deserialize, Serialize};
+
+use crate::TrailPreset;
+
+/// Accepted ranges for preset fields; the settings editor uses them for its sliders.
+pub const MAX_PARTICLES_RANGE: RangeInclusive<u32> = 1..=65536;
+pub const EMISSION_RATE_RANGE: RangeInclusive<f32> = 0.0..=2000.0;
+pub const DECAY_SECONDS_RANGE: RangeInclusive<f32> = 0.05..=10.0;
+pub const PARTICLE_SIZE_RANGE: RangeInclusive<f32> = 0.5..=64.0;
+/// Largest multiplier a size-over-life curve may reach.
+pub const MAX_SIZE_SCALE: f32 = 4.0;
+
+/// Something wrong with a preset that would make it unusable or misbehave.
+#[derive(Debug, Clone, PartialEq)]
+pub enum PresetProblem {
+    EmptyName,
+    /// A numeric field is outside its accepted range (or not a number).
+    OutOfRange {
+        field: &'static str,
+        value: f32,
+        min: f32,
+        max: f32,
+    },
+    /// A color channel is outside 0..=1.
+    InvalidColor {
+        field: &'static str,
+    },
+    /// A gradient or curve has no stops.
+    EmptyCurve {
+        field: &'static str,
+    },
+}
+
+impl fmt::Display for PresetProblem {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            PresetProblem::EmptyName => f.write_str("name must not be empty"),
+            PresetProblem::OutOfRange {
+                field,
+                value,
+                min,
+                max,
+            } => write!(f, "{field} is {value}, expected {min} to {max}"),
+            PresetProblem::InvalidColor { field } => {
+                write!(f, "{field} channels must be between 0 and 1")
+            }
+            PresetProblem::EmptyCurve { field } => write!(f, "{field} needs at least one stop"),
+        }
+    }
+}
+
+impl TrailPreset {
+    /// Every problem with this preset.
+    pub fn validate(&self) -> Result<(), Vec<PresetProblem>> {
+        let mut problems = Vec::new();
+        if self.name.trim().is_empty() {
+            problems.push(PresetProblem::EmptyName);
+        }
+        let max_particles = *MAX_PARTICLES_RANGE.start() as f32..=*MAX_PARTICLES_RANGE.end() as f32;
+        let ranges = [
+            ("max particles", self.max_particles as f32, max_particles),
+            ("emission rate", self.emission_rate, EMISSION_RATE_RANGE),
+            ("decay seconds", self.decay_seconds, DECAY_SECONDS_RANGE),
+            ("particle size", self.particle_size, PARTICLE_SIZE_RANGE),
+        ];
+        for (field, value, range) in ranges {
+            if !range.contains(&value) {
+                problems.push(PresetProblem::OutOfRange {
+                    field,
+                    value,
+                    min: *range.start(),
+                    max: *range.end(),
+                });
+            }
+        }
+        let gradient = self
+            .color_over_life
+            .iter()
+            .flat_map(|gradient| gradient.stops());
+        let colors = [self.color_start, self.color_end]
+            .into_iter()
+            .zip(["start color", "end color"])
+            .chain(gradient.map(|stop| (stop.color, "gradient stop")));
+        for (color, field) in colors {
+            if !color.is_finite() || color.min_element() < 0.0 || color.max_element() > 1.0 {
+                problems.push(PresetProblem::InvalidColor { field });
+            }
+        }
+        if let Some(gradient) = &self.color_over_life {
+            if gradient.stops().is_empty() {
+                problems.push(PresetProblem::EmptyCurve {
+                    field: "color over life",
+                });
+            }
+            let positions = gradient.stops().iter().map(|stop| stop.t);
+            check_positions("color over life position", positions, &mut problems);
+        }
+        if let Some(curve) = &self.size_over_life {
+            let keys = curve.keys();
+            check_positions(
+                "size over life position",
+                keys.iter().map(|key| key.t),
+                &mut problems,
+            );
+            let finite = keys.iter().all(|key| {
+                key.value.is_finite() && key.in_tangent.is_finite() && key.out_tangent.is_finite()
+            });
+            if keys.is_empty() {
+                problems.push(PresetProblem::EmptyCurve {
+                    field: "size over life",
+                });
+            } else if !finite || curve.max_value() > MAX_SIZE_SCALE {
+                problems.push(PresetProblem::OutOfRange {
+                    field: "size over life",
+                    value: curve.max_value(),
+                    min: 0.0,
+                    max: MAX_SIZE_SCALE,
+                });
+            }
+        }
+        if problems.is_empty() {
+            Ok(())
+        } else {
+            Err(problems)
+        }
+    }
+}
+
+/// Gradient stops and curve keys must sit within a particle's life.
+fn check_positions(
+    field: &'static str,
+    mut positions: impl Iterator<Item = f32>,
+    problems: &mut Vec<PresetProblem>,
+) {
+    if let Some(value) = positions.find(|t| !(0.0..=1.0).contains(t)) {
+        problems.push(PresetProblem::OutOfRange {
+            field,
+            value,
+            min: 0.0,
+            max: 1.0,
+        });
+    }
+}
+
+/// A change to the preset library, as requested from the preset browser.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(tag = "type", rename_all = "snake_case")]
+pub enum LibraryEdit {
+    Duplicate {
+        name: String,
+    },
+    Rename {
+        from: String,
+        to: String,
+    },
+    Delete {
+        name: String,
+    },
+    SetFavorite {
+        name: String,
+        favorite: bool,
+    },
+    SetTags {
+        name: String,
+        tags: Vec<String>,
+    },
+    /// Add presets from a pack; names that are taken get a numbered suffix.
+    Import {
+        presets: Vec<TrailPreset>,
+    },
+}
+
+/// Which presets the browser shows.
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct PresetFilter {
+    /// Case-insensitive substring of the name or of any tag.
+    pub text: String,
+    /// Only presets carrying this tag (case-insensitive).
+    pub tag: Option<String>,
+    pub favorites_only: bool,
+}
+
+impl PresetFilter {
+    pub fn matches(&self, preset: &TrailPreset) -> bool {
+        if self.favorites_only && !preset.favorite {
+            return false;
+        }
+        if let Some(tag) = &self.tag {
+            if !preset
+                .tags
+                .iter()
+                .any(|candidate| candidate.eq_ignore_ascii_case(tag))
+            {
+                return false;
+            }
+        }
+        let text = self.text.trim().to_lowercase();
+        text.is_empty()
+            || preset.name.to_lowercase().contains(&text)
+            || preset
+                .tags
+                .iter()
+                .any(|tag| tag.to_lowercase().contains(&text))
+    }
+}
+
+/// Ordered set of presets with unique names (compared case-insensitively).
+#[derive(Debug, Clone, Default)]
+pub struct PresetLibrary {
+    presets: Vec<TrailPreset>,
+}
+
+/// Later presets replace earlier ones with the same name.
+impl FromIterator<TrailPreset> for PresetLibrary {
+    fn from_iter<I: IntoIterator<Item = TrailPreset>>(presets: I) -> Self {
+        let mut library = Self::new();
+        for preset in presets {
+            library.insert(preset);
+        }
+        library
+    }
+}
+
+impl PresetLibrary {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Presets shipped with the app.
+    pub fn builtin() -> Self {
+        let mut library = Self::new();
+        library.insert(TrailPreset::default());
+        library.insert(TrailPreset {
+            name: "Comet".into(),
+            max_particles: 2048,
+            emission_rate: 200.0,
+            decay_seconds: 0.35,
+            color_start: Vec4::new(0.6, 0.85, 1.0, 1.0),
+            color_end: Vec4::new(0.1, 0.2, 0.9, 0.0),
+            particle_size: 8.0,
+            color_over_life: None,
+            size_over_life: None,
+            tags: vec!["cool".into(), "fast".into()],
+            favorite: false,
+        });
+        library.insert(TrailPreset {
+            name: "Ember".into(),
+            max_particles: 4096,
+            emission_rate: 90.0,
+            decay_seconds: 1.2,
+            color_start: Vec4::new(1.0, 0.75, 0.2, 1.0),
+            color_end: Vec4::new(0.8, 0.1, 0.0, 0.0),
+            particle_size: 5.0,
+            color_over_life: None,
+            size_over_life: None,
+            tags: vec!["warm".into()],
+            favorite: false,
+        });
+        library.insert(TrailPreset {
+            name: "Subtle".into(),
+            max_particles: 512,
+            emission_rate: 40.0,
+            decay_seconds: 0.3,
+            color_start: Vec4::new(1.0, 1.0, 1.0, 0.5),
+            color_end: Vec4::new(1.0, 1.0, 1.0, 0.0),
+            particle_size: 3.0,
+            color_over_life: None,
+            size_over_life: None,
+            tags: vec!["minimal".into()],
+            favorite: false,
+        });
+        library
+    }
+
+    pub fn presets(&self) -> &[TrailPreset] {
+        &self.presets
+    }
+
+    pub fn names(&self) -> impl Iterator<Item = &str> {
+        self.presets.iter().map(|preset| preset.name.as_str())
+    }
+
+    pub fn len(&self) -> usize {
+        self.presets.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.presets.is_empty()
+    }
+
+    pub fn get(&self, name: &str) -> Option<&TrailPreset> {
+        self.position(name).map(|index| &self.presets[index])
+    }
+
+    /// Add `preset`, replacing any preset with the same name in place. Returns the replaced one.
+    pub fn insert(&mut self, preset: TrailPreset) -> Option<TrailPreset> {
+        match self.position(&preset.name) {
+            Some(index) => Some(std::mem::replace(&mut self.presets[index], preset)),
+            None => {
+                self.presets.push(preset);
+                None
+            }
+        }
+    }
+
+    /// Preset after (or before, when `forward` is false) the one named `current`, wrapping around.
+    /// Starts from the first preset when `current` isn't in the library.
+    pub fn cycle(&self, current: &str, forward: bool) -> Option<&TrailPreset> {
+        let len = self.presets.len();
+        if len == 0 {
+            return None;
+        }
+        let index = match self.position(current) {
+            Some(index) if forward => (index + 1) % len,
+            Some(index) => (index + len - 1) % len,
+            None => 0,
+        };
+        self.presets.get(index)
+    }
+
+    pub fn remove(&mut self, name: &str) -> Option<TrailPreset> {
+        self.position(name).map(|index| self.presets.remove(index))
+    }
+
+    /// Presets passing `filter`, in library order.
+    pub fn filtered<'a>(
+        &'a self,
+        filter: &'a PresetFilter,
+    ) -> impl Iterator<Item = &'a TrailPreset> {
+        self.presets.iter().filter(|preset| filter.matches(preset))
+    }
+
+    /// Every tag in use, lowercased and sorted.
+    pub fn tags(&self) -> BTreeSet<String> {
+        self.presets
+            .iter()
+            .flat_map(|preset| preset.tags.iter().map(|tag| tag.to_lowercase()))
+            .collect()
+    }
+
+    /// `base` if no preset has that name yet, otherwise `base (2)`, `base (3)`, ...
+    pub fn unique_name(&self, base: &str) -> String {
+        if self.position(base).is_none() {
+            return base.to_string();
+        }
+        (2..)
+            .map(|n| format!("{base} ({n})"))
+            .find(|name| self.position(name).is_none())
+            .expect("unbounded range")
+    }
+
+    /// Copy the preset named `name` under a fresh name, right after the original.
+    pub fn duplicate(&mut self, name: &str) -> Option<&TrailPreset> {
+        let index = self.position(name)?;
+        let mut copy = self.presets[index].clone();
+        copy.name = self.unique_name(&format!("{} copy", copy.name));
+        copy.favorite = false;
+        self.presets.insert(index + 1, copy);
+        self.presets.get(index + 1)
+    }
+
+    /// Rename a preset. Fails if it doesn't exist, `to` is blank, or another preset has that name.
+    pub fn rename(&mut self, from: &str, to: &str) -> bool {
+        let to = to.trim();
+        let Some(index) = self.position(from) else {
+            return false;
+        };
+        if to.is_empty() || self.position(to).is_some_and(|other| other != index) {
+            return false;
+        }
+        self.presets[index].name = to.to_string();
+        true
+    }
+
+    pub fn set_favorite(&mut self, name: &str, favorite: bool) -> bool {
+        self.update(name, |preset| preset.favorite = favorite)
+    }
+
+    pub fn set_tags(&mut self, name: &str, tags: Vec<String>) -> bool {
+        self.update(name, |preset| preset.tags = tags)
+    }
+
+    /// Add `presets`, renaming any whose name is taken. Returns the names they were added under.
+    pub fn import(&mut self, presets: Vec<TrailPreset>) -> Vec<String> {
+        presets
+            .into_iter()
+            .map(|mut preset| {
+                preset.name = self.unique_name(&preset.name);
+                let name = preset.name.clone();
+                self.presets.push(preset);
+                name
+            })
+            .collect()
+    }
+
+    /// Apply a browser edit. Returns false if it referred to a missing preset or was rejected.
+    pub fn apply(&mut self, edit: LibraryEdit) -> bool {
+        match edit {
+            LibraryEdit::Duplicate { name } => self.duplicate(&name).is_some(),
+            LibraryEdit::Rename { from, to } => self.rename(&from, &to),
+            LibraryEdit::Delete { name } => self.remove(&name).is_some(),
+            LibraryEdit::SetFavorite { name, favorite } => self.set_favorite(&name, favorite),
+            LibraryEdit::SetTags { name, tags } => self.set_tags(&name, tags),
+            LibraryEdit::Import { presets } => {
+                self.import(presets);
+                true
+            }
+        }
+    }
+
+    fn update(&mut self, name: &str, change: impl FnOnce(&mut TrailPreset)) -> bool {
+        let Some(index) = self.position(name) else {
+            return false;
+        };
+        change(&mut self.presets[index]);
+        true
+    }
+
+    fn position(&self, name: &str) -> Option<usize> {
+        self.presets
+            .iter()
+            .position(|preset| preset.name.eq_ignore_ascii_case(name))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn named(name: &str) -> TrailPreset {
+        TrailPreset {
+            name: name.into(),
+            ..TrailPreset::default()
+        }
+    }
+
+    fn names(library: &PresetLibrary) -> Vec<&str> {
+        library.names().collect()
+    }
+
+    #[test]
+    fn rename_rejects_case_insensitive_collisions() {
+        let mut library: PresetLibrary = [named("Comet"), named("Ember")].into_iter().collect();
+        assert!(!library.rename("Comet", "ember"));
+        assert!(!library.rename("Comet", "   "));
+        assert!(!library.rename("Missing", "Other"));
+        assert_eq!(names(&library), ["Comet", "Ember"]);
+
+        // Changing only the case of its own name is fine.
+        assert!(library.rename("comet", " COMET "));
+        assert_eq!(names(&library), ["COMET", "Ember"]);
+    }
+
+    #[test]
+    fn unique_name_suffixes_taken_names() {
+        let library: PresetLibrary = [named("Comet"), named("comet (2)")].into_iter().collect();
+        assert_eq!(library.unique_name("Ember"), "Ember");
+        assert_eq!(library.unique_name("COMET"), "COMET (3)");
+    }
+
+    #[test]
+    fn duplicate_inserts_an_unfavorited_copy_after_the_original() {
+        let mut library: PresetLibrary = [named("Comet"), named("Ember")].into_iter().collect();
+        library.set_favorite("Comet", true);
+        assert_eq!(library.duplicate("Comet").unwrap().name, "Comet copy");
+        assert_eq!(library.duplicate("comet").unwrap().name, "Comet copy (2)");
+        assert!(library.duplicate("Missing").is_none());
+        assert_eq!(
+            names(&library),
+            ["Comet", "Comet copy (2)", "Comet copy", "Ember"]
+        );
+        assert!(!library.get("Comet copy").unwrap().favorite);
+        assert!(library.get("Comet").unwrap().favorite);
+    }
+
+    #[test]
+    fn import_renames_instead_of_replacing() {
+        let mut library: PresetLibrary = [named("Comet")].into_iter().collect();
+        let imported = library.import(vec![named("comet"), named("Neon"), named("Neon")]);
+        assert_eq!(imported, ["comet (2)", "Neon", "Neon (2)"]);
+        assert_eq!(names(&library), ["Comet", "comet (2)", "Neon", "Neon (2)"]);
+    }
+
+    #[test]
+    fn apply_reports_missing_presets() {
+        let mut library = PresetLibrary::builtin();
+        let before = library.len();
+        let edit = |name: &str| LibraryEdit::SetTags {
+            name: name.into(),
+            tags: vec!["bright".into()],
+        };
+        assert!(library.apply(edit("Comet")));
+        assert!(!library.apply(edit("Missing")));
+        assert!(library.apply(LibraryEdit::SetFavorite {
+            name: "ember".into(),
+            favorite: true,
+        }));
+        assert!(library.apply(LibraryEdit::Duplicate {
+            name: "Subtle".into()
+        }));
+        assert!(!library.apply(LibraryEdit::Rename {
+            from: "Subtle copy".into(),
+            to: "comet".into(),
+        }));
+        assert!(library.apply(LibraryEdit::Delete {
+            name: "Subtle copy".into()
+        }));
+        assert!(!library.apply(LibraryEdit::Delete {
+            name: "Subtle copy".into()
+        }));
+        assert!(library.apply(LibraryEdit::Import {
+            presets: vec![named("Ember")]
+        }));
+
+        assert_eq!(library.len(), before + 1);
+        assert_eq!(library.get("Comet").unwrap().tags, ["bright"]);
+        assert!(library.get("Ember").unwrap().favorite);
+        assert!(library.get("Ember (2)").is_some());
+    }
+
+    #[test]
+    fn validate_collects_every_problem() {
+        assert!(TrailPreset::default().validate().is_ok());
+        let broken = TrailPreset {
+            name: " ".into(),
+            emission_rate: f32::NAN,
+            color_end: Vec4::new(2.0, 0.0, 0.0, 1.0),
+            ..TrailPreset::default()
+        };
+        let problems = broken.validate().unwrap_err();
+        assert_eq!(problems.len(), 3, "{problems:?}");
+        assert_eq!(problems[0], PresetProblem::EmptyName);
+        assert!(matches!(
+            problems[1],
+            PresetProblem::OutOfRange {
+                field: "emission rate",
+                ..
+            }
+        ));
+        assert_eq!(
+            problems[2],
+            PresetProblem::InvalidColor { field: "end color" }
+        );
+    }
+}
@@ -0,0 +1,257 @@
+//! Adaptive quality: trade particle density and post-effects for frame time on slow machines.
+
+use std::fmt;
+use std::time::Duration;
+
+use serde::{Deserialize, Serialize};
+
+/// Smoothing factor for the exponential moving average of frame cost.
+const COST_SMOOTHING: f32 = 0.1;
+/// Consecutive over-budget frames before stepping quality down.
+const DOWNGRADE_AFTER_FRAMES: u32 = 10;
+/// Consecutive frames with headroom before stepping quality back up.
+const UPGRADE_AFTER_FRAMES: u32 = 120;
+/// Fraction of the budget the smoothed cost must stay under to count as headroom.
+const HEADROOM_RATIO: f32 = 0.6;
+
+/// Discrete quality steps, lowest first.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash, Serialize, Deserialize)]
+pub enum QualityLevel {
+    Minimal,
+    Low,
+    Medium,
+    High,
+    Full,
+}
+
+impl QualityLevel {
+    pub const ALL: [QualityLevel; 5] = [
+        QualityLevel::Minimal,
+        QualityLevel::Low,
+        QualityLevel::Medium,
+        QualityLevel::High,
+        QualityLevel::Full,
+    ];
+
+    pub fn label(self) -> &'static str {
+        match self {
+            QualityLevel::Minimal => "minimal",
+            QualityLevel::Low => "low",
+            QualityLevel::Medium => "medium",
+            QualityLevel::High => "high",
+            QualityLevel::Full => "full",
+        }
+    }
+
+    pub fn lower(self) -> Self {
+        Self::ALL[(self as usize).saturating_sub(1)]
+    }
+
+    pub fn higher(self) -> Self {
+        Self::ALL[(self as usize + 1).min(Self::ALL.len() - 1)]
+    }
+
+    pub fn settings(self) -> QualitySettings {
+        let (particle_scale, emission_scale, post_effects) = match self {
+            QualityLevel::Minimal => (0.15, 0.35, false),
+            QualityLevel::Low => (0.3, 0.5, false),
+            QualityLevel::Medium => (0.5, 0.7, false),
+            QualityLevel::High => (0.75, 0.85, true),
+            QualityLevel::Full => (1.0, 1.0, true),
+        };
+        QualitySettings {
+            particle_scale,
+            emission_scale,
+            post_effects,
+        }
+    }
+}
+
+impl fmt::Display for QualityLevel {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(self.label())
+    }
+}
+
+/// Multipliers applied on top of the active preset.
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+pub struct QualitySettings {
+    pub particle_scale: f32,
+    pub emission_scale: f32,
+    pub post_effects: bool,
+}
+
+impl Default for QualitySettings {
+    fn default() -> Self {
+        QualityLevel::Full.settings()
+    }
+}
+
+/// Time spent in the two halves of a frame.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct FrameTimings {
+    pub update: Duration,
+    pub render: Duration,
+}
+
+impl FrameTimings {
+    pub fn total(&self) -> Duration {
+        self.update + self.render
+    }
+}
+
+/// Watches frame cost against a budget and steps `QualityLevel` down or up.
+pub struct QualityController {
+    budget: Duration,
+    level: QualityLevel,
+    smoothed_cost: Option<f32>,
+    over_budget_frames: u32,
+    headroom_frames: u32,
+}
+
+impl QualityController {
+    pub fn new(budget: Duration) -> Self {
+        Self {
+            budget,
+            level: QualityLevel::Full,
+            smoothed_cost: None,
+            over_budget_frames: 0,
+            headroom_frames: 0,
+        }
+    }
+
+    pub fn level(&self) -> QualityLevel {
+        self.level
+    }
+
+    pub fn settings(&self) -> QualitySettings {
+        self.level.settings()
+    }
+
+    pub fn budget(&self) -> Duration {
+        self.budget
+    }
+
+    /// Change the per-frame budget, typically the scheduler's frame interval.
+    pub fn set_budget(&mut self, budget: Duration) {
+        self.budget = budget;
+        self.over_budget_frames = 0;
+        self.headroom_frames = 0;
+    }
+
+    /// Smoothed update + render cost per frame.
+    pub fn smoothed_cost(&self) -> Duration {
+        Duration::from_secs_f32(self.smoothed_cost.unwrap_or(0.0))
+    }
+
+    /// Feed one frame's timings. Returns the new level when it changed.
+    pub fn record(&mut self, timings: FrameTimings) -> Option<QualityLevel> {
+        let cost = timings.total().as_secs_f32();
+        let smoothed = match self.smoothed_cost {
+            Some(previous) => previous + (cost - previous) * COST_SMOOTHING,
+            None => cost,
+        };
+        self.smoothed_cost = Some(smoothed);
+
+        let budget = self.budget.as_secs_f32();
+        if smoothed > budget {
+            self.over_budget_frames += 1;
+            self.headroom_frames = 0;
+        } else if smoothed < budget * HEADROOM_RATIO {
+            self.headroom_frames += 1;
+            self.over_budget_frames = 0;
+        } else {
+            self.over_budget_frames = 0;
+            self.headroom_frames = 0;
+        }
+
+        let next = if self.over_budget_frames >= DOWNGRADE_AFTER_FRAMES {
+            self.level.lower()
+        } else if self.headroom_frames >= UPGRADE_AFTER_FRAMES {
+            self.level.higher()
+        } else {
+            return None;
+        };
+        self.over_budget_frames = 0;
+        self.headroom_frames = 0;
+        if next == self.level {
+            return None;
+        }
+        self.level = next;
+        // Judge the new level on its own frames; the average still carries the cost that caused
+        // the change and would otherwise cascade a single spike down to `Minimal`.
+        self.smoothed_cost = None;
+        Some(next)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const BUDGET: Duration = Duration::from_millis(10);
+
+    fn frame(millis: f32) -> FrameTimings {
+        FrameTimings {
+            update: Duration::from_secs_f32(millis / 1000.0),
+            render: Duration::ZERO,
+        }
+    }
+
+    /// Record `count` frames costing `millis`, returning every level change.
+    fn run(controller: &mut QualityController, millis: f32, count: usize) -> Vec<QualityLevel> {
+        (0..count)
+            .filter_map(|_| controller.record(frame(millis)))
+            .collect()
+    }
+
+    #[test]
+    fn sustained_overload_steps_down_once_per_window() {
+        let mut controller = QualityController::new(BUDGET);
+        assert!(run(&mut controller, 15.0, DOWNGRADE_AFTER_FRAMES as usize - 1).is_empty());
+        assert_eq!(controller.record(frame(15.0)), Some(QualityLevel::High));
+        assert_eq!(
+            run(&mut controller, 15.0, 4 * DOWNGRADE_AFTER_FRAMES as usize),
+            [
+                QualityLevel::Medium,
+                QualityLevel::Low,
+                QualityLevel::Minimal
+            ]
+        );
+        assert!(!controller.settings().post_effects);
+    }
+
+    #[test]
+    fn headroom_steps_back_up() {
+        let mut controller = QualityController::new(BUDGET);
+        run(&mut controller, 15.0, DOWNGRADE_AFTER_FRAMES as usize);
+        assert_eq!(controller.level(), QualityLevel::High);
+        // Between the headroom line and the budget nothing changes.
+        assert!(run(&mut controller, 8.0, 2 * UPGRADE_AFTER_FRAMES as usize).is_empty());
+        // A few extra frames for the average to fall under the headroom line.
+        assert_eq!(
+            run(&mut controller, 2.0, UPGRADE_AFTER_FRAMES as usize + 10),
+            [QualityLevel::Full]
+        );
+        assert!(run(&mut controller, 2.0, 2 * UPGRADE_AFTER_FRAMES as usize).is_empty());
+    }
+
+    #[test]
+    fn one_spike_does_not_cascade() {
+        let mut controller = QualityController::new(BUDGET);
+        run(&mut controller, 5.0, 30);
+        controller.record(frame(1000.0));
+        let changes = run(&mut controller, 5.0, 100);
+        assert!(changes.len() <= 1, "{changes:?}");
+        assert!(controller.level() >= QualityLevel::High);
+    }
+
+    #[test]
+    fn budget_change_restarts_the_count() {
+        let mut controller = QualityController::new(BUDGET);
+        run(&mut controller, 15.0, DOWNGRADE_AFTER_FRAMES as usize - 1);
+        controller.set_budget(Duration::from_millis(20));
+        assert!(run(&mut controller, 15.0, 50).is_empty());
+        assert_eq!(controller.level(), QualityLevel::Full);
+    }
+}
@@ -0,0 +1,364 @@
+//! Frame pacing: drives `TrailEngine::update` at the display refresh rate with a fixed simulation step.
+
+use std::time::Duration;
+
+use serpentines_platform::FrameClock;
+
+use crate::{PowerConfig, TrailEngine};
+
+pub const DEFAULT_REFRESH_HZ: f32 = 60.0;
+pub const DEFAULT_SIMULATION_HZ: f32 = 120.0;
+/// Longest wall-clock gap a single frame may feed into the simulation (seconds).
+pub const MAX_FRAME_DT: f32 = 0.25;
+/// Upper bound on simulation steps per frame, so a slow frame can't snowball into slower ones.
+pub const MAX_STEPS_PER_FRAME: u32 = 8;
+
+/// What the frame loop should block on before the next tick.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum FrameWait {
+    /// Render again once this much time has passed.
+    Timeout(Duration),
+    /// Nothing to draw: stop rendering until input arrives, then call `FrameScheduler::wake`.
+    Input,
+}
+
+/// Outcome of one scheduler tick.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct FrameTick {
+    /// Number of fixed-size `TrailEngine::update` calls made this frame.
+    pub steps: u32,
+    /// Wall-clock time since the previous tick, after clamping (seconds).
+    pub frame_dt: f32,
+    /// True when the raw frame time exceeded `MAX_FRAME_DT` (e.g. after sleep or resume).
+    pub clamped: bool,
+    /// Leftover fraction of a step, for interpolating between simulation states when drawing.
+    pub alpha: f32,
+}
+
+pub struct FrameScheduler {
+    clock: Box<dyn FrameClock>,
+    frame_interval: Duration,
+    fixed_step: f32,
+    accumulator: f32,
+    last_tick: Option<Duration>,
+    next_deadline: Duration,
+    display_hz: f32,
+    low_power_hz: Option<f32>,
+    idle: bool,
+}
+
+impl FrameScheduler {
+    pub fn new(clock: impl FrameClock + 'static, refresh_hz: f32) -> Self {
+        let next_deadline = clock.now();
+        Self {
+            clock: Box::new(clock),
+            frame_interval: interval_for(refresh_hz),
+            fixed_step: 1.0 / DEFAULT_SIMULATION_HZ,
+            accumulator: 0.0,
+            last_tick: None,
+            next_deadline,
+            display_hz: sanitize_hz(refresh_hz, DEFAULT_REFRESH_HZ),
+            low_power_hz: None,
+            idle: false,
+        }
+    }
+
+    pub fn set_refresh_rate(&mut self, refresh_hz: f32) {
+        self.display_hz = sanitize_hz(refresh_hz, DEFAULT_REFRESH_HZ);
+        self.update_frame_interval();
+    }
+
+    /// Cap the frame rate when low-power mode is on.
+    pub fn apply_power(&mut self, power: &PowerConfig) {
+        self.low_power_hz = power
+            .low_power
+            .then(|| sanitize_hz(power.low_power_fps, DEFAULT_REFRESH_HZ));
+        self.update_frame_interval();
+    }
+
+    fn update_frame_interval(&mut self) {
+        let hz = match self.low_power_hz {
+            Some(low_power_hz) => low_power_hz.min(self.display_hz),
+            None => self.display_hz,
+        };
+        self.frame_interval = interval_for(hz);
+    }
+
+    pub fn set_simulation_rate(&mut self, simulation_hz: f32) {
+        self.fixed_step = 1.0 / sanitize_hz(simulation_hz, DEFAULT_SIMULATION_HZ);
+    }
+
+    pub fn frame_interval(&self) -> Duration {
+        self.frame_interval
+    }
+
+    pub fn fixed_step(&self) -> f32 {
+        self.fixed_step
+    }
+
+    pub fn clock(&self) -> &dyn FrameClock {
+        self.clock.as_ref()
+    }
+
+    /// Forget accumulated time, e.g. after the loop was paused. The next tick advances nothing.
+    pub fn reset(&mut self) {
+        self.accumulator = 0.0;
+        self.last_tick = None;
+        self.next_deadline = self.clock.now();
+    }
+
+    /// Measure elapsed time since the previous tick and advance the engine in fixed steps.
+    pub fn tick(&mut self, engine: &mut TrailEngine) -> FrameTick {
+        let now = self.clock.now();
+        let raw_dt = match self.last_tick {
+            Some(previous) => now.saturating_sub(previous).as_secs_f32(),
+            None => 0.0,
+        };
+        self.last_tick = Some(now);
+
+        let clamped = raw_dt > MAX_FRAME_DT;
+        let frame_dt = raw_dt.min(MAX_FRAME_DT);
+        self.accumulator += frame_dt;
+
+        let mut steps = 0;
+        while self.accumulator >= self.fixed_step && steps < MAX_STEPS_PER_FRAME {
+            engine.update(self.fixed_step);
+            self.accumulator -= self.fixed_step;
+            steps += 1;
+        }
+        if steps == MAX_STEPS_PER_FRAME {
+            // Drop whatever is left instead of carrying a backlog into the next frame.
+            self.accumulator = self.accumulator.min(self.fixed_step);
+        }
+
+        FrameTick {
+            steps,
+            frame_dt,
+            clamped,
+            alpha: self.accumulator / self.fixed_step,
+        }
+    }
+
+    pub fn is_idle(&self) -> bool {
+        self.idle
+    }
+
+    /// Decide how long the loop may block. Entering idle stops frame pacing entirely.
+    pub fn next_wait(&mut self, engine: &TrailEngine) -> FrameWait {
+        if engine.is_idle() {
+            self.idle = true;
+            FrameWait::Input
+        } else {
+            FrameWait::Timeout(self.time_until_next_frame())
+        }
+    }
+
+    /// Resume pacing after input arrives. The idle gap is not fed into the simulation.
+    pub fn wake(&mut self) {
+        if self.idle {
+            self.idle = false;
+            self.reset();
+        }
+    }
+
+    /// Time left until the next frame should start.
+    pub fn time_until_next_frame(&self) -> Duration {
+        self.next_deadline.saturating_sub(self.clock.now())
+    }
+
+    /// Sleep until the next frame boundary and schedule the one after it.
+    pub fn wait_for_next_frame(&mut self) {
+        self.clock.sleep_until(self.next_deadline);
+        let now = self.clock.now();
+        self.next_deadline += self.frame_interval;
+        if self.next_deadline <= now {
+            // Fell behind by more than a frame; re-anchor instead of bursting to catch up.
+            self.next_deadline = now + self.frame_interval;
+        }
+    }
+}
+
+/// `hz` if it is a usable rate, otherwise `fallback`.
+fn sanitize_hz(hz: f32, fallback: f32) -> f32 {
+    if hz.is_finite() && hz > 0.0 {
+        hz
+    } else {
+        fallback
+    }
+}
+
+fn interval_for(refresh_hz: f32) -> Duration {
+    Duration::from_secs_f32(1.0 / sanitize_hz(refresh_hz, DEFAULT_REFRESH_HZ))
+}
+
+#[cfg(test)]
+mod tests {
+    use glam::Vec2;
+    use serpentines_platform::ManualClock;
+
+    use super::*;
+    use crate::{EngineConfig, TrailPreset};
+
+    /// Drive `engine` the way the runtime does: one cursor sample per 60 Hz frame, moving
+    /// `speed` pixels per second to the right.
+    fn run_frames(engine: &mut TrailEngine, frames: u32, speed: f32) {
+        let mut scheduler = FrameScheduler::new(ManualClock::new(), 60.0);
+        for frame in 0..=frames {
+            scheduler.wait_for_next_frame();
+            engine.set_cursor(Vec2::new(100.0 + frame as f32 * speed / 60.0, 100.0));
+            scheduler.tick(engine);
+        }
+    }
+
+    fn engine_with_rate(rate: f32) -> TrailEngine {
+        TrailEngine::new(EngineConfig {
+            preset: TrailPreset {
+                emission_rate: rate,
+                decay_seconds: 10.0,
+                max_particles: 100_000,
+                ..TrailPreset::default()
+            },
+            ..EngineConfig::default()
+        })
+    }
+
+    #[test]
+    fn emission_matches_rate_across_substeps() {
+        for rate in [40.0, 90.0, 120.0, 500.0] {
+            let mut engine = engine_with_rate(rate);
+            run_frames(&mut engine, 60, 600.0);
+            let emitted = engine.live_particles() as f32;
+            assert!(
+                (emitted - rate).abs() <= rate * 0.05 + 1.0,
+                "rate {rate}: emitted {emitted}"
+            );
+        }
+    }
+
+    #[test]
+    fn inherited_velocity_uses_time_between_samples() {
+        let mut engine = engine_with_rate(120.0);
+        run_frames(&mut engine, 10, 600.0);
+        let newest = engine.particles().last().expect("particles emitted");
+        // 600 px/s inherited at 10%, minus one substep of damping.
+        assert!(
+            (50.0..=61.0).contains(&newest.vel.x),
+            "velocity {}",
+            newest.vel.x
+        );
+    }
+
+    #[test]
+    fn resting_cursor_does_not_burst_on_resume() {
+        let mut engine = engine_with_rate(120.0);
+        let mut scheduler = FrameScheduler::new(ManualClock::new(), 60.0);
+        engine.set_cursor(Vec2::new(0.0, 0.0));
+        for _ in 0..60 {
+            scheduler.wait_for_next_frame();
+            scheduler.tick(&mut engine);
+        }
+        engine.clear();
+        scheduler.wait_for_next_frame();
+        engine.set_cursor(Vec2::new(10.0, 0.0));
+        scheduler.tick(&mut engine);
+        assert!(
+            engine.live_particles() <= 2,
+            "emitted {}",
+            engine.live_particles()
+        );
+    }
+
+    fn low_power(fps: f32) -> PowerConfig {
+        PowerConfig {
+            low_power: true,
+            low_power_fps: fps,
+            ..PowerConfig::default()
+        }
+    }
+
+    #[test]
+    fn invalid_rates_fall_back_to_their_own_defaults() {
+        let mut scheduler = FrameScheduler::new(ManualClock::new(), f32::NAN);
+        assert_eq!(scheduler.frame_interval(), interval_for(DEFAULT_REFRESH_HZ));
+
+        scheduler.set_simulation_rate(240.0);
+        assert_eq!(scheduler.fixed_step(), 1.0 / 240.0);
+        scheduler.set_simulation_rate(0.0);
+        assert_eq!(scheduler.fixed_step(), 1.0 / DEFAULT_SIMULATION_HZ);
+
+        scheduler.set_refresh_rate(144.0);
+        scheduler.set_refresh_rate(-1.0);
+        assert_eq!(scheduler.frame_interval(), interval_for(DEFAULT_REFRESH_HZ));
+    }
+
+    #[test]
+    fn low_power_caps_but_never_raises_the_frame_rate() {
+        let mut scheduler = FrameScheduler::new(ManualClock::new(), 144.0);
+        assert_eq!(scheduler.frame_interval(), interval_for(144.0));
+
+        scheduler.apply_power(&low_power(30.0));
+        assert_eq!(scheduler.frame_interval(), interval_for(30.0));
+        scheduler.apply_power(&low_power(240.0));
+        assert_eq!(scheduler.frame_interval(), interval_for(144.0));
+        scheduler.apply_power(&PowerConfig::default());
+        assert_eq!(scheduler.frame_interval(), interval_for(144.0));
+    }
+
+    #[test]
+    fn tick_runs_fixed_steps_and_clamps_long_gaps() {
+        let clock = ManualClock::new();
+        let mut scheduler = FrameScheduler::new(clock.clone(), 60.0);
+        let mut engine = TrailEngine::new(EngineConfig::default());
+        assert_eq!(scheduler.tick(&mut engine).steps, 0);
+
+        clock.advance(Duration::from_secs_f32(2.0 / DEFAULT_SIMULATION_HZ));
+        let tick = scheduler.tick(&mut engine);
+        assert_eq!(tick.steps, 2);
+        assert!(!tick.clamped);
+
+        clock.advance(Duration::from_secs(5));
+        let tick = scheduler.tick(&mut engine);
+        assert!(tick.clamped);
+        assert_eq!(tick.frame_dt, MAX_FRAME_DT);
+        assert_eq!(tick.steps, MAX_STEPS_PER_FRAME);
+        assert!(
+            tick.alpha <= 1.0,
+            "backlog carried over: alpha {}",
+            tick.alpha
+        );
+    }
+
+    #[test]
+    fn falling_behind_reanchors_the_deadline() {
+        let clock = ManualClock::new();
+        let mut scheduler = FrameScheduler::new(clock.clone(), 60.0);
+        scheduler.wait_for_next_frame();
+        assert_eq!(
+            scheduler.time_until_next_frame(),
+            scheduler.frame_interval()
+        );
+
+        clock.advance(Duration::from_secs(1));
+        scheduler.wait_for_next_frame();
+        assert_eq!(
+            scheduler.time_until_next_frame(),
+            scheduler.frame_interval()
+        );
+    }
+
+    #[test]
+    fn idle_gap_is_not_simulated_after_wake() {
+        let clock = ManualClock::new();
+        let mut scheduler = FrameScheduler::new(clock.clone(), 60.0);
+        let mut engine = TrailEngine::new(EngineConfig::default());
+        scheduler.tick(&mut engine);
+
+        assert_eq!(scheduler.next_wait(&engine), FrameWait::Input);
+        assert!(scheduler.is_idle());
+        clock.advance(Duration::from_secs(10));
+        scheduler.wake();
+        assert!(!scheduler.is_idle());
+        let tick = scheduler.tick(&mut engine);
+        assert_eq!((tick.steps, tick.frame_dt), (0, 0.0));
+    }
+}
@@ -0,0 +1,255 @@
+//! Snapshot of engine state for the control panel and the performance HUD.
+
+use std::collections::VecDeque;
+use std::time::Duration;
+
+use serde::{Deserialize, Serialize};
+use serpentines_platform::{MonitorId, RendererStats};
+
+use crate::{FrameTimings, QualityLevel, TrailEngine};
+
+/// Frames kept by `FrameHistory` by default, a few seconds at typical refresh rates.
+pub const DEFAULT_HISTORY_FRAMES: usize = 240;
+/// Frame times included in `EngineStats::recent_frame_ms` for plotting.
+const RECENT_FRAMES: usize = 120;
+
+/// Rolling window of recent frame timings.
+#[derive(Debug, Clone)]
+pub struct FrameHistory {
+    samples: VecDeque<FrameTimings>,
+    capacity: usize,
+}
+
+impl Default for FrameHistory {
+    fn default() -> Self {
+        Self::new(DEFAULT_HISTORY_FRAMES)
+    }
+}
+
+impl FrameHistory {
+    pub fn new(capacity: usize) -> Self {
+        let capacity = capacity.max(1);
+        Self {
+            samples: VecDeque::with_capacity(capacity),
+            capacity,
+        }
+    }
+
+    pub fn record(&mut self, timings: FrameTimings) {
+        if self.samples.len() == self.capacity {
+            self.samples.pop_front();
+        }
+        self.samples.push_back(timings);
+    }
+
+    pub fn len(&self) -> usize {
+        self.samples.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.samples.is_empty()
+    }
+
+    pub fn clear(&mut self) {
+        self.samples.clear();
+    }
+
+    /// Total frame time at percentile `p` (0..=100), nearest-rank.
+    pub fn percentile(&self, p: f32) -> Duration {
+        if self.samples.is_empty() {
+            return Duration::ZERO;
+        }
+        let mut totals: Vec<Duration> = self.samples.iter().map(FrameTimings::total).collect();
+        totals.sort_unstable();
+        let rank = ((p.clamp(0.0, 100.0) / 100.0) * totals.len() as f32).ceil() as usize;
+        totals[rank.clamp(1, totals.len()) - 1]
+    }
+
+    /// Average update and render time over the window.
+    pub fn mean(&self) -> FrameTimings {
+        let count = self.samples.len().max(1) as u32;
+        let (update, render) = self.samples.iter().fold(
+            (Duration::ZERO, Duration::ZERO),
+            |(update, render), timings| (update + timings.update, render + timings.render),
+        );
+        FrameTimings {
+            update: update / count,
+            render: render / count,
+        }
+    }
+
+    /// Total frame times, oldest first, in milliseconds.
+    pub fn totals_ms(&self) -> impl Iterator<Item = f32> + '_ {
+        self.samples
+            .iter()
+            .map(|timings| timings.total().as_secs_f32() * 1000.0)
+    }
+}
+
+/// Frame time distribution in milliseconds.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Serialize, Deserialize)]
+pub struct FrameTimePercentiles {
+    pub p50_ms: f32,
+    pub p95_ms: f32,
+    pub p99_ms: f32,
+    pub max_ms: f32,
+}
+
+impl FrameTimePercentiles {
+    pub fn from_history(history: &FrameHistory) -> Self {
+        let ms = |p| history.percentile(p).as_secs_f32() * 1000.0;
+        Self {
+            p50_ms: ms(50.0),
+            p95_ms: ms(95.0),
+            p99_ms: ms(99.0),
+            max_ms: ms(100.0),
+        }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct EngineStats {
+    pub enabled: bool,
+    pub paused: bool,
+    pub live_particles: usize,
+    /// Particle cap after power and quality settings.
+    pub max_particles: u32,
+    pub emission_rate: f32,
+    pub quality: QualityLevel,
+    /// Rendered frames per second over the last reporting interval.
+    pub fps: f32,
+    /// Smoothed update + render cost per frame, in milliseconds.
+    pub frame_cost_ms: f32,
+    pub active_monitor: Option<MonitorId>,
+    #[serde(default)]
+    pub frame_time: FrameTimePercentiles,
+    /// Mean simulation time per frame, in milliseconds.
+    #[serde(default)]
+    pub update_ms: f32,
+    /// Mean render submission time per frame, in milliseconds.
+    #[serde(default)]
+    pub render_ms: f32,
+    #[serde(default)]
+    pub renderer: RendererStats,
+    /// Most recent total frame times in milliseconds, oldest first.
+    #[serde(default)]
+    pub recent_frame_ms: Vec<f32>,
+}
+
+impl EngineStats {
+    /// Engine-side fields; the frame loop fills in the rest.
+    pub fn from_engine(engine: &TrailEngine) -> Self {
+        Self {
+            enabled: engine.config.enabled,
+            paused: false,
+            live_particles: engine.live_particles(),
+            max_particles: engine.effective_max_particles(),
+            emission_rate: engine.effective_emission_rate(),
+            quality: QualityLevel::Full,
+            fps: 0.0,
+            frame_cost_ms: 0.0,
+            active_monitor: engine.active_monitor(),
+            frame_time: FrameTimePercentiles::default(),
+            update_ms: 0.0,
+            render_ms: 0.0,
+            renderer: RendererStats::default(),
+            recent_frame_ms: Vec::new(),
+        }
+    }
+
+    /// Fill in the timing fields from the frame loop's history.
+    pub fn with_history(mut self, history: &FrameHistory) -> Self {
+        let mean = history.mean();
+        self.frame_time = FrameTimePercentiles::from_history(history);
+        self.update_ms = mean.update.as_secs_f32() * 1000.0;
+        self.render_ms = mean.render.as_secs_f32() * 1000.0;
+        let skip = history.len().saturating_sub(RECENT_FRAMES);
+        self.recent_frame_ms = history.totals_ms().skip(skip).collect();
+        self
+    }
+
+    /// Short text summary, as shown by the overlay HUD.
+    pub fn hud_lines(&self) -> Vec<String> {
+        let mut lines = vec![
+            format!("{:.0} fps  quality {}", self.fps, self.quality),
+            format!(
+                "frame p50 {:.2}  p95 {:.2}  p99 {:.2} ms",
+                self.frame_time.p50_ms, self.frame_time.p95_ms, self.frame_time.p99_ms
+            ),
+            format!(
+                "update {:.2}  render {:.2} ms",
+                self.update_ms, self.render_ms
+            ),
+            format!("particles {} / {}", self.live_particles, self.max_particles),
+        ];
+        if self.paused {
+            lines.push("paused".into());
+        }
+        lines
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn frame(update_ms: u64, render_ms: u64) -> FrameTimings {
+        FrameTimings {
+            update: Duration::from_millis(update_ms),
+            render: Duration::from_millis(render_ms),
+        }
+    }
+
+    fn history_of(totals_ms: impl IntoIterator<Item = u64>) -> FrameHistory {
+        let mut history = FrameHistory::new(DEFAULT_HISTORY_FRAMES);
+        for total in totals_ms {
+            history.record(frame(0, total));
+        }
+        history
+    }
+
+    #[test]
+    fn percentile_uses_nearest_rank() {
+        let history = history_of((1..=10).rev());
+        let ms = |p| history.percentile(p).as_millis();
+        assert_eq!(ms(0.0), 1);
+        assert_eq!(ms(10.0), 1);
+        assert_eq!(ms(11.0), 2);
+        assert_eq!(ms(50.0), 5);
+        assert_eq!(ms(95.0), 10);
+        assert_eq!(ms(100.0), 10);
+        assert_eq!(ms(250.0), 10);
+        assert_eq!(FrameHistory::default().percentile(50.0), Duration::ZERO);
+    }
+
+    #[test]
+    fn mean_averages_each_half() {
+        let mut history = FrameHistory::default();
+        assert_eq!(history.mean(), FrameTimings::default());
+        history.record(frame(2, 4));
+        history.record(frame(4, 8));
+        assert_eq!(history.mean(), frame(3, 6));
+    }
+
+    #[test]
+    fn window_evicts_the_oldest_frames() {
+        let mut history = FrameHistory::new(3);
+        for total in [50, 1, 2, 3] {
+            history.record(frame(0, total));
+        }
+        assert_eq!(history.len(), 3);
+        assert_eq!(history.totals_ms().collect::<Vec<_>>(), [1.0, 2.0, 3.0]);
+        assert_eq!(history.percentile(100.0), Duration::from_millis(3));
+        assert_eq!(FrameHistory::new(0).capacity, 1);
+    }
+
+    #[test]
+    fn stats_plot_only_the_most_recent_frames() {
+        let history = history_of(1..=(RECENT_FRAMES as u64 + 5));
+        let stats =
+            EngineStats::from_engine(&TrailEngine::new(Default::default())).with_history(&history);
+        assert_eq!(stats.recent_frame_ms.len(), RECENT_FRAMES);
+        assert_eq!(stats.recent_frame_ms[0], 6.0);
+        assert_eq!(stats.frame_time.max_ms, RECENT_FRAMES as f32 + 5.0);
+    }
+}
@@ -0,0 +1,126 @@
+//! Offscreen software rendering of presets, for thumbnails and exports.
+
+use glam::{Vec2, Vec3};
+
+use crate::{EngineConfig, TrailEngine, TrailPreset};
+
+/// Width of the logical canvas the animation runs on; output is scaled to the image size.
+const CANVAS_WIDTH: f32 = 320.0;
+/// Simulation step, matching a 60 Hz display.
+const STEP_SECONDS: f32 = 1.0 / 60.0;
+/// Seconds per loop of the demo path.
+pub const DEMO_PERIOD: f32 = 4.0;
+/// Simulated time before a still thumbnail is taken, so the trail has built up.
+const THUMBNAIL_WARMUP: f32 = DEMO_PERIOD * 0.6;
+const BACKGROUND: Vec3 = Vec3::new(0.06, 0.06, 0.07);
+
+/// Point on a figure-eight filling `size`, for `t` in 0..1.
+pub fn demo_path(t: f32, size: Vec2) -> Vec2 {
+    let angle = t * std::f32::consts::TAU;
+    let center = size * 0.5;
+    let radius = size * Vec2::new(0.4, 0.35);
+    center + radius * Vec2::new(angle.sin(), (angle * 2.0).sin())
+}
+
+/// Opaque RGBA8 pixels, row-major.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Image {
+    pub width: u32,
+    pub height: u32,
+    pub pixels: Vec<u8>,
+}
+
+/// A preset's trail following the demo path, rendered frame by frame in software.
+pub struct PresetAnimation {
+    engine: TrailEngine,
+    width: u32,
+    height: u32,
+    canvas: Vec2,
+    time: f32,
+    /// Simulated time not yet consumed by a whole step.
+    carry: f32,
+}
+
+impl PresetAnimation {
+    pub fn new(preset: &TrailPreset, width: u32, height: u32) -> Self {
+        let width = width.max(1);
+        let height = height.max(1);
+        Self {
+            engine: TrailEngine::new(EngineConfig {
+                preset: preset.clone(),
+                ..EngineConfig::default()
+            }),
+            width,
+            height,
+            canvas: Vec2::new(CANVAS_WIDTH, CANVAS_WIDTH * height as f32 / width as f32),
+            time: 0.0,
+            carry: 0.0,
+        }
+    }
+
+    pub fn time(&self) -> f32 {
+        self.time
+    }
+
+    /// Simulate `seconds` more of the animation in fixed steps.
+    pub fn advance(&mut self, seconds: f32) {
+        self.carry += seconds.max(0.0);
+        while self.carry >= STEP_SECONDS {
+            self.carry -= STEP_SECONDS;
+            self.time += STEP_SECONDS;
+            let t = (self.time % DEMO_PERIOD) / DEMO_PERIOD;
+            self.engine.set_cursor(demo_path(t, self.canvas));
+            self.engine.update(STEP_SECONDS);
+        }
+    }
+
+    /// Rasterize the current particles as soft-edged discs over a dark background.
+    pub fn render(&self) -> Image {
+        let (width, height) = (self.width as usize, self.height as usize);
+        let scale = self.width as f32 / self.canvas.x;
+        let mut buffer = vec![BACKGROUND; width * height];
+        for instance in self.engine.instances() {
+            let center = Vec2::new(instance.x, instance.y) * scale;
+            let radius = (instance.size * 0.5 * scale).max(0.5);
+            let [r, g, b, a] = instance.color;
+            let color = Vec3::new(r, g, b);
+            let min_x = ((center.x - radius - 1.0).floor().max(0.0)) as usize;
+            let min_y = ((center.y - radius - 1.0).floor().max(0.0)) as usize;
+            let max_x = ((center.x + radius + 1.0).ceil().max(0.0) as usize).min(width);
+            let max_y = ((center.y + radius + 1.0).ceil().max(0.0) as usize).min(height);
+            for y in min_y..max_y {
+                for x in min_x..max_x {
+                    let pixel = Vec2::new(x as f32 + 0.5, y as f32 + 0.5);
+                    let coverage = (radius + 0.5 - pixel.distance(center)).clamp(0.0, 1.0);
+                    let alpha = a.clamp(0.0, 1.0) * coverage;
+                    if alpha > 0.0 {
+                        let dst = &mut buffer[y * width + x];
+                        *dst = dst.lerp(color, alpha);
+                    }
+                }
+            }
+        }
+        let pixels = buffer
+            .iter()
+            .flat_map(|color| {
+                let [r, g, b] = color
+                    .clamp(Vec3::ZERO, Vec3::ONE)
+                    .to_array()
+                    .map(|c| (c * 255.0).round() as u8);
+                [r, g, b, 255]
+            })
+            .collect();
+        Image {
+            width: self.width,
+            height: self.height,
+            pixels,
+        }
+    }
+}
+
+/// Still image of `preset` mid-way through the demo path.
+pub fn render_thumbnail(preset: &TrailPreset, width: u32, height: u32) -> Image {
+    let mut animation = PresetAnimation::new(preset, width, height);
+    animation.advance(THUMBNAIL_WARMUP);
+    animation.render()
+}
@@ -0,0 +1,113 @@
+use serde::{Deserialize, Serialize};
+
+use crate::{Platform, Result};
+
+/// What a backend can do in the current session.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct Capabilities {
+    /// Cursor position is observed system-wide, not only over our own windows.
+    pub global_input: bool,
+    /// Overlays let clicks and hover through to the windows below.
+    pub click_through_overlays: bool,
+    /// Each monitor reports its own DPI rather than one desktop-wide scale.
+    pub per_monitor_dpi: bool,
+    pub tray: bool,
+    pub hotkeys: bool,
+}
+
+impl Capabilities {
+    /// Names of the capabilities that are present, for logging.
+    pub fn names(&self) -> Vec<&'static str> {
+        [
+            (self.global_input, "global input"),
+            (self.click_through_overlays, "click-through overlays"),
+            (self.per_monitor_dpi, "per-monitor DPI"),
+            (self.tray, "tray"),
+            (self.hotkeys, "hotkeys"),
+        ]
+        .into_iter()
+        .filter_map(|(present, name)| present.then_some(name))
+        .collect()
+    }
+}
+
+/// A compiled-in platform backend.
+pub struct PlatformBackend {
+    pub name: &'static str,
+    /// Higher wins when several backends are usable.
+    pub priority: i32,
+    /// Runtime check that the backend can work in this session (display server present, etc.).
+    pub detect: fn() -> bool,
+    /// Capabilities in this session; may probe the environment.
+    pub capabilities: fn() -> Capabilities,
+    /// Connect to the display server and build the platform pieces the runtime drives.
+    pub create: fn() -> Result<Platform>,
+}
+
+/// Backends compiled into the app, in registration order.
+#[derive(Default)]
+pub struct BackendRegistry {
+    backends: Vec<PlatformBackend>,
+}
+
+impl BackendRegistry {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn register(&mut self, backend: PlatformBackend) {
+        self.backends.push(backend);
+    }
+
+    pub fn backends(&self) -> &[PlatformBackend] {
+        &self.backends
+    }
+
+    pub fn get(&self, name: &str) -> Option<&PlatformBackend> {
+        self.backends
+            .iter()
+            .find(|backend| backend.name.eq_ignore_ascii_case(name))
+    }
+
+    /// Backends whose runtime detection passes, highest priority first.
+    pub fn available(&self) -> Vec<&PlatformBackend> {
+        let mut available: Vec<&PlatformBackend> = self
+            .backends
+            .iter()
+            .filter(|backend| (backend.detect)())
+            .collect();
+        available.sort_by_key(|backend| std::cmp::Reverse(backend.priority));
+        available
+    }
+
+    /// Pick `preferred` if given (it must be compiled in and detected), else the best available one.
+    pub fn select(&self, preferred: Option<&str>) -> Result<&PlatformBackend> {
+        if let Some(name) = preferred {
+            let backend = self.get(name).ok_or_else(|| {
+                format!(
+                    "backend '{name}' is not compiled in (available: {})",
+                    self.names().join(", ")
+                )
+            })?;
+            if !(backend.detect)() {
+                return Err(format!("backend '{name}' is not usable in this session").into());
+            }
+            return Ok(backend);
+        }
+        self.available().into_iter().next().ok_or_else(|| {
+            if self.backends.is_empty() {
+                "no platform backend compiled in for this target".into()
+            } else {
+                format!(
+                    "none of the compiled-in backends ({}) is usable",
+                    self.names().join(", ")
+                )
+                .into()
+            }
+        })
+    }
+
+    fn names(&self) -> Vec<&'static str> {
+        self.backends.iter().map(|backend| backend.name).collect()
+    }
+}
@@ -0,0 +1,83 @@
+use std::sync::{Arc, Mutex};
+use std::time::{Duration, Instant};
+
+/// Monotonic time source used to pace frames.
+///
+/// Times are expressed as a [`Duration`] since an arbitrary, clock-specific epoch.
+pub trait FrameClock: Send + Sync {
+    fn now(&self) -> Duration;
+    /// Block the calling thread until `deadline` (in this clock's time) has passed.
+    fn sleep_until(&self, deadline: Duration);
+}
+
+/// Real clock backed by [`Instant`].
+pub struct MonotonicClock {
+    epoch: Instant,
+}
+
+impl MonotonicClock {
+    pub fn new() -> Self {
+        Self {
+            epoch: Instant::now(),
+        }
+    }
+}
+
+impl Default for MonotonicClock {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl FrameClock for MonotonicClock {
+    fn now(&self) -> Duration {
+        self.epoch.elapsed()
+    }
+
+    fn sleep_until(&self, deadline: Duration) {
+        let now = self.now();
+        if deadline > now {
+            std::thread::sleep(deadline - now);
+        }
+    }
+}
+
+/// Manually driven clock for tests. Clones share the same time.
+///
+/// `sleep_until` never blocks; it jumps the clock forward to the deadline instead.
+#[derive(Clone, Default)]
+pub struct ManualClock {
+    now: Arc<Mutex<Duration>>,
+}
+
+impl ManualClock {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn advance(&self, delta: Duration) {
+        if let Ok(mut now) = self.now.lock() {
+            *now += delta;
+        }
+    }
+
+    pub fn set(&self, time: Duration) {
+        if let Ok(mut now) = self.now.lock() {
+            *now = time;
+        }
+    }
+}
+
+impl FrameClock for ManualClock {
+    fn now(&self) -> Duration {
+        self.now.lock().map(|now| *now).unwrap_or_default()
+    }
+
+    fn sleep_until(&self, deadline: Duration) {
+        if let Ok(mut now) = self.now.lock() {
+            if deadline > *now {
+                *now = deadline;
+            }
+        }
+    }
+}
@@ -0,0 +1,150 @@
+use serde::{Deserialize, Serialize};
+
+use crate::MonitorRect;
+
+/// Axis-aligned region that changed since the last presented frame, in desktop pixels.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub struct DamageRect {
+    pub x: i32,
+    pub y: i32,
+    pub width: i32,
+    pub height: i32,
+}
+
+impl DamageRect {
+    pub fn new(x: i32, y: i32, width: i32, height: i32) -> Self {
+        Self {
+            x,
+            y,
+            width,
+            height,
+        }
+    }
+
+    /// Smallest integer rect covering the given float bounds.
+    pub fn from_bounds(min_x: f32, min_y: f32, max_x: f32, max_y: f32) -> Self {
+        let x = min_x.floor() as i32;
+        let y = min_y.floor() as i32;
+        Self {
+            x,
+            y,
+            width: max_x.ceil() as i32 - x,
+            height: max_y.ceil() as i32 - y,
+        }
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.width <= 0 || self.height <= 0
+    }
+
+    pub fn area(&self) -> i64 {
+        if self.is_empty() {
+            0
+        } else {
+            self.width as i64 * self.height as i64
+        }
+    }
+
+    pub fn right(&self) -> i32 {
+        self.x + self.width
+    }
+
+    pub fn bottom(&self) -> i32 {
+        self.y + self.height
+    }
+
+    pub fn union(&self, other: &DamageRect) -> DamageRect {
+        if self.is_empty() {
+            return *other;
+        }
+        if other.is_empty() {
+            return *self;
+        }
+        let x = self.x.min(other.x);
+        let y = self.y.min(other.y);
+        DamageRect {
+            x,
+            y,
+            width: self.right().max(other.right()) - x,
+            height: self.bottom().max(other.bottom()) - y,
+        }
+    }
+
+    pub fn intersect(&self, other: &DamageRect) -> Option<DamageRect> {
+        let x = self.x.max(other.x);
+        let y = self.y.max(other.y);
+        let rect = DamageRect {
+            x,
+            y,
+            width: self.right().min(other.right()) - x,
+            height: self.bottom().min(other.bottom()) - y,
+        };
+        (!rect.is_empty()).then_some(rect)
+    }
+
+    /// Part of this rect on `monitor`, translated into that monitor's local pixel space.
+    pub fn clip_to_monitor(&self, monitor: &MonitorRect) -> Option<DamageRect> {
+        let bounds = DamageRect::new(monitor.x, monitor.y, monitor.width, monitor.height);
+        self.intersect(&bounds).map(|rect| DamageRect {
+            x: rect.x - monitor.x,
+            y: rect.y - monitor.y,
+            ..rect
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::MonitorId;
+
+    #[test]
+    fn from_bounds_covers_fractional_edges() {
+        assert_eq!(
+            DamageRect::from_bounds(1.5, -2.5, 3.2, 4.0),
+            DamageRect::new(1, -3, 3, 7)
+        );
+    }
+
+    #[test]
+    fn union_ignores_empty_rects() {
+        let a = DamageRect::new(0, 0, 10, 10);
+        let b = DamageRect::new(20, -5, 5, 5);
+        let empty = DamageRect::new(100, 100, 0, 3);
+        assert_eq!(a.union(&b), DamageRect::new(0, -5, 25, 15));
+        assert_eq!(a.union(&empty), a);
+        assert_eq!(empty.union(&a), a);
+        assert_eq!(empty.area(), 0);
+        assert_eq!(DamageRect::new(0, 0, -4, 5).area(), 0);
+    }
+
+    #[test]
+    fn intersect_requires_overlap() {
+        let a = DamageRect::new(0, 0, 10, 10);
+        assert_eq!(
+            a.intersect(&DamageRect::new(5, 5, 10, 10)),
+            Some(DamageRect::new(5, 5, 5, 5))
+        );
+        // Touching edges share no pixels.
+        assert_eq!(a.intersect(&DamageRect::new(10, 0, 10, 10)), None);
+    }
+
+    #[test]
+    fn clip_to_monitor_returns_local_pixels() {
+        let monitor = MonitorRect {
+            id: MonitorId::from_key("left"),
+            x: -1920,
+            y: 0,
+            width: 1920,
+            height: 1080,
+            dpi: 96,
+            refresh_hz: None,
+        };
+        let rect = DamageRect::new(-10, 100, 20, 20);
+        assert_eq!(
+            rect.clip_to_monitor(&monitor),
+            Some(DamageRect::new(1910, 100, 10, 20))
+        );
+        assert_eq!(DamageRect::new(5, 0, 5, 5).clip_to_monitor(&monitor), None);
+    }
+}
@@ -0,0 +1,199 @@
+use std::collections::HashMap;
+use std::sync::mpsc::{channel, Receiver, Sender};
+
+use serde::{Deserialize, Serialize};
+
+use crate::{MonitorId, MonitorRect};
+
+/// Fan-out list of channel subscribers. Disconnected receivers are pruned on publish.
+pub struct Subscribers<T> {
+    senders: Vec<Sender<T>>,
+}
+
+impl<T: Clone> Subscribers<T> {
+    pub fn new() -> Self {
+        Self {
+            senders: Vec::new(),
+        }
+    }
+
+    pub fn subscribe(&mut self) -> Receiver<T> {
+        let (sender, receiver) = channel();
+        self.senders.push(sender);
+        receiver
+    }
+
+    pub fn publish(&mut self, event: &T) {
+        self.senders
+            .retain(|sender| sender.send(event.clone()).is_ok());
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.senders.is_empty()
+    }
+}
+
+impl<T: Clone> Default for Subscribers<T> {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+/// Global input observed by an `InputSource`, in desktop pixels.
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+pub enum InputEvent {
+    CursorMoved { x: f32, y: f32 },
+    Button { button: MouseButton, pressed: bool },
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Serialize, Deserialize)]
+pub enum MouseButton {
+    Left,
+    Right,
+    Middle,
+    Other(u16),
+}
+
+/// Change in the monitor topology reported by an `OverlayManager`.
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+pub enum MonitorEvent {
+    Added {
+        new: MonitorRect,
+    },
+    Removed {
+        old: MonitorRect,
+    },
+    /// Origin and/or size changed.
+    Moved {
+        old: MonitorRect,
+        new: MonitorRect,
+    },
+    DpiChanged {
+        old: MonitorRect,
+        new: MonitorRect,
+    },
+}
+
+/// Events turning `old` into `new`, matching monitors by `MonitorId`. A monitor whose geometry
+/// and DPI both changed yields a `Moved` followed by a `DpiChanged`.
+pub fn diff_monitors(old: &[MonitorRect], new: &[MonitorRect]) -> Vec<MonitorEvent> {
+    let previous: HashMap<MonitorId, &MonitorRect> =
+        old.iter().map(|rect| (rect.id, rect)).collect();
+    let current: HashMap<MonitorId, &MonitorRect> =
+        new.iter().map(|rect| (rect.id, rect)).collect();
+    let mut events = Vec::new();
+
+    for rect in old {
+        if !current.contains_key(&rect.id) {
+            events.push(MonitorEvent::Removed { old: *rect });
+        }
+    }
+    for rect in new {
+        let Some(before) = previous.get(&rect.id) else {
+            events.push(MonitorEvent::Added { new: *rect });
+            continue;
+        };
+        let geometry_changed = before.x != rect.x
+            || before.y != rect.y
+            || before.width != rect.width
+            || before.height != rect.height;
+        if geometry_changed {
+            events.push(MonitorEvent::Moved {
+                old: **before,
+                new: MonitorRect {
+                    dpi: before.dpi,
+                    ..*rect
+                },
+            });
+        }
+        if before.dpi != rect.dpi {
+            events.push(MonitorEvent::DpiChanged {
+                old: MonitorRect {
+                    dpi: before.dpi,
+                    ..*rect
+                },
+                new: *rect,
+            });
+        }
+    }
+    events
+}
+
+/// Requests from the desktop shell to the app that don't come through the tray menu.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ShellEvent {
+    OpenSettings,
+    Quit,
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn rect(key: &str, x: i32, width: i32, dpi: u32) -> MonitorRect {
+        MonitorRect {
+            id: MonitorId::from_key(key),
+            x,
+            y: 0,
+            width,
+            height: 1080,
+            dpi,
+            refresh_hz: None,
+        }
+    }
+
+    #[test]
+    fn same_monitors_in_any_order_yield_nothing() {
+        let old = [rect("left", 0, 1920, 96), rect("right", 1920, 1920, 96)];
+        let new = [old[1], old[0]];
+        assert!(diff_monitors(&old, &new).is_empty());
+    }
+
+    #[test]
+    fn added_and_removed_are_matched_by_id() {
+        let left = rect("left", 0, 1920, 96);
+        let right = rect("right", 1920, 1920, 96);
+        // Same geometry under a different id is a different monitor.
+        let replacement = rect("replacement", 1920, 1920, 96);
+        assert_eq!(
+            diff_monitors(&[left, right], &[left, replacement]),
+            [
+                MonitorEvent::Removed { old: right },
+                MonitorEvent::Added { new: replacement },
+            ]
+        );
+    }
+
+    #[test]
+    fn geometry_and_dpi_changes_are_separate_events() {
+        let old = rect("main", 0, 1920, 96);
+
+        let moved = rect("main", -1920, 1920, 96);
+        assert_eq!(
+            diff_monitors(&[old], &[moved]),
+            [MonitorEvent::Moved { old, new: moved }]
+        );
+
+        let rescaled = rect("main", 0, 1920, 144);
+        assert_eq!(
+            diff_monitors(&[old], &[rescaled]),
+            [MonitorEvent::DpiChanged { old, new: rescaled }]
+        );
+
+        let both = rect("main", 100, 2560, 144);
+        let moved_at_old_dpi = rect("main", 100, 2560, 96);
+        assert_eq!(
+            diff_monitors(&[old], &[both]),
+            [
+                MonitorEvent::Moved {
+                    old,
+                    new: moved_at_old_dpi
+                },
+                MonitorEvent::DpiChanged {
+                    old: moved_at_old_dpi,
+                    new: both
+                },
+            ]
+        );
+    }
+}
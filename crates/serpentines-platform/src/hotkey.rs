@@ -0,0 +1,368 @@
+use std::fmt;
+use std::str::FromStr;
+use std::sync::mpsc::Receiver;
+
+use serde::{Deserialize, Serialize};
+
+use crate::Result;
+
+/// Modifier keys held for a chord.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Hash, PartialOrd, Ord)]
+pub struct Modifiers {
+    pub ctrl: bool,
+    pub alt: bool,
+    pub shift: bool,
+    /// Windows / Command / Super key.
+    pub super_key: bool,
+}
+
+impl Modifiers {
+    pub fn is_empty(&self) -> bool {
+        !(self.ctrl || self.alt || self.shift || self.super_key)
+    }
+}
+
+/// Non-modifier key of a chord.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, PartialOrd, Ord)]
+pub enum Key {
+    /// `A`-`Z` or `0`-`9`, always stored uppercase.
+    Char(char),
+    /// `F1`-`F24`.
+    F(u8),
+    Space,
+    Tab,
+    Enter,
+    Escape,
+    Backspace,
+    Delete,
+    Insert,
+    Home,
+    End,
+    PageUp,
+    PageDown,
+    Up,
+    Down,
+    Left,
+    Right,
+}
+
+const NAMED_KEYS: &[(&str, Key)] = &[
+    ("Space", Key::Space),
+    ("Tab", Key::Tab),
+    ("Enter", Key::Enter),
+    ("Escape", Key::Escape),
+    ("Backspace", Key::Backspace),
+    ("Delete", Key::Delete),
+    ("Insert", Key::Insert),
+    ("Home", Key::Home),
+    ("End", Key::End),
+    ("PageUp", Key::PageUp),
+    ("PageDown", Key::PageDown),
+    ("Up", Key::Up),
+    ("Down", Key::Down),
+    ("Left", Key::Left),
+    ("Right", Key::Right),
+];
+
+/// Alternate spellings accepted when parsing.
+const KEY_ALIASES: &[(&str, Key)] = &[
+    ("Return", Key::Enter),
+    ("Esc", Key::Escape),
+    ("Del", Key::Delete),
+    ("Ins", Key::Insert),
+    ("PgUp", Key::PageUp),
+    ("PgDn", Key::PageDown),
+];
+
+impl Key {
+    fn parse(token: &str) -> Option<Self> {
+        let mut chars = token.chars();
+        if let (Some(c), None) = (chars.next(), chars.next()) {
+            return c
+                .is_ascii_alphanumeric()
+                .then(|| Key::Char(c.to_ascii_uppercase()));
+        }
+        if let Some(number) = token
+            .strip_prefix(['F', 'f'])
+            .and_then(|digits| digits.parse::<u8>().ok())
+        {
+            return (1..=24).contains(&number).then_some(Key::F(number));
+        }
+        NAMED_KEYS
+            .iter()
+            .chain(KEY_ALIASES)
+            .find(|(name, _)| name.eq_ignore_ascii_case(token))
+            .map(|(_, key)| *key)
+    }
+}
+
+impl fmt::Display for Key {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Key::Char(c) => write!(f, "{c}"),
+            Key::F(number) => write!(f, "F{number}"),
+            named => {
+                let name = NAMED_KEYS
+                    .iter()
+                    .find(|(_, key)| key == named)
+                    .map_or("?", |(name, _)| name);
+                f.write_str(name)
+            }
+        }
+    }
+}
+
+/// Why a chord string was rejected.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum ChordError {
+    Empty,
+    UnknownKey(String),
+    UnknownModifier(String),
+    DuplicateModifier(String),
+    /// Only modifiers, e.g. `Ctrl+Alt`.
+    MissingKey,
+    /// A plain key would swallow normal typing system-wide. Function keys are exempt.
+    NoModifier,
+    /// Taken by the OS (e.g. `Ctrl+Alt+Delete`) and never delivered to apps.
+    Reserved(KeyChord),
+}
+
+impl fmt::Display for ChordError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            ChordError::Empty => f.write_str("empty key chord"),
+            ChordError::UnknownKey(key) => write!(f, "unknown key '{key}'"),
+            ChordError::UnknownModifier(modifier) => write!(f, "unknown modifier '{modifier}'"),
+            ChordError::DuplicateModifier(modifier) => {
+                write!(f, "modifier '{modifier}' given twice")
+            }
+            ChordError::MissingKey => f.write_str("chord has modifiers but no key"),
+            ChordError::NoModifier => f.write_str("global hotkeys need at least one modifier"),
+            ChordError::Reserved(chord) => write!(f, "{chord} is reserved by the system"),
+        }
+    }
+}
+
+impl std::error::Error for ChordError {}
+
+/// A key plus modifiers, written like `Ctrl+Alt+T`. Parsing is case-insensitive; `Display` gives
+/// the canonical form with modifiers in Ctrl, Alt, Shift, Super order.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, PartialOrd, Ord, Serialize, Deserialize)]
+#[serde(into = "String", try_from = "String")]
+pub struct KeyChord {
+    pub modifiers: Modifiers,
+    pub key: Key,
+}
+
+impl KeyChord {
+    pub fn new(modifiers: Modifiers, key: Key) -> Self {
+        Self { modifiers, key }
+    }
+
+    /// Check that the chord is usable as a global hotkey.
+    pub fn validate(&self) -> std::result::Result<(), ChordError> {
+        if self.modifiers.is_empty() && !matches!(self.key, Key::F(_)) {
+            return Err(ChordError::NoModifier);
+        }
+        let ctrl_alt = Modifiers {
+            ctrl: true,
+            alt: true,
+            ..Modifiers::default()
+        };
+        let reserved = [
+            KeyChord::new(ctrl_alt, Key::Delete),
+            KeyChord::new(ctrl_alt, Key::Backspace),
+            KeyChord::new(
+                Modifiers {
+                    ctrl: true,
+                    shift: true,
+                    ..Modifiers::default()
+                },
+                Key::Escape,
+            ),
+        ];
+        if reserved.contains(self) {
+            return Err(ChordError::Reserved(*self));
+        }
+        Ok(())
+    }
+}
+
+impl FromStr for KeyChord {
+    type Err = ChordError;
+
+    fn from_str(s: &str) -> std::result::Result<Self, Self::Err> {
+        let tokens: Vec<&str> = s.split('+').map(str::trim).collect();
+        if tokens.iter().all(|token| token.is_empty()) {
+            return Err(ChordError::Empty);
+        }
+        let (key_token, modifier_tokens) = tokens.split_last().ok_or(ChordError::Empty)?;
+        let mut modifiers = Modifiers::default();
+        for token in modifier_tokens {
+            let slot = match token.to_ascii_lowercase().as_str() {
+                "ctrl" | "control" => &mut modifiers.ctrl,
+                "alt" | "option" => &mut modifiers.alt,
+                "shift" => &mut modifiers.shift,
+                "super" | "win" | "meta" | "cmd" => &mut modifiers.super_key,
+                _ => return Err(ChordError::UnknownModifier(token.to_string())),
+            };
+            if *slot {
+                return Err(ChordError::DuplicateModifier(token.to_string()));
+            }
+            *slot = true;
+        }
+        if key_token.is_empty() {
+            return Err(ChordError::MissingKey);
+        }
+        let key = match Key::parse(key_token) {
+            Some(key) => key,
+            None if is_modifier_name(key_token) => return Err(ChordError::MissingKey),
+            None => return Err(ChordError::UnknownKey(key_token.to_string())),
+        };
+        Ok(Self { modifiers, key })
+    }
+}
+
+fn is_modifier_name(token: &str) -> bool {
+    matches!(
+        token.to_ascii_lowercase().as_str(),
+        "ctrl" | "control" | "alt" | "option" | "shift" | "super" | "win" | "meta" | "cmd"
+    )
+}
+
+impl fmt::Display for KeyChord {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let modifiers = [
+            (self.modifiers.ctrl, "Ctrl"),
+            (self.modifiers.alt, "Alt"),
+            (self.modifiers.shift, "Shift"),
+            (self.modifiers.super_key, "Super"),
+        ];
+        for (_, name) in modifiers.iter().filter(|(held, _)| *held) {
+            write!(f, "{name}+")?;
+        }
+        write!(f, "{}", self.key)
+    }
+}
+
+impl From<KeyChord> for String {
+    fn from(chord: KeyChord) -> Self {
+        chord.to_string()
+    }
+}
+
+impl TryFrom<String> for KeyChord {
+    type Error = ChordError;
+
+    fn try_from(value: String) -> std::result::Result<Self, Self::Error> {
+        value.parse()
+    }
+}
+
+/// Caller-chosen handle for a registered hotkey.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, PartialOrd, Ord)]
+pub struct HotkeyId(pub u32);
+
+/// System-wide keyboard shortcuts.
+pub trait HotkeyProvider {
+    /// Grab `chord` system-wide. Fails if the chord is invalid or another app already owns it.
+    fn register(&mut self, id: HotkeyId, chord: &KeyChord) -> Result<()>;
+    fn unregister(&mut self, id: HotkeyId) -> Result<()>;
+    /// Receive the id of each registered hotkey as it is pressed.
+    fn subscribe(&mut self) -> Receiver<HotkeyId>;
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn chord(text: &str) -> std::result::Result<KeyChord, ChordError> {
+        text.parse()
+    }
+
+    #[test]
+    fn parsing_ignores_case_and_spacing() {
+        let expected = KeyChord::new(
+            Modifiers {
+                ctrl: true,
+                alt: true,
+                ..Modifiers::default()
+            },
+            Key::Char('T'),
+        );
+        for text in ["Ctrl+Alt+T", "ctrl+alt+t", "CTRL + ALT + t", "Alt+Ctrl+T"] {
+            assert_eq!(chord(text), Ok(expected), "{text}");
+        }
+        assert_eq!(chord("shift+f12").unwrap().key, Key::F(12));
+        assert_eq!(chord("Ctrl+pagedown").unwrap().key, Key::PageDown);
+    }
+
+    #[test]
+    fn parsing_accepts_aliases() {
+        assert_eq!(chord("Control+Option+Return"), chord("Ctrl+Alt+Enter"));
+        assert_eq!(chord("Win+Esc"), chord("Super+Escape"));
+        assert_eq!(chord("Meta+Del"), chord("Super+Delete"));
+        assert_eq!(chord("Cmd+PgUp"), chord("Super+PageUp"));
+        assert_eq!(chord("Ctrl+Ins"), chord("Ctrl+Insert"));
+        assert_eq!(chord("Shift+PgDn"), chord("Shift+PageDown"));
+    }
+
+    #[test]
+    fn parsing_rejects_bad_chords() {
+        assert_eq!(chord(""), Err(ChordError::Empty));
+        assert_eq!(chord(" + "), Err(ChordError::Empty));
+        assert_eq!(chord("Ctrl+Alt"), Err(ChordError::MissingKey));
+        assert_eq!(chord("Ctrl+"), Err(ChordError::MissingKey));
+        assert_eq!(
+            chord("Hyper+T"),
+            Err(ChordError::UnknownModifier("Hyper".into()))
+        );
+        assert_eq!(
+            chord("Ctrl+Control+T"),
+            Err(ChordError::DuplicateModifier("Control".into()))
+        );
+        assert_eq!(
+            chord("win+super+T"),
+            Err(ChordError::DuplicateModifier("super".into()))
+        );
+        assert_eq!(chord("Ctrl+F25"), Err(ChordError::UnknownKey("F25".into())));
+        assert_eq!(chord("Ctrl+F0"), Err(ChordError::UnknownKey("F0".into())));
+        assert_eq!(chord("Ctrl+é"), Err(ChordError::UnknownKey("é".into())));
+        assert_eq!(
+            chord("Ctrl+Banana"),
+            Err(ChordError::UnknownKey("Banana".into()))
+        );
+    }
+
+    #[test]
+    fn display_is_canonical_and_round_trips() {
+        assert_eq!(
+            chord("super+shift+alt+ctrl+x").unwrap().to_string(),
+            "Ctrl+Alt+Shift+Super+X"
+        );
+        assert_eq!(chord("cmd+return").unwrap().to_string(), "Super+Enter");
+        for text in [
+            "Ctrl+Alt+T",
+            "Shift+F1",
+            "F24",
+            "Ctrl+Shift+Space",
+            "Alt+Left",
+            "Super+9",
+            "Ctrl+Alt+Shift+Super+PageUp",
+        ] {
+            let parsed = chord(text).unwrap();
+            assert_eq!(parsed.to_string(), text);
+            assert_eq!(chord(&parsed.to_string()), Ok(parsed));
+        }
+    }
+
+    #[test]
+    fn validation_requires_a_modifier_and_skips_reserved_chords() {
+        assert_eq!(chord("T").unwrap().validate(), Err(ChordError::NoModifier));
+        assert!(chord("F5").unwrap().validate().is_ok());
+        let reserved = chord("Ctrl+Alt+Del").unwrap();
+        assert_eq!(reserved.validate(), Err(ChordError::Reserved(reserved)));
+        assert!(chord("Ctrl+Shift+Esc").unwrap().validate().is_err());
+        assert!(chord("Ctrl+Alt+T").unwrap().validate().is_ok());
+    }
+}
@@ -0,0 +1,262 @@
+use glam::Vec2;
+
+use crate::{DamageRect, MonitorEvent, MonitorId, MonitorRect, OverlayManager, Result};
+
+/// DPI Windows (and most toolkits) treat as 100% scale.
+pub const BASE_DPI: f32 = 96.0;
+
+/// Snapshot of the virtual desktop built from `OverlayManager::monitors()`.
+///
+/// Three coordinate spaces are involved:
+/// - desktop: global physical pixels spanning all monitors (what `MonitorRect` x/y use),
+/// - monitor-local: physical pixels relative to one monitor's top-left corner,
+/// - logical: monitor-local pixels divided by that monitor's scale factor.
+#[derive(Debug, Clone, Default)]
+pub struct DesktopLayout {
+    monitors: Vec<MonitorRect>,
+}
+
+impl DesktopLayout {
+    pub fn new(monitors: Vec<MonitorRect>) -> Self {
+        Self { monitors }
+    }
+
+    pub fn from_manager(manager: &dyn OverlayManager) -> Result<Self> {
+        Ok(Self::new(manager.monitors()?))
+    }
+
+    pub fn monitors(&self) -> &[MonitorRect] {
+        &self.monitors
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.monitors.is_empty()
+    }
+
+    /// Keep the snapshot current without re-querying the manager.
+    pub fn apply_event(&mut self, event: &MonitorEvent) {
+        match event {
+            MonitorEvent::Added { new } => self.monitors.push(*new),
+            MonitorEvent::Removed { old } => self.monitors.retain(|monitor| monitor.id != old.id),
+            MonitorEvent::Moved { new, .. } | MonitorEvent::DpiChanged { new, .. } => {
+                for monitor in self
+                    .monitors
+                    .iter_mut()
+                    .filter(|monitor| monitor.id == new.id)
+                {
+                    *monitor = *new;
+                }
+            }
+        }
+    }
+
+    /// Bounding box of every monitor, in desktop pixels.
+    pub fn bounds(&self) -> Option<DamageRect> {
+        self.monitors
+            .iter()
+            .map(monitor_bounds)
+            .reduce(|acc, rect| acc.union(&rect))
+    }
+
+    /// Index of the monitor containing `point` (desktop pixels).
+    pub fn monitor_index_at(&self, point: Vec2) -> Option<usize> {
+        self.monitors
+            .iter()
+            .position(|monitor| contains(monitor, point))
+    }
+
+    pub fn monitor(&self, id: MonitorId) -> Option<&MonitorRect> {
+        self.monitors.iter().find(|monitor| monitor.id == id)
+    }
+
+    pub fn monitor_at(&self, point: Vec2) -> Option<&MonitorRect> {
+        self.monitor_index_at(point)
+            .map(|index| &self.monitors[index])
+    }
+
+    /// Monitor containing `point`, or the closest one when the point falls in a gap between displays.
+    pub fn nearest_monitor(&self, point: Vec2) -> Option<&MonitorRect> {
+        self.monitor_at(point).or_else(|| {
+            self.monitors
+                .iter()
+                .min_by(|a, b| distance_squared(a, point).total_cmp(&distance_squared(b, point)))
+        })
+    }
+
+    pub fn desktop_to_monitor(monitor: &MonitorRect, point: Vec2) -> Vec2 {
+        point - origin(monitor)
+    }
+
+    pub fn monitor_to_desktop(monitor: &MonitorRect, point: Vec2) -> Vec2 {
+        point + origin(monitor)
+    }
+
+    pub fn desktop_to_logical(monitor: &MonitorRect, point: Vec2) -> Vec2 {
+        Self::desktop_to_monitor(monitor, point) / scale_factor(monitor)
+    }
+
+    pub fn logical_to_desktop(monitor: &MonitorRect, point: Vec2) -> Vec2 {
+        Self::monitor_to_desktop(monitor, point * scale_factor(monitor))
+    }
+
+    /// Map a desktop rect into `monitor`'s local physical pixels, clipped to the monitor.
+    pub fn desktop_rect_to_monitor(monitor: &MonitorRect, rect: &DamageRect) -> Option<DamageRect> {
+        rect.clip_to_monitor(monitor)
+    }
+
+    /// Map a monitor-local logical rect into desktop pixels.
+    pub fn logical_rect_to_desktop(monitor: &MonitorRect, rect: &DamageRect) -> DamageRect {
+        let min = Self::logical_to_desktop(monitor, Vec2::new(rect.x as f32, rect.y as f32));
+        let max = Self::logical_to_desktop(
+            monitor,
+            Vec2::new(rect.right() as f32, rect.bottom() as f32),
+        );
+        DamageRect::from_bounds(min.x, min.y, max.x, max.y)
+    }
+
+    /// Scale factor at `point`; 1.0 when the point is off every monitor.
+    pub fn scale_at(&self, point: Vec2) -> f32 {
+        self.nearest_monitor(point).map(scale_factor).unwrap_or(1.0)
+    }
+
+    /// Convert a size in logical pixels to physical pixels at `point`, so a particle keeps the same
+    /// apparent size when the trail crosses between displays with different DPI.
+    pub fn scale_size_at(&self, point: Vec2, logical_size: f32) -> f32 {
+        logical_size * self.scale_at(point)
+    }
+}
+
+impl MonitorRect {
+    /// Whether `point` (desktop pixels) lies on this monitor.
+    pub fn contains(&self, point: Vec2) -> bool {
+        contains(self, point)
+    }
+}
+
+/// Ratio of the monitor's DPI to `BASE_DPI` (1.0 at 100%, 2.0 at 200%).
+pub fn scale_factor(monitor: &MonitorRect) -> f32 {
+    if monitor.dpi == 0 {
+        1.0
+    } else {
+        monitor.dpi as f32 / BASE_DPI
+    }
+}
+
+fn origin(monitor: &MonitorRect) -> Vec2 {
+    Vec2::new(monitor.x as f32, monitor.y as f32)
+}
+
+fn monitor_bounds(monitor: &MonitorRect) -> DamageRect {
+    DamageRect::new(monitor.x, monitor.y, monitor.width, monitor.height)
+}
+
+fn contains(monitor: &MonitorRect, point: Vec2) -> bool {
+    let min = origin(monitor);
+    let max = min + Vec2::new(monitor.width as f32, monitor.height as f32);
+    point.x >= min.x && point.y >= min.y && point.x < max.x && point.y < max.y
+}
+
+fn distance_squared(monitor: &MonitorRect, point: Vec2) -> f32 {
+    let min = origin(monitor);
+    let max = min + Vec2::new(monitor.width as f32, monitor.height as f32);
+    point.clamp(min, max).distance_squared(point)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn monitor(key: &str, x: i32, y: i32, width: i32, height: i32, dpi: u32) -> MonitorRect {
+        MonitorRect {
+            id: MonitorId::from_key(key),
+            x,
+            y,
+            width,
+            height,
+            dpi,
+            refresh_hz: None,
+        }
+    }
+
+    /// A 200% laptop panel left of and above a 100% 1080p primary at the origin.
+    fn mixed_layout() -> DesktopLayout {
+        DesktopLayout::new(vec![
+            monitor("primary", 0, 0, 1920, 1080, 96),
+            monitor("laptop", -2880, -400, 2880, 1800, 192),
+        ])
+    }
+
+    #[test]
+    fn negative_origins_are_found_and_bounded() {
+        let layout = mixed_layout();
+        assert_eq!(
+            layout.bounds(),
+            Some(DamageRect::new(-2880, -400, 4800, 1800))
+        );
+        assert_eq!(layout.monitor_index_at(Vec2::new(-1.0, -1.0)), Some(1));
+        assert_eq!(layout.monitor_index_at(Vec2::new(0.0, 0.0)), Some(0));
+        // Right and bottom edges are exclusive.
+        assert_eq!(layout.monitor_index_at(Vec2::new(1920.0, 10.0)), None);
+    }
+
+    #[test]
+    fn conversions_round_trip_at_each_scale() {
+        let layout = mixed_layout();
+        let laptop = &layout.monitors()[1];
+        let point = Vec2::new(-1880.0, 0.0);
+        assert_eq!(
+            DesktopLayout::desktop_to_monitor(laptop, point),
+            Vec2::new(1000.0, 400.0)
+        );
+        assert_eq!(
+            DesktopLayout::desktop_to_logical(laptop, point),
+            Vec2::new(500.0, 200.0)
+        );
+        assert_eq!(
+            DesktopLayout::logical_to_desktop(laptop, Vec2::new(500.0, 200.0)),
+            point
+        );
+
+        let logical = DamageRect::new(10, 10, 5, 5);
+        assert_eq!(
+            DesktopLayout::logical_rect_to_desktop(laptop, &logical),
+            DamageRect::new(-2860, -380, 10, 10)
+        );
+    }
+
+    #[test]
+    fn scale_follows_the_monitor_under_the_point() {
+        let layout = mixed_layout();
+        assert_eq!(layout.scale_at(Vec2::new(100.0, 100.0)), 1.0);
+        assert_eq!(layout.scale_at(Vec2::new(-100.0, 100.0)), 2.0);
+        assert_eq!(layout.scale_size_at(Vec2::new(-100.0, 100.0), 8.0), 16.0);
+    }
+
+    #[test]
+    fn points_off_every_monitor_use_the_nearest() {
+        let layout = mixed_layout();
+        // In the dead zone below the laptop, closer to it than to the primary.
+        let below_laptop = Vec2::new(-2000.0, 1500.0);
+        assert!(layout.monitor_at(below_laptop).is_none());
+        assert_eq!(
+            layout.nearest_monitor(below_laptop).unwrap().id,
+            MonitorId::from_key("laptop")
+        );
+        assert_eq!(layout.scale_at(below_laptop), 2.0);
+
+        let far_right = Vec2::new(5000.0, 500.0);
+        assert_eq!(
+            layout.nearest_monitor(far_right).unwrap().id,
+            MonitorId::from_key("primary")
+        );
+        assert_eq!(DesktopLayout::default().scale_at(far_right), 1.0);
+        assert!(DesktopLayout::default()
+            .nearest_monitor(far_right)
+            .is_none());
+    }
+
+    #[test]
+    fn zero_dpi_is_treated_as_base_scale() {
+        assert_eq!(scale_factor(&monitor("broken", 0, 0, 800, 600, 0)), 1.0);
+    }
+}
@@ -1,16 +1,43 @@
 //! Platform abstraction traits so `serpentines-core` stays OS-agnostic.
 
+use std::fmt;
+use std::sync::mpsc::Receiver;
+use std::sync::Arc;
+use std::time::Duration;
+
 use serde::{Deserialize, Serialize};
 
+mod backend;
+mod clock;
+mod damage;
+mod events;
+mod hotkey;
+mod layout;
+#[cfg(feature = "mock")]
+pub mod mock;
+mod monitor_id;
+mod tray;
+pub use backend::{BackendRegistry, Capabilities, PlatformBackend};
+pub use clock::{FrameClock, ManualClock, MonotonicClock};
+pub use damage::DamageRect;
+pub use events::{diff_monitors, InputEvent, MonitorEvent, MouseButton, ShellEvent, Subscribers};
+pub use hotkey::{ChordError, HotkeyId, HotkeyProvider, Key, KeyChord, Modifiers};
+pub use layout::{scale_factor, DesktopLayout, BASE_DPI};
+pub use monitor_id::MonitorId;
+pub use tray::{PowerMode, TrayAction, TrayItem, TrayMenu, TrayProvider};
+
 pub type Result<T> = std::result::Result<T, Box<dyn std::error::Error + Send + Sync>>;
 
-#[derive(Debug, Clone, Copy, Serialize, Deserialize)]
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
 pub struct MonitorRect {
+    pub id: MonitorId,
     pub x: i32,
     pub y: i32,
     pub width: i32,
     pub height: i32,
     pub dpi: u32,
+    /// Refresh rate in Hz, when the backend can tell.
+    pub refresh_hz: Option<f32>,
 }
 
 /// Manages per-monitor transparent overlays.
@@ -19,12 +46,68 @@ pub trait OverlayManager: Send + Sync {
     fn monitors(&self) -> Result<Vec<MonitorRect>>;
     fn create_overlays(&mut self) -> Result<()>;
     fn destroy_overlays(&mut self) -> Result<()>;
+    /// Receive topology changes (monitors added, removed, moved or re-scaled).
+    fn subscribe(&mut self) -> Receiver<MonitorEvent>;
 }
 
 /// Source of global mouse events.
 pub trait InputSource: Send + Sync {
     fn start(&mut self) -> Result<()>;
     fn stop(&mut self) -> Result<()>;
+    /// Receive input events observed while started.
+    fn subscribe(&mut self) -> Receiver<InputEvent>;
+}
+
+/// One particle to draw, in desktop pixels.
+#[derive(Debug, Clone, Copy, Default, PartialEq)]
+pub struct ParticleInstance {
+    pub x: f32,
+    pub y: f32,
+    /// Diameter in physical pixels.
+    pub size: f32,
+    /// Straight (non-premultiplied) RGBA.
+    pub color: [f32; 4],
+}
+
+/// Performance HUD text drawn on the overlay, anchored at `x`, `y` in desktop pixels. Renderers
+/// draw it with a fixed-cell font of `CHAR_WIDTH` x `LINE_HEIGHT` pixels.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct HudOverlay {
+    pub x: i32,
+    pub y: i32,
+    pub lines: Vec<String>,
+}
+
+impl HudOverlay {
+    pub const CHAR_WIDTH: i32 = 8;
+    pub const LINE_HEIGHT: i32 = 16;
+    /// Background margin around the text.
+    pub const PADDING: i32 = 6;
+
+    /// Area covered by the HUD and its background.
+    pub fn bounds(&self) -> DamageRect {
+        let columns = self
+            .lines
+            .iter()
+            .map(|line| line.chars().count())
+            .max()
+            .unwrap_or(0) as i32;
+        DamageRect::new(
+            self.x,
+            self.y,
+            columns * Self::CHAR_WIDTH + 2 * Self::PADDING,
+            self.lines.len() as i32 * Self::LINE_HEIGHT + 2 * Self::PADDING,
+        )
+    }
+}
+
+/// Renderer-side numbers for the stats panel.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Serialize, Deserialize)]
+pub struct RendererStats {
+    /// Particle instances drawn in the last frame.
+    pub instances: u32,
+    /// GPU time of the last frame, when the backend can measure it.
+    pub gpu_ms: Option<f32>,
 }
 
 /// GPU renderer abstraction (to be backed by wgpu on each platform).
@@ -34,4 +117,81 @@ pub trait GpuRenderer: Send + Sync {
     fn resize(&mut self, _width: u32, _height: u32) -> Result<()> {
         Ok(())
     }
+    /// Regions (desktop pixels) that changed since the last frame. Backends may present only these;
+    /// an empty slice means nothing needs redrawing.
+    fn set_damage(&mut self, _damage: &[DamageRect]) -> Result<()> {
+        Ok(())
+    }
+    /// Toggle optional post-processing (glow, blur). Backends without post-effects ignore this.
+    fn set_post_effects(&mut self, _enabled: bool) -> Result<()> {
+        Ok(())
+    }
+    /// Particles to draw in the next `render_frame`.
+    fn set_particles(&mut self, _particles: &[ParticleInstance]) -> Result<()> {
+        Ok(())
+    }
+    /// HUD to draw on top of the particles from the next frame on; `None` hides it. No backend
+    /// draws it yet.
+    fn set_hud(&mut self, _hud: Option<&HudOverlay>) -> Result<()> {
+        Ok(())
+    }
+    fn stats(&self) -> RendererStats {
+        RendererStats::default()
+    }
+}
+
+/// Renderer that draws nothing, for backends without a GPU path yet.
+#[derive(Debug, Default)]
+pub struct NullRenderer;
+
+impl GpuRenderer for NullRenderer {
+    fn init(&mut self) -> Result<()> {
+        Ok(())
+    }
+    fn render_frame(&mut self) -> Result<()> {
+        Ok(())
+    }
+}
+
+/// Interrupts a blocked `EventLoop::pump` from any thread.
+#[derive(Clone)]
+pub struct Waker(Arc<dyn Fn() + Send + Sync>);
+
+impl Waker {
+    pub fn new(wake: impl Fn() + Send + Sync + 'static) -> Self {
+        Self(Arc::new(wake))
+    }
+
+    pub fn wake(&self) {
+        (self.0)()
+    }
+}
+
+impl fmt::Debug for Waker {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str("Waker")
+    }
+}
+
+/// Main-thread OS event dispatch, driven by the app runtime.
+pub trait EventLoop {
+    /// Dispatch pending OS events, blocking for at most `timeout` (indefinitely when `None`) until
+    /// something arrives or the waker fires.
+    fn pump(&mut self, timeout: Option<Duration>) -> Result<()>;
+    fn waker(&self) -> Waker;
+    /// Overlays whose window events this loop dispatches.
+    fn overlays(&mut self) -> &mut dyn OverlayManager;
+    /// Receive requests from the OS shell, such as a session ending.
+    fn subscribe_shell(&mut self) -> Receiver<ShellEvent>;
+}
+
+/// Everything the runtime needs from one backend.
+pub struct Platform {
+    pub event_loop: Box<dyn EventLoop>,
+    pub input: Box<dyn InputSource>,
+    pub renderer: Box<dyn GpuRenderer>,
+    /// `None` when the session has no tray host.
+    pub tray: Option<Box<dyn TrayProvider>>,
+    /// `None` when the backend can't grab keys system-wide.
+    pub hotkeys: Option<Box<dyn HotkeyProvider>>,
 }
@@ -0,0 +1,754 @@
+//! In-memory platform implementations for tests. Enabled with the `mock` feature.
+//!
+//! Every mock records the trait calls it receives into a [`CallLog`] and can be told to fail the
+//! next call to a given method. Mocks are cheap `Clone` handles over shared state, so a test keeps
+//! one clone for scripting and assertions while the code under test owns another as a trait object.
+
+use std::collections::HashMap;
+use std::fmt;
+use std::sync::mpsc::Receiver;
+use std::sync::{Arc, Mutex, MutexGuard};
+use std::time::Duration;
+
+use crate::{
+    diff_monitors, DamageRect, EventLoop, GpuRenderer, HotkeyId, HotkeyProvider, HudOverlay,
+    InputEvent, InputSource, KeyChord, MonitorEvent, MonitorId, MonitorRect, OverlayManager,
+    ParticleInstance, Platform, Result, ShellEvent, Subscribers, TrayAction, TrayMenu,
+    TrayProvider, Waker,
+};
+
+fn lock<T>(mutex: &Mutex<T>) -> MutexGuard<'_, T> {
+    mutex
+        .lock()
+        .unwrap_or_else(|poisoned| poisoned.into_inner())
+}
+
+/// A platform trait call observed by a mock, with its arguments.
+#[derive(Debug, Clone, PartialEq)]
+pub enum MockCall {
+    OverlayInit,
+    Monitors,
+    CreateOverlays,
+    DestroyOverlays,
+    SubscribeMonitors,
+    InputStart,
+    InputStop,
+    SubscribeInput,
+    RendererInit,
+    RenderFrame,
+    Resize {
+        width: u32,
+        height: u32,
+    },
+    SetDamage(Vec<DamageRect>),
+    SetPostEffects(bool),
+    /// Number of particles handed to the renderer.
+    SetParticles(usize),
+    SetHud(Option<HudOverlay>),
+    Pump {
+        timeout: Option<Duration>,
+    },
+    SubscribeShell,
+    SetTrayMenu(TrayMenu),
+    SubscribeTray,
+    RegisterHotkey {
+        id: HotkeyId,
+        chord: KeyChord,
+    },
+    UnregisterHotkey(HotkeyId),
+    SubscribeHotkeys,
+}
+
+/// Fallible trait methods a test can make fail.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum MockMethod {
+    OverlayInit,
+    Monitors,
+    CreateOverlays,
+    DestroyOverlays,
+    InputStart,
+    InputStop,
+    RendererInit,
+    RenderFrame,
+    Resize,
+    SetDamage,
+    SetPostEffects,
+    SetParticles,
+    SetHud,
+    Pump,
+    SetTrayMenu,
+    RegisterHotkey,
+    UnregisterHotkey,
+}
+
+/// Error returned by an injected failure.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct MockFailure {
+    pub method: MockMethod,
+    pub message: String,
+}
+
+impl fmt::Display for MockFailure {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{:?} failed: {}", self.method, self.message)
+    }
+}
+
+impl std::error::Error for MockFailure {}
+
+#[derive(Default)]
+struct LogState {
+    calls: Vec<MockCall>,
+    failures: HashMap<MockMethod, Vec<String>>,
+}
+
+/// Ordered record of calls, shared between mocks so cross-trait ordering can be asserted.
+#[derive(Clone, Default)]
+pub struct CallLog {
+    state: Arc<Mutex<LogState>>,
+}
+
+impl CallLog {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn calls(&self) -> Vec<MockCall> {
+        lock(&self.state).calls.clone()
+    }
+
+    pub fn count(&self, matches: impl Fn(&MockCall) -> bool) -> usize {
+        lock(&self.state)
+            .calls
+            .iter()
+            .filter(|call| matches(call))
+            .count()
+    }
+
+    pub fn clear(&self) {
+        lock(&self.state).calls.clear();
+    }
+
+    /// Make the next call to `method` return an error. Queued failures are consumed in order.
+    pub fn fail_next(&self, method: MockMethod, message: impl Into<String>) {
+        lock(&self.state)
+            .failures
+            .entry(method)
+            .or_default()
+            .push(message.into());
+    }
+
+    fn record(&self, call: MockCall, method: Option<MockMethod>) -> Result<()> {
+        let mut state = lock(&self.state);
+        state.calls.push(call);
+        let Some(method) = method else {
+            return Ok(());
+        };
+        match state.failures.get_mut(&method) {
+            Some(queue) if !queue.is_empty() => {
+                let message = queue.remove(0);
+                Err(Box::new(MockFailure { method, message }))
+            }
+            _ => Ok(()),
+        }
+    }
+}
+
+#[derive(Default)]
+struct OverlayState {
+    monitors: Vec<MonitorRect>,
+    overlays_created: bool,
+    subscribers: Subscribers<MonitorEvent>,
+}
+
+/// Overlay manager whose monitor topology is scripted by the test.
+#[derive(Clone, Default)]
+pub struct MockOverlayManager {
+    state: Arc<Mutex<OverlayState>>,
+    log: CallLog,
+}
+
+impl MockOverlayManager {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn with_log(log: CallLog) -> Self {
+        Self {
+            log,
+            ..Self::default()
+        }
+    }
+
+    pub fn with_monitors(monitors: Vec<MonitorRect>) -> Self {
+        let manager = Self::new();
+        manager.state().monitors = monitors;
+        manager
+    }
+
+    fn state(&self) -> MutexGuard<'_, OverlayState> {
+        lock(&self.state)
+    }
+
+    pub fn log(&self) -> &CallLog {
+        &self.log
+    }
+
+    pub fn overlays_created(&self) -> bool {
+        self.state().overlays_created
+    }
+
+    /// Replace the whole topology and publish the resulting events.
+    pub fn set_monitors(&self, monitors: Vec<MonitorRect>) {
+        let mut state = self.state();
+        let events = diff_monitors(&state.monitors, &monitors);
+        state.monitors = monitors;
+        for event in &events {
+            state.subscribers.publish(event);
+        }
+    }
+
+    pub fn add_monitor(&self, rect: MonitorRect) {
+        let mut monitors = self.state().monitors.clone();
+        monitors.retain(|monitor| monitor.id != rect.id);
+        monitors.push(rect);
+        self.set_monitors(monitors);
+    }
+
+    pub fn remove_monitor(&self, id: MonitorId) {
+        let mut monitors = self.state().monitors.clone();
+        monitors.retain(|monitor| monitor.id != id);
+        self.set_monitors(monitors);
+    }
+
+    /// Move, resize or re-scale the monitor with `rect.id`.
+    pub fn update_monitor(&self, rect: MonitorRect) {
+        let mut monitors = self.state().monitors.clone();
+        for monitor in monitors.iter_mut().filter(|monitor| monitor.id == rect.id) {
+            *monitor = rect;
+        }
+        self.set_monitors(monitors);
+    }
+}
+
+impl OverlayManager for MockOverlayManager {
+    fn init(&mut self) -> Result<()> {
+        self.log
+            .record(MockCall::OverlayInit, Some(MockMethod::OverlayInit))
+    }
+
+    fn monitors(&self) -> Result<Vec<MonitorRect>> {
+        self.log
+            .record(MockCall::Monitors, Some(MockMethod::Monitors))?;
+        Ok(self.state().monitors.clone())
+    }
+
+    fn create_overlays(&mut self) -> Result<()> {
+        self.log
+            .record(MockCall::CreateOverlays, Some(MockMethod::CreateOverlays))?;
+        self.state().overlays_created = true;
+        Ok(())
+    }
+
+    fn destroy_overlays(&mut self) -> Result<()> {
+        self.log
+            .record(MockCall::DestroyOverlays, Some(MockMethod::DestroyOverlays))?;
+        self.state().overlays_created = false;
+        Ok(())
+    }
+
+    fn subscribe(&mut self) -> Receiver<MonitorEvent> {
+        let _ = self.log.record(MockCall::SubscribeMonitors, None);
+        self.state().subscribers.subscribe()
+    }
+}
+
+#[derive(Default)]
+struct InputState {
+    running: bool,
+    subscribers: Subscribers<InputEvent>,
+}
+
+/// Input source fed by the test. Injected events are delivered only while started, like a real hook.
+#[derive(Clone, Default)]
+pub struct MockInputSource {
+    state: Arc<Mutex<InputState>>,
+    log: CallLog,
+}
+
+impl MockInputSource {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn with_log(log: CallLog) -> Self {
+        Self {
+            log,
+            ..Self::default()
+        }
+    }
+
+    pub fn log(&self) -> &CallLog {
+        &self.log
+    }
+
+    pub fn is_running(&self) -> bool {
+        lock(&self.state).running
+    }
+
+    /// Deliver `event` to subscribers. Returns false (and drops it) when the source is stopped.
+    pub fn inject(&self, event: InputEvent) -> bool {
+        let mut state = lock(&self.state);
+        if !state.running {
+            return false;
+        }
+        state.subscribers.publish(&event);
+        true
+    }
+
+    pub fn move_cursor(&self, x: f32, y: f32) -> bool {
+        self.inject(InputEvent::CursorMoved { x, y })
+    }
+}
+
+impl InputSource for MockInputSource {
+    fn start(&mut self) -> Result<()> {
+        self.log
+            .record(MockCall::InputStart, Some(MockMethod::InputStart))?;
+        lock(&self.state).running = true;
+        Ok(())
+    }
+
+    fn stop(&mut self) -> Result<()> {
+        self.log
+            .record(MockCall::InputStop, Some(MockMethod::InputStop))?;
+        lock(&self.state).running = false;
+        Ok(())
+    }
+
+    fn subscribe(&mut self) -> Receiver<InputEvent> {
+        let _ = self.log.record(MockCall::SubscribeInput, None);
+        lock(&self.state).subscribers.subscribe()
+    }
+}
+
+/// Renderer that draws nothing and records what it was asked to do.
+#[derive(Clone, Default)]
+pub struct MockGpuRenderer {
+    log: CallLog,
+}
+
+impl MockGpuRenderer {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn with_log(log: CallLog) -> Self {
+        Self { log }
+    }
+
+    pub fn log(&self) -> &CallLog {
+        &self.log
+    }
+
+    pub fn frames_rendered(&self) -> usize {
+        self.log.count(|call| *call == MockCall::RenderFrame)
+    }
+}
+
+impl GpuRenderer for MockGpuRenderer {
+    fn init(&mut self) -> Result<()> {
+        self.log
+            .record(MockCall::RendererInit, Some(MockMethod::RendererInit))
+    }
+
+    fn render_frame(&mut self) -> Result<()> {
+        self.log
+            .record(MockCall::RenderFrame, Some(MockMethod::RenderFrame))
+    }
+
+    fn resize(&mut self, width: u32, height: u32) -> Result<()> {
+        self.log
+            .record(MockCall::Resize { width, height }, Some(MockMethod::Resize))
+    }
+
+    fn set_damage(&mut self, damage: &[DamageRect]) -> Result<()> {
+        self.log.record(
+            MockCall::SetDamage(damage.to_vec()),
+            Some(MockMethod::SetDamage),
+        )
+    }
+
+    fn set_post_effects(&mut self, enabled: bool) -> Result<()> {
+        self.log.record(
+            MockCall::SetPostEffects(enabled),
+            Some(MockMethod::SetPostEffects),
+        )
+    }
+
+    fn set_particles(&mut self, particles: &[ParticleInstance]) -> Result<()> {
+        self.log.record(
+            MockCall::SetParticles(particles.len()),
+            Some(MockMethod::SetParticles),
+        )
+    }
+
+    fn set_hud(&mut self, hud: Option<&HudOverlay>) -> Result<()> {
+        self.log
+            .record(MockCall::SetHud(hud.cloned()), Some(MockMethod::SetHud))
+    }
+}
+
+#[derive(Default)]
+struct EventLoopState {
+    wakes: usize,
+    subscribers: Subscribers<ShellEvent>,
+}
+
+/// Event loop that never blocks: `pump` returns immediately, so a test drives time itself.
+#[derive(Clone, Default)]
+pub struct MockEventLoop {
+    state: Arc<Mutex<EventLoopState>>,
+    overlays: MockOverlayManager,
+    log: CallLog,
+}
+
+impl MockEventLoop {
+    pub fn new(overlays: MockOverlayManager) -> Self {
+        Self {
+            log: overlays.log.clone(),
+            overlays,
+            state: Arc::default(),
+        }
+    }
+
+    pub fn log(&self) -> &CallLog {
+        &self.log
+    }
+
+    /// Deliver a shell request, as a tray click would.
+    pub fn request(&self, event: ShellEvent) {
+        lock(&self.state).subscribers.publish(&event);
+    }
+
+    /// How many times the waker fired.
+    pub fn wake_count(&self) -> usize {
+        lock(&self.state).wakes
+    }
+
+    /// Timeouts passed to `pump`, in call order.
+    pub fn pump_timeouts(&self) -> Vec<Option<Duration>> {
+        self.log
+            .calls()
+            .into_iter()
+            .filter_map(|call| match call {
+                MockCall::Pump { timeout } => Some(timeout),
+                _ => None,
+            })
+            .collect()
+    }
+}
+
+impl EventLoop for MockEventLoop {
+    fn pump(&mut self, timeout: Option<Duration>) -> Result<()> {
+        self.log
+            .record(MockCall::Pump { timeout }, Some(MockMethod::Pump))
+    }
+
+    fn waker(&self) -> Waker {
+        let state = Arc::clone(&self.state);
+        Waker::new(move || lock(&state).wakes += 1)
+    }
+
+    fn overlays(&mut self) -> &mut dyn OverlayManager {
+        &mut self.overlays
+    }
+
+    fn subscribe_shell(&mut self) -> Receiver<ShellEvent> {
+        let _ = self.log.record(MockCall::SubscribeShell, None);
+        lock(&self.state).subscribers.subscribe()
+    }
+}
+
+#[derive(Default)]
+struct TrayState {
+    menu: Option<TrayMenu>,
+    subscribers: Subscribers<TrayAction>,
+}
+
+/// Tray that keeps the last menu it was given and lets the test click entries.
+#[derive(Clone, Default)]
+pub struct MockTrayProvider {
+    state: Arc<Mutex<TrayState>>,
+    log: CallLog,
+}
+
+impl MockTrayProvider {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn with_log(log: CallLog) -> Self {
+        Self {
+            log,
+            ..Self::default()
+        }
+    }
+
+    pub fn log(&self) -> &CallLog {
+        &self.log
+    }
+
+    /// Menu from the most recent successful `set_menu`.
+    pub fn menu(&self) -> Option<TrayMenu> {
+        lock(&self.state).menu.clone()
+    }
+
+    /// Deliver `action` as if the user picked it.
+    pub fn click(&self, action: TrayAction) {
+        lock(&self.state).subscribers.publish(&action);
+    }
+}
+
+impl TrayProvider for MockTrayProvider {
+    fn set_menu(&mut self, menu: &TrayMenu) -> Result<()> {
+        self.log.record(
+            MockCall::SetTrayMenu(menu.clone()),
+            Some(MockMethod::SetTrayMenu),
+        )?;
+        lock(&self.state).menu = Some(menu.clone());
+        Ok(())
+    }
+
+    fn subscribe(&mut self) -> Receiver<TrayAction> {
+        let _ = self.log.record(MockCall::SubscribeTray, None);
+        lock(&self.state).subscribers.subscribe()
+    }
+}
+
+#[derive(Default)]
+struct HotkeyState {
+    registered: HashMap<HotkeyId, KeyChord>,
+    subscribers: Subscribers<HotkeyId>,
+}
+
+/// Hotkey provider that behaves like an OS registry: chords are validated and can't be taken twice.
+#[derive(Clone, Default)]
+pub struct MockHotkeyProvider {
+    state: Arc<Mutex<HotkeyState>>,
+    log: CallLog,
+}
+
+impl MockHotkeyProvider {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn with_log(log: CallLog) -> Self {
+        Self {
+            log,
+            ..Self::default()
+        }
+    }
+
+    pub fn log(&self) -> &CallLog {
+        &self.log
+    }
+
+    pub fn registered(&self) -> HashMap<HotkeyId, KeyChord> {
+        lock(&self.state).registered.clone()
+    }
+
+    /// Simulate the user pressing `chord`. Returns false when no registered hotkey matches.
+    pub fn press(&self, chord: &KeyChord) -> bool {
+        let mut state = lock(&self.state);
+        let id = state
+            .registered
+            .iter()
+            .find(|(_, registered)| *registered == chord)
+            .map(|(id, _)| *id);
+        match id {
+            Some(id) => {
+                state.subscribers.publish(&id);
+                true
+            }
+            None => false,
+        }
+    }
+}
+
+impl HotkeyProvider for MockHotkeyProvider {
+    fn register(&mut self, id: HotkeyId, chord: &KeyChord) -> Result<()> {
+        self.log.record(
+            MockCall::RegisterHotkey { id, chord: *chord },
+            Some(MockMethod::RegisterHotkey),
+        )?;
+        chord.validate()?;
+        let mut state = lock(&self.state);
+        if state
+            .registered
+            .iter()
+            .any(|(other, registered)| *other != id && registered == chord)
+        {
+            return Err(format!("{chord} is already registered").into());
+        }
+        state.registered.insert(id, *chord);
+        Ok(())
+    }
+
+    fn unregister(&mut self, id: HotkeyId) -> Result<()> {
+        self.log.record(
+            MockCall::UnregisterHotkey(id),
+            Some(MockMethod::UnregisterHotkey),
+        )?;
+        lock(&self.state).registered.remove(&id);
+        Ok(())
+    }
+
+    fn subscribe(&mut self) -> Receiver<HotkeyId> {
+        let _ = self.log.record(MockCall::SubscribeHotkeys, None);
+        lock(&self.state).subscribers.subscribe()
+    }
+}
+
+/// One of each mock sharing a single call log.
+#[derive(Clone, Default)]
+pub struct MockPlatform {
+    pub log: CallLog,
+    pub overlays: MockOverlayManager,
+    pub event_loop: MockEventLoop,
+    pub input: MockInputSource,
+    pub renderer: MockGpuRenderer,
+    pub tray: MockTrayProvider,
+    pub hotkeys: MockHotkeyProvider,
+}
+
+impl MockPlatform {
+    pub fn new() -> Self {
+        Self::with_monitors(Vec::new())
+    }
+
+    pub fn with_monitors(monitors: Vec<MonitorRect>) -> Self {
+        let log = CallLog::new();
+        let overlays = MockOverlayManager::with_log(log.clone());
+        overlays.state().monitors = monitors;
+        Self {
+            event_loop: MockEventLoop::new(overlays.clone()),
+            overlays,
+            input: MockInputSource::with_log(log.clone()),
+            renderer: MockGpuRenderer::with_log(log.clone()),
+            tray: MockTrayProvider::with_log(log.clone()),
+            hotkeys: MockHotkeyProvider::with_log(log.clone()),
+            log,
+        }
+    }
+
+    /// Boxed handles for the runtime; this value keeps clones for scripting and assertions.
+    pub fn platform(&self) -> Platform {
+        Platform {
+            event_loop: Box::new(self.event_loop.clone()),
+            input: Box::new(self.input.clone()),
+            renderer: Box::new(self.renderer.clone()),
+            tray: Some(Box::new(self.tray.clone())),
+            hotkeys: Some(Box::new(self.hotkeys.clone())),
+        }
+    }
+}
+
+/// 1080p monitor at 100% scale with the given origin, for building test layouts.
+pub fn monitor(key: &str, x: i32, y: i32) -> MonitorRect {
+    MonitorRect {
+        id: MonitorId::from_key(key),
+        x,
+        y,
+        width: 1920,
+        height: 1080,
+        dpi: 96,
+        refresh_hz: None,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn platform_records_calls_and_injected_failures() {
+        let mock = MockPlatform::with_monitors(vec![monitor("left", 0, 0)]);
+        let mut platform = mock.platform();
+
+        let overlays = platform.event_loop.overlays();
+        overlays.init().unwrap();
+        let monitor_events = overlays.subscribe();
+        assert_eq!(overlays.monitors().unwrap(), vec![monitor("left", 0, 0)]);
+        overlays.create_overlays().unwrap();
+        assert!(mock.overlays.overlays_created());
+
+        let right = monitor("right", 1920, 0);
+        mock.overlays.add_monitor(right);
+        assert_eq!(
+            monitor_events.try_recv(),
+            Ok(MonitorEvent::Added { new: right })
+        );
+        assert_eq!(platform.event_loop.overlays().monitors().unwrap().len(), 2);
+
+        let input_events = platform.input.subscribe();
+        assert!(
+            !mock.input.move_cursor(1.0, 2.0),
+            "stopped sources drop input"
+        );
+        platform.input.start().unwrap();
+        assert!(mock.input.move_cursor(10.0, 20.0));
+        assert_eq!(
+            input_events.try_recv(),
+            Ok(InputEvent::CursorMoved { x: 10.0, y: 20.0 })
+        );
+        assert!(input_events.try_recv().is_err());
+
+        platform.renderer.init().unwrap();
+        mock.log.fail_next(MockMethod::RenderFrame, "device lost");
+        let err = platform.renderer.render_frame().unwrap_err();
+        assert_eq!(err.to_string(), "RenderFrame failed: device lost");
+        platform.renderer.render_frame().unwrap();
+        assert_eq!(mock.renderer.frames_rendered(), 2);
+
+        assert_eq!(
+            mock.log.calls(),
+            vec![
+                MockCall::OverlayInit,
+                MockCall::SubscribeMonitors,
+                MockCall::Monitors,
+                MockCall::CreateOverlays,
+                MockCall::Monitors,
+                MockCall::SubscribeInput,
+                MockCall::InputStart,
+                MockCall::RendererInit,
+                MockCall::RenderFrame,
+                MockCall::RenderFrame,
+            ]
+        );
+    }
+
+    #[test]
+    fn hotkeys_reject_taken_chords() {
+        let mock = MockPlatform::new();
+        let mut hotkeys = mock.platform().hotkeys.unwrap();
+        let pressed = hotkeys.subscribe();
+        let chord: KeyChord = "Ctrl+Alt+T".parse().unwrap();
+
+        hotkeys.register(HotkeyId(1), &chord).unwrap();
+        assert!(hotkeys.register(HotkeyId(2), &chord).is_err());
+        assert!(mock.hotkeys.press(&chord));
+        assert_eq!(pressed.try_recv(), Ok(HotkeyId(1)));
+
+        mock.log.fail_next(MockMethod::UnregisterHotkey, "busy");
+        assert!(hotkeys.unregister(HotkeyId(1)).is_err());
+        hotkeys.unregister(HotkeyId(1)).unwrap();
+        assert!(!mock.hotkeys.press(&chord));
+        assert_eq!(
+            mock.log
+                .count(|call| matches!(call, MockCall::RegisterHotkey { .. })),
+            2
+        );
+    }
+}
@@ -0,0 +1,116 @@
+use std::fmt;
+use std::str::FromStr;
+
+use serde::{Deserialize, Serialize};
+
+const FNV_OFFSET_BASIS: u64 = 0xcbf2_9ce4_8422_2325;
+const FNV_PRIME: u64 = 0x0000_0100_0000_01b3;
+
+/// Identity of a physical display that survives unplug/re-plug and docking, unlike OS handles.
+///
+/// Backends derive it from the most stable thing the OS exposes: a hardware/EDID-based device path
+/// when available, the adapter output name otherwise, and the monitor's geometry as a last resort.
+/// Serialized as a 16-digit hex string so it can key maps in config files.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, PartialOrd, Ord, Serialize, Deserialize)]
+#[serde(into = "String", try_from = "String")]
+pub struct MonitorId(u64);
+
+impl MonitorId {
+    pub const fn from_raw(raw: u64) -> Self {
+        Self(raw)
+    }
+
+    pub const fn raw(self) -> u64 {
+        self.0
+    }
+
+    /// Hash a device path, EDID string or output name. Case-insensitive, stable across runs.
+    pub fn from_key(key: &str) -> Self {
+        let mut hash = FNV_OFFSET_BASIS;
+        for byte in key.trim().bytes().map(|byte| byte.to_ascii_lowercase()) {
+            hash ^= byte as u64;
+            hash = hash.wrapping_mul(FNV_PRIME);
+        }
+        Self(hash)
+    }
+
+    /// Fingerprint from position and resolution, for backends without any device identifier.
+    pub fn from_geometry(x: i32, y: i32, width: i32, height: i32) -> Self {
+        Self::from_key(&format!("geometry:{x},{y},{width}x{height}"))
+    }
+}
+
+impl fmt::Display for MonitorId {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{:016x}", self.0)
+    }
+}
+
+impl FromStr for MonitorId {
+    type Err = std::num::ParseIntError;
+
+    fn from_str(s: &str) -> std::result::Result<Self, Self::Err> {
+        u64::from_str_radix(s.trim(), 16).map(Self)
+    }
+}
+
+impl From<MonitorId> for String {
+    fn from(id: MonitorId) -> Self {
+        id.to_string()
+    }
+}
+
+impl TryFrom<String> for MonitorId {
+    type Error = std::num::ParseIntError;
+
+    fn try_from(value: String) -> std::result::Result<Self, Self::Error> {
+        value.parse()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::collections::BTreeMap;
+
+    use super::*;
+
+    #[test]
+    fn hash_is_fnv1a() {
+        // Reference FNV-1a 64 values; config files key on these, so they must never change.
+        assert_eq!(MonitorId::from_key("").raw(), 0xcbf2_9ce4_8422_2325);
+        assert_eq!(MonitorId::from_key("a").raw(), 0xaf63_dc4c_8601_ec8c);
+        assert_eq!(MonitorId::from_key("foobar").raw(), 0x8594_4171_f739_67e8);
+    }
+
+    #[test]
+    fn keys_ignore_case_and_surrounding_whitespace() {
+        assert_eq!(
+            MonitorId::from_key(" FooBar\n"),
+            MonitorId::from_key("foobar")
+        );
+        assert_ne!(
+            MonitorId::from_geometry(0, 0, 1920, 1080),
+            MonitorId::from_geometry(1920, 0, 1920, 1080)
+        );
+    }
+
+    #[test]
+    fn serializes_as_padded_hex() {
+        let id = MonitorId::from_raw(0xab);
+        assert_eq!(id.to_string(), "00000000000000ab");
+        assert_eq!(serde_json::to_string(&id).unwrap(), "\"00000000000000ab\"");
+        assert_eq!(
+            serde_json::from_str::<MonitorId>("\"00000000000000AB\"").unwrap(),
+            id
+        );
+        assert!(serde_json::from_str::<MonitorId>("\"DP-1\"").is_err());
+
+        let map = BTreeMap::from([(MonitorId::from_key("foobar"), 1)]);
+        let json = serde_json::to_string(&map).unwrap();
+        assert_eq!(json, "{\"85944171f73967e8\":1}");
+        assert_eq!(
+            serde_json::from_str::<BTreeMap<MonitorId, i32>>(&json).unwrap(),
+            map
+        );
+    }
+}
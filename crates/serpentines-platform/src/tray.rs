@@ -0,0 +1,98 @@
+use std::sync::mpsc::Receiver;
+
+use crate::Result;
+
+/// Frame pacing profile selectable from the tray.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum PowerMode {
+    Normal,
+    LowPower,
+}
+
+/// What a tray menu entry (or a click on the icon) asks the app to do.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum TrayAction {
+    ToggleTrails,
+    /// Switch to the library preset with this name.
+    SelectPreset(String),
+    SetPowerMode(PowerMode),
+    OpenSettings,
+    Quit,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub enum TrayItem {
+    Action {
+        label: String,
+        action: TrayAction,
+        enabled: bool,
+    },
+    /// Checkbox entry; selecting it sends `action` and the app decides the new state.
+    Check {
+        label: String,
+        checked: bool,
+        action: TrayAction,
+    },
+    Submenu {
+        label: String,
+        items: Vec<TrayItem>,
+    },
+    Separator,
+}
+
+impl TrayItem {
+    pub fn action(label: impl Into<String>, action: TrayAction) -> Self {
+        TrayItem::Action {
+            label: label.into(),
+            action,
+            enabled: true,
+        }
+    }
+
+    pub fn check(label: impl Into<String>, checked: bool, action: TrayAction) -> Self {
+        TrayItem::Check {
+            label: label.into(),
+            checked,
+            action,
+        }
+    }
+
+    pub fn submenu(label: impl Into<String>, items: Vec<TrayItem>) -> Self {
+        TrayItem::Submenu {
+            label: label.into(),
+            items,
+        }
+    }
+}
+
+/// Complete tray menu. Providers rebuild their native menu from it on every `set_menu`.
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct TrayMenu {
+    pub tooltip: String,
+    pub items: Vec<TrayItem>,
+}
+
+impl TrayMenu {
+    /// Every entry in menu order, with submenus flattened.
+    pub fn entries(&self) -> Vec<&TrayItem> {
+        fn walk<'a>(items: &'a [TrayItem], out: &mut Vec<&'a TrayItem>) {
+            for item in items {
+                out.push(item);
+                if let TrayItem::Submenu { items, .. } = item {
+                    walk(items, out);
+                }
+            }
+        }
+        let mut entries = Vec::new();
+        walk(&self.items, &mut entries);
+        entries
+    }
+}
+
+/// System tray icon with an app-defined menu.
+pub trait TrayProvider {
+    /// Replace the menu shown by the tray.
+    fn set_menu(&mut self, menu: &TrayMenu) -> Result<()>;
+    /// Receive menu selections. Activating the icon itself sends `TrayAction::OpenSettings`.
+    fn subscribe(&mut self) -> Receiver<TrayAction>;
+}
@@ -0,0 +1,161 @@
+//! StatusNotifierItem tray shared by the Linux backends (X11 and Wayland).
+use serpentines_platform::{Result, Subscribers, TrayAction, TrayItem, TrayMenu, TrayProvider};
+
+use std::sync::mpsc::Receiver;
+use std::sync::{Arc, Mutex};
+
+use ksni::blocking::{Handle, TrayMethods};
+use ksni::menu::{CheckmarkItem, StandardItem, SubMenu};
+
+type ActionSubscribers = Arc<Mutex<Subscribers<TrayAction>>>;
+
+/// Tray served over D-Bus. Works under any X11 or Wayland desktop with an SNI host, which covers
+/// KDE, GNOME with the AppIndicator extension, and most panels.
+struct SerpentinesTray {
+    icon: ksni::Icon,
+    menu: TrayMenu,
+    subscribers: ActionSubscribers,
+}
+
+impl SerpentinesTray {
+    fn publish(&self, action: &TrayAction) {
+        if let Ok(mut subscribers) = self.subscribers.lock() {
+            subscribers.publish(action);
+        }
+    }
+}
+
+impl ksni::Tray for SerpentinesTray {
+    fn id(&self) -> String {
+        "serpentines".into()
+    }
+
+    fn title(&self) -> String {
+        "Serpentines".into()
+    }
+
+    fn icon_pixmap(&self) -> Vec<ksni::Icon> {
+        vec![self.icon.clone()]
+    }
+
+    fn tool_tip(&self) -> ksni::ToolTip {
+        let title = if self.menu.tooltip.is_empty() {
+            "Serpentines".into()
+        } else {
+            self.menu.tooltip.clone()
+        };
+        ksni::ToolTip {
+            title,
+            ..Default::default()
+        }
+    }
+
+    fn activate(&mut self, _x: i32, _y: i32) {
+        self.publish(&TrayAction::OpenSettings);
+    }
+
+    fn menu(&self) -> Vec<ksni::MenuItem<Self>> {
+        self.menu.items.iter().map(menu_item).collect()
+    }
+}
+
+fn menu_item(item: &TrayItem) -> ksni::MenuItem<SerpentinesTray> {
+    match item {
+        TrayItem::Action {
+            label,
+            action,
+            enabled,
+        } => {
+            let action = action.clone();
+            StandardItem {
+                label: label.clone(),
+                enabled: *enabled,
+                activate: Box::new(move |tray: &mut SerpentinesTray| tray.publish(&action)),
+                ..Default::default()
+            }
+            .into()
+        }
+        TrayItem::Check {
+            label,
+            checked,
+            action,
+        } => {
+            let action = action.clone();
+            CheckmarkItem {
+                label: label.clone(),
+                checked: *checked,
+                activate: Box::new(move |tray: &mut SerpentinesTray| tray.publish(&action)),
+                ..Default::default()
+            }
+            .into()
+        }
+        TrayItem::Submenu { label, items } => SubMenu {
+            label: label.clone(),
+            submenu: items.iter().map(menu_item).collect(),
+            ..Default::default()
+        }
+        .into(),
+        TrayItem::Separator => ksni::MenuItem::Separator,
+    }
+}
+
+pub struct SniTray {
+    handle: Handle<SerpentinesTray>,
+    subscribers: ActionSubscribers,
+}
+
+impl SniTray {
+    /// Register the tray with an empty menu; the app fills it with `set_menu`.
+    pub fn new() -> Result<Self> {
+        let ico_bytes = include_bytes!("../../../assets/icon.ico");
+        let img =
+            image::load_from_memory_with_format(ico_bytes, image::ImageFormat::Ico)?.to_rgba8();
+        let width = img.width() as i32;
+        let height = img.height() as i32;
+        // SNI pixmaps are ARGB32 in network byte order.
+        let data = img
+            .pixels()
+            .flat_map(|pixel| {
+                let [r, g, b, a] = pixel.0;
+                [a, r, g, b]
+            })
+            .collect();
+        let subscribers: ActionSubscribers = Arc::new(Mutex::new(Subscribers::new()));
+        let tray = SerpentinesTray {
+            icon: ksni::Icon {
+                width,
+                height,
+                data,
+            },
+            menu: TrayMenu::default(),
+            subscribers: Arc::clone(&subscribers),
+        };
+        let handle = tray.spawn()?;
+        Ok(Self {
+            handle,
+            subscribers,
+        })
+    }
+}
+
+impl TrayProvider for SniTray {
+    fn set_menu(&mut self, menu: &TrayMenu) -> Result<()> {
+        let menu = menu.clone();
+        self.handle
+            .update(move |tray| tray.menu = menu)
+            .ok_or_else(|| "tray service has shut down".into())
+    }
+
+    fn subscribe(&mut self) -> Receiver<TrayAction> {
+        match self.subscribers.lock() {
+            Ok(mut subscribers) => subscribers.subscribe(),
+            Err(poisoned) => poisoned.into_inner().subscribe(),
+        }
+    }
+}
+
+impl Drop for SniTray {
+    fn drop(&mut self) {
+        let _ = self.handle.shutdown();
+    }
+}
@@ -0,0 +1,652 @@
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::Arc;
+
+use crossbeam_channel::{Receiver, Sender};
+use serpentines_core::{EngineConfig, EngineStats, TrailPreset};
+use serpentines_platform::MonitorRect;
+use tracing::info;
+
+use crate::logs::LogView;
+use crate::{
+    BrowserAction, EditorAction, LogBuffer, PresetBrowser, PresetEditor, TrailPreview, UiCommand,
+    UiEvent, UiSettings,
+};
+
+/// Height of the trail preview canvas, in points.
+const PREVIEW_HEIGHT: f32 = 160.0;
+const FRAME_PLOT_HEIGHT: f32 = 80.0;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum Tab {
+    Settings,
+    Presets,
+    Stats,
+    Logs,
+}
+
+/// Shows and hides the panel window. Shared between the app and the command forwarder, because a
+/// hidden eframe window stops calling `update` and must be shown from outside.
+#[derive(Clone)]
+pub(crate) struct WindowControl {
+    context: egui::Context,
+    visible: Arc<AtomicBool>,
+    #[cfg(target_os = "windows")]
+    hwnd: Option<isize>,
+}
+
+impl WindowControl {
+    pub(crate) fn new(context: egui::Context) -> Self {
+        Self {
+            context,
+            visible: Arc::new(AtomicBool::new(true)),
+            #[cfg(target_os = "windows")]
+            hwnd: None,
+        }
+    }
+
+    #[cfg(target_os = "windows")]
+    pub(crate) fn with_hwnd(mut self, hwnd: Option<isize>) -> Self {
+        self.hwnd = hwnd;
+        self
+    }
+
+    pub(crate) fn context(&self) -> &egui::Context {
+        &self.context
+    }
+
+    pub(crate) fn is_visible(&self) -> bool {
+        self.visible.load(Ordering::SeqCst)
+    }
+
+    /// Apply a Show/Hide/Toggle command. Returns false for any other command.
+    pub(crate) fn apply(&self, command: &UiCommand) -> bool {
+        let visible = match command {
+            UiCommand::Show => true,
+            UiCommand::Hide => false,
+            UiCommand::Toggle => !self.is_visible(),
+            _ => return false,
+        };
+        self.set_visible(visible);
+        true
+    }
+
+    pub(crate) fn set_visible(&self, visible: bool) {
+        info!("UI: visible -> {visible}");
+        self.visible.store(visible, Ordering::SeqCst);
+        #[cfg(target_os = "windows")]
+        if let Some(hwnd_val) = self.hwnd {
+            use windows::Win32::Foundation::HWND;
+            use windows::Win32::UI::WindowsAndMessaging::{
+                SetForegroundWindow, ShowWindow, SW_HIDE, SW_SHOW,
+            };
+            let hwnd = HWND(hwnd_val as *mut core::ffi::c_void);
+            unsafe {
+                let _ = ShowWindow(hwnd, if visible { SW_SHOW } else { SW_HIDE });
+                if visible {
+                    let _ = SetForegroundWindow(hwnd);
+                }
+            }
+        }
+        self.context
+            .send_viewport_cmd(egui::ViewportCommand::Visible(visible));
+        if visible {
+            self.context.send_viewport_cmd(egui::ViewportCommand::Focus);
+        }
+        self.context.request_repaint();
+    }
+}
+
+/// The control panel. Everything it knows comes in as `UiCommand`s and everything the user does
+/// goes out as `UiEvent`s, so it can be driven without a window via `run_headless`.
+pub struct SerpentinesApp {
+    commands: Receiver<UiCommand>,
+    events: Sender<UiEvent>,
+    window: WindowControl,
+    config: Option<EngineConfig>,
+    presets: Vec<TrailPreset>,
+    editor: Option<PresetEditor>,
+    preview: TrailPreview,
+    browser: PresetBrowser,
+    tab: Tab,
+    stats: Option<EngineStats>,
+    monitors: Vec<MonitorRect>,
+    settings: UiSettings,
+    logs: Option<LogBuffer>,
+    log_view: LogView,
+}
+
+impl SerpentinesApp {
+    /// Panel reading `commands` and reporting on `events`, rendering into `context`.
+    pub fn new(
+        commands: Receiver<UiCommand>,
+        events: Sender<UiEvent>,
+        context: &egui::Context,
+    ) -> Self {
+        Self::with_window(commands, events, WindowControl::new(context.clone()))
+    }
+
+    pub(crate) fn with_window(
+        commands: Receiver<UiCommand>,
+        events: Sender<UiEvent>,
+        window: WindowControl,
+    ) -> Self {
+        Self {
+            commands,
+            events,
+            window,
+            config: None,
+            presets: Vec::new(),
+            editor: None,
+            preview: TrailPreview::new(TrailPreset::default()),
+            browser: PresetBrowser::new(),
+            tab: Tab::Settings,
+            stats: None,
+            monitors: Vec::new(),
+            settings: UiSettings::default(),
+            logs: None,
+            log_view: LogView::default(),
+        }
+    }
+
+    /// Show `logs` in a log tab.
+    pub fn with_logs(mut self, logs: LogBuffer) -> Self {
+        self.logs = Some(logs);
+        self
+    }
+
+    pub fn config(&self) -> Option<&EngineConfig> {
+        self.config.as_ref()
+    }
+
+    pub fn presets(&self) -> &[TrailPreset] {
+        &self.presets
+    }
+
+    pub fn editor(&self) -> Option<&PresetEditor> {
+        self.editor.as_ref()
+    }
+
+    pub fn preview(&self) -> &TrailPreview {
+        &self.preview
+    }
+
+    pub fn stats(&self) -> Option<&EngineStats> {
+        self.stats.as_ref()
+    }
+
+    pub fn monitors(&self) -> &[MonitorRect] {
+        &self.monitors
+    }
+
+    pub fn settings(&self) -> &UiSettings {
+        &self.settings
+    }
+
+    pub fn is_visible(&self) -> bool {
+        self.window.is_visible()
+    }
+
+    /// Apply every queued command.
+    pub fn process_commands(&mut self) {
+        while let Ok(command) = self.commands.try_recv() {
+            self.handle_command(command);
+        }
+    }
+
+    pub fn handle_command(&mut self, command: UiCommand) {
+        if self.window.apply(&command) {
+            return;
+        }
+        match command {
+            UiCommand::Show | UiCommand::Hide | UiCommand::Toggle => {}
+            UiCommand::LoadConfig { config, presets } => {
+                // Keep the edit history unless the preset was changed from elsewhere.
+                let current = self.editor.as_ref().map(|editor| editor.preset());
+                if current != Some(&config.preset) {
+                    self.editor = Some(self.new_editor(config.preset.clone()));
+                }
+                self.config = Some(*config);
+                self.presets = presets;
+            }
+            UiCommand::LoadUiSettings(settings) => {
+                self.preview.set_auto_demo(settings.preview_auto_demo);
+                if let Some(editor) = self.editor.as_mut() {
+                    editor.set_snap(settings.snap_to_grid);
+                }
+                self.settings = settings;
+            }
+            UiCommand::UpdateStats(stats) => self.stats = Some(stats),
+            UiCommand::MonitorsChanged(monitors) => self.monitors = monitors,
+        }
+    }
+
+    /// Run one frame without a window: apply queued commands, lay out the panel against `input`,
+    /// and return egui's output. For tests and tooling.
+    pub fn run_headless(
+        &mut self,
+        context: &egui::Context,
+        input: egui::RawInput,
+    ) -> egui::FullOutput {
+        self.process_commands();
+        context.run(input, |context| self.show(context))
+    }
+
+    fn new_editor(&self, preset: TrailPreset) -> PresetEditor {
+        let mut editor = PresetEditor::new(preset);
+        editor.set_snap(self.settings.snap_to_grid);
+        editor
+    }
+
+    /// Report panel preferences changed through their widgets this frame.
+    fn sync_settings(&mut self) {
+        let settings = UiSettings {
+            preview_auto_demo: self.preview.auto_demo(),
+            snap_to_grid: self
+                .editor
+                .as_ref()
+                .map_or(self.settings.snap_to_grid, PresetEditor::snap),
+            ..self.settings.clone()
+        };
+        if settings != self.settings {
+            self.settings = settings.clone();
+            self.send(UiEvent::UiSettingsChanged(settings));
+        }
+    }
+
+    fn send(&self, event: UiEvent) {
+        info!("UI: {event:?}");
+        let _ = self.events.send(event);
+    }
+
+    /// Lay out the panel.
+    pub fn show(&mut self, context: &egui::Context) {
+        egui::CentralPanel::default().show(context, |ui| {
+            ui.horizontal(|ui| {
+                ui.heading("Serpentines");
+                ui.separator();
+                ui.selectable_value(&mut self.tab, Tab::Settings, "Settings");
+                ui.selectable_value(&mut self.tab, Tab::Presets, "Presets");
+                ui.selectable_value(&mut self.tab, Tab::Stats, "Stats");
+                if self.logs.is_some() {
+                    ui.selectable_value(&mut self.tab, Tab::Logs, "Log");
+                }
+            });
+            ui.separator();
+            if self.tab == Tab::Logs {
+                if let Some(logs) = &self.logs {
+                    self.log_view.show(ui, logs);
+                }
+                return;
+            }
+            let Some(config) = self.config.clone() else {
+                ui.label("Waiting for the engine...");
+                return;
+            };
+            egui::ScrollArea::vertical().show(ui, |ui| match self.tab {
+                Tab::Settings => self.show_settings(ui, &config),
+                Tab::Presets => self.show_browser(ui, &config),
+                Tab::Stats => self.show_stats(ui, &config),
+                Tab::Logs => {}
+            });
+        });
+        self.sync_settings();
+    }
+
+    fn show_settings(&mut self, ui: &mut egui::Ui, config: &EngineConfig) {
+        let mut enabled = config.enabled;
+        if ui.checkbox(&mut enabled, "Enable trails").changed() {
+            if let Some(config) = self.config.as_mut() {
+                config.enabled = enabled;
+            }
+            self.send(UiEvent::EnableToggled(enabled));
+        }
+
+        let mut selected = None;
+        egui::ComboBox::from_label("Preset")
+            .selected_text(&config.preset.name)
+            .show_ui(ui, |ui| {
+                for preset in &self.presets {
+                    let current = preset.name.eq_ignore_ascii_case(&config.preset.name);
+                    if ui.selectable_label(current, &preset.name).clicked() && !current {
+                        selected = Some(preset.clone());
+                    }
+                }
+            });
+        if let Some(preset) = selected {
+            self.select_preset(preset);
+        }
+
+        let mut low_power = config.power.low_power;
+        if ui.checkbox(&mut low_power, "Low power mode").changed() {
+            let mut edited = config.clone();
+            edited.power.low_power = low_power;
+            self.config = Some(edited.clone());
+            self.send(UiEvent::ConfigEdited(edited));
+        }
+
+        egui::CollapsingHeader::new("Edit preset")
+            .default_open(true)
+            .show(ui, |ui| self.show_editor(ui));
+
+        egui::CollapsingHeader::new("Preview")
+            .default_open(true)
+            .show(ui, |ui| {
+                let preset = match &self.editor {
+                    Some(editor) => editor.preset(),
+                    None => &config.preset,
+                };
+                self.preview.set_preset(preset);
+                self.preview.show(ui, PREVIEW_HEIGHT);
+            });
+
+        let mut open_at_launch = !self.settings.start_hidden;
+        if ui
+            .checkbox(&mut open_at_launch, "Open this window at launch")
+            .changed()
+        {
+            self.settings.start_hidden = !open_at_launch;
+            self.send(UiEvent::UiSettingsChanged(self.settings.clone()));
+        }
+
+        ui.separator();
+        if ui.button("Quit Serpentines").clicked() {
+            self.send(UiEvent::QuitRequested);
+        }
+    }
+
+    fn show_stats(&mut self, ui: &mut egui::Ui, config: &EngineConfig) {
+        let mut show_hud = config.show_hud;
+        if ui.checkbox(&mut show_hud, "Show HUD on overlay").changed() {
+            let mut edited = config.clone();
+            edited.show_hud = show_hud;
+            self.config = Some(edited.clone());
+            self.send(UiEvent::ConfigEdited(edited));
+        }
+        ui.separator();
+        let Some(stats) = &self.stats else {
+            ui.label("No stats yet");
+            return;
+        };
+        egui::Grid::new("engine_stats")
+            .num_columns(2)
+            .striped(true)
+            .show(ui, |ui| {
+                let mut row = |label: &str, value: String| {
+                    ui.label(label);
+                    ui.monospace(value);
+                    ui.end_row();
+                };
+                row("FPS", format!("{:.1}", stats.fps));
+                row(
+                    "Frame time p50 / p95 / p99",
+                    format!(
+                        "{:.2} / {:.2} / {:.2} ms",
+                        stats.frame_time.p50_ms, stats.frame_time.p95_ms, stats.frame_time.p99_ms
+                    ),
+                );
+                row("Worst frame", format!("{:.2} ms", stats.frame_time.max_ms));
+                row(
+                    "Update / render",
+                    format!("{:.2} / {:.2} ms", stats.update_ms, stats.render_ms),
+                );
+                if let Some(gpu_ms) = stats.renderer.gpu_ms {
+                    row("GPU", format!("{gpu_ms:.2} ms"));
+                }
+                row(
+                    "Particles",
+                    format!("{} / {}", stats.live_particles, stats.max_particles),
+                );
+                row("Emission rate", format!("{:.0} /s", stats.emission_rate));
+                row("Quality", stats.quality.to_string());
+                row(
+                    "State",
+                    if stats.paused {
+                        "paused".into()
+                    } else if stats.enabled {
+                        "running".into()
+                    } else {
+                        "disabled".into()
+                    },
+                );
+                row("Displays", self.monitors.len().to_string());
+            });
+        ui.separator();
+        ui.label("Recent frame times");
+        frame_time_plot(ui, &stats.recent_frame_ms);
+    }
+
+    fn show_browser(&mut self, ui: &mut egui::Ui, config: &EngineConfig) {
+        for action in self.browser.show(ui, &self.presets, &config.preset.name) {
+            match action {
+                BrowserAction::Select(name) => {
+                    if let Some(preset) = self.presets.iter().find(|preset| preset.name == name) {
+                        self.select_preset(preset.clone());
+                    }
+                }
+                BrowserAction::Edit(edit) => self.send(UiEvent::LibraryEdited(edit)),
+            }
+        }
+    }
+
+    fn select_preset(&mut self, preset: TrailPreset) {
+        let name = preset.name.clone();
+        self.editor = Some(self.new_editor(preset.clone()));
+        if let Some(config) = self.config.as_mut() {
+            config.preset = preset;
+        }
+        self.send(UiEvent::PresetSelected(name));
+    }
+}
+
+impl SerpentinesApp {
+    fn show_editor(&mut self, ui: &mut egui::Ui) {
+        let Some(editor) = self.editor.as_mut() else {
+            return;
+        };
+        match editor.show(ui) {
+            Some(EditorAction::Edited) => {
+                let preset = editor.preset().clone();
+                if let Some(config) = self.config.as_mut() {
+                    config.preset = preset.clone();
+                }
+                // Invalid presets stay in the editor until fixed.
+                if editor.is_valid() {
+                    self.send(UiEvent::PresetEdited(preset));
+                }
+            }
+            Some(EditorAction::Save) => {
+                editor.mark_saved();
+                let preset = editor.preset().clone();
+                self.send(UiEvent::PresetSaved(preset));
+            }
+            None => {}
+        }
+    }
+}
+
+impl eframe::App for SerpentinesApp {
+    fn update(&mut self, context: &egui::Context, _frame: &mut eframe::Frame) {
+        // Intercept OS close requests via egui/eframe: hide (Visible(false)) and cancel the close.
+        let close_requested = context.input(|i| i.viewport().close_requested());
+        if close_requested {
+            info!("UI: CloseRequested -> hide and cancel");
+            context.send_viewport_cmd(egui::ViewportCommand::CancelClose);
+            self.window.set_visible(false);
+        }
+        self.process_commands();
+        self.show(context);
+    }
+}
+
+/// Bar chart of frame times, scaled to the slowest frame shown.
+fn frame_time_plot(ui: &mut egui::Ui, frame_ms: &[f32]) {
+    let size = egui::vec2(ui.available_width(), FRAME_PLOT_HEIGHT);
+    let (rect, _) = ui.allocate_exact_size(size, egui::Sense::hover());
+    let painter = ui.painter_at(rect);
+    painter.rect_filled(rect, 2.0, ui.visuals().extreme_bg_color);
+    let peak = frame_ms.iter().copied().fold(0.0, f32::max);
+    if frame_ms.is_empty() || peak <= 0.0 {
+        return;
+    }
+    let bar_width = rect.width() / frame_ms.len() as f32;
+    let color = ui.visuals().selection.bg_fill;
+    for (index, ms) in frame_ms.iter().enumerate() {
+        let height = ms / peak * rect.height();
+        let left = rect.left() + index as f32 * bar_width;
+        let bar = egui::Rect::from_min_max(
+            egui::pos2(left, rect.bottom() - height),
+            egui::pos2(left + bar_width.max(1.0), rect.bottom()),
+        );
+        painter.rect_filled(bar, 0.0, color);
+    }
+    painter.text(
+        rect.left_top() + egui::vec2(4.0, 2.0),
+        egui::Align2::LEFT_TOP,
+        format!("{peak:.1} ms"),
+        egui::FontId::monospace(11.0),
+        ui.visuals().text_color(),
+    );
+}
+
+#[cfg(test)]
+mod tests {
+    use crossbeam_channel::unbounded;
+    use serpentines_core::{PresetLibrary, TrailEngine};
+
+    use super::*;
+
+    struct Harness {
+        app: SerpentinesApp,
+        context: egui::Context,
+        commands: crossbeam_channel::Sender<UiCommand>,
+        events: crossbeam_channel::Receiver<UiEvent>,
+    }
+
+    impl Harness {
+        fn new() -> Self {
+            let (commands, command_receiver) = unbounded();
+            let (event_sender, events) = unbounded();
+            let context = egui::Context::default();
+            let app = SerpentinesApp::new(command_receiver, event_sender, &context);
+            Self {
+                app,
+                context,
+                commands,
+                events,
+            }
+        }
+
+        fn frame(&mut self, events: Vec<egui::Event>) -> egui::FullOutput {
+            let input = egui::RawInput {
+                screen_rect: Some(egui::Rect::from_min_size(
+                    egui::Pos2::ZERO,
+                    egui::vec2(800.0, 2000.0),
+                )),
+                events,
+                ..egui::RawInput::default()
+            };
+            self.app.run_headless(&self.context, input)
+        }
+
+        /// Press and release the primary button over the text `label` in the last frame.
+        fn click(&mut self, label: &str) {
+            let output = self.frame(Vec::new());
+            let pos = find_text(output.shapes.iter().map(|clipped| &clipped.shape), label)
+                .unwrap_or_else(|| panic!("'{label}' not drawn"));
+            let button = |pressed| egui::Event::PointerButton {
+                pos,
+                button: egui::PointerButton::Primary,
+                pressed,
+                modifiers: egui::Modifiers::NONE,
+            };
+            self.frame(vec![egui::Event::PointerMoved(pos), button(true)]);
+            self.frame(vec![button(false)]);
+            self.frame(Vec::new());
+        }
+
+        fn events(&self) -> Vec<UiEvent> {
+            self.events.try_iter().collect()
+        }
+    }
+
+    fn find_text<'a>(
+        shapes: impl Iterator<Item = &'a egui::Shape>,
+        label: &str,
+    ) -> Option<egui::Pos2> {
+        for shape in shapes {
+            match shape {
+                egui::Shape::Text(text) if text.galley.text() == label => {
+                    return Some(text.pos + text.galley.rect.center().to_vec2());
+                }
+                egui::Shape::Vec(shapes) => {
+                    if let Some(pos) = find_text(shapes.iter(), label) {
+                        return Some(pos);
+                    }
+                }
+                _ => {}
+            }
+        }
+        None
+    }
+
+    fn load_config() -> UiCommand {
+        UiCommand::LoadConfig {
+            config: Box::new(EngineConfig::default()),
+            presets: PresetLibrary::builtin().presets().to_vec(),
+        }
+    }
+
+    #[test]
+    fn commands_update_panel_state() {
+        let mut harness = Harness::new();
+        harness.frame(Vec::new());
+        assert!(harness.app.config().is_none());
+
+        harness.commands.send(load_config()).unwrap();
+        harness.commands.send(UiCommand::Toggle).unwrap();
+        let stats = EngineStats::from_engine(&TrailEngine::new(EngineConfig::default()));
+        harness
+            .commands
+            .send(UiCommand::UpdateStats(stats))
+            .unwrap();
+        harness.frame(Vec::new());
+
+        assert_eq!(harness.app.presets().len(), 4);
+        assert_eq!(
+            harness
+                .app
+                .editor()
+                .map(|editor| editor.preset().name.as_str()),
+            Some("Default")
+        );
+        assert!(harness.app.stats().is_some());
+        assert!(!harness.app.is_visible());
+        harness.commands.send(UiCommand::Toggle).unwrap();
+        harness.frame(Vec::new());
+        assert!(harness.app.is_visible());
+        assert!(harness.events().is_empty());
+    }
+
+    #[test]
+    fn widgets_send_events() {
+        let mut harness = Harness::new();
+        harness.commands.send(load_config()).unwrap();
+        harness.frame(Vec::new());
+
+        harness.click("Enable trails");
+        assert!(matches!(
+            harness.events()[..],
+            [UiEvent::EnableToggled(false)]
+        ));
+        assert!(!harness.app.config().unwrap().enabled);
+
+        harness.click("Open this window at launch");
+        match &harness.events()[..] {
+            [UiEvent::UiSettingsChanged(settings)] => assert!(settings.start_hidden),
+            events => panic!("unexpected events {events:?}"),
+        }
+
+        harness.click("Quit Serpentines");
+        assert!(matches!(harness.events()[..], [UiEvent::QuitRequested]));
+    }
+}
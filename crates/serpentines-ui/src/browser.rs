@@ -0,0 +1,298 @@
+//! Preset browser: thumbnail grid with search, tag and favorite filters, library edits and pack
+//! import by drag and drop.
+
+use std::collections::{BTreeSet, HashMap};
+use std::path::PathBuf;
+
+use serpentines_core::pack::PACK_EXTENSION;
+use serpentines_core::{render_thumbnail, LibraryEdit, PresetFilter, PresetPack, TrailPreset};
+use tracing::warn;
+
+const THUMBNAIL_WIDTH: u32 = 128;
+const THUMBNAIL_HEIGHT: u32 = 80;
+
+/// What the user asked for in the browser this frame.
+#[derive(Debug, Clone, PartialEq)]
+pub enum BrowserAction {
+    /// Make the named preset active.
+    Select(String),
+    Edit(LibraryEdit),
+}
+
+/// Name and tags being edited for one preset.
+struct Details {
+    preset: String,
+    name: String,
+    tags: String,
+}
+
+#[derive(Default)]
+pub struct PresetBrowser {
+    filter: PresetFilter,
+    /// Rendered thumbnails by preset name, with the preset they were rendered from.
+    thumbnails: HashMap<String, (TrailPreset, egui::TextureHandle)>,
+    editing: Option<Details>,
+    /// Import results and errors, newest last.
+    messages: Vec<String>,
+}
+
+impl PresetBrowser {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn filter(&self) -> &PresetFilter {
+        &self.filter
+    }
+
+    pub fn filter_mut(&mut self) -> &mut PresetFilter {
+        &mut self.filter
+    }
+
+    pub fn messages(&self) -> &[String] {
+        &self.messages
+    }
+
+    /// Read preset packs from `paths`. Failures are reported in `messages`; returns an import of
+    /// every preset that loaded, if any.
+    pub fn import_files(
+        &mut self,
+        paths: impl IntoIterator<Item = PathBuf>,
+    ) -> Option<LibraryEdit> {
+        let mut presets = Vec::new();
+        for path in paths {
+            let is_pack = path
+                .extension()
+                .is_some_and(|extension| extension.eq_ignore_ascii_case(PACK_EXTENSION));
+            if !is_pack {
+                self.messages.push(format!(
+                    "{}: not a .{PACK_EXTENSION} preset pack",
+                    path.display()
+                ));
+                continue;
+            }
+            match PresetPack::load(&path) {
+                Ok(pack) => {
+                    self.messages.push(format!(
+                        "Imported {} presets from '{}'",
+                        pack.presets.len(),
+                        pack.name
+                    ));
+                    presets.extend(pack.presets);
+                }
+                Err(err) => {
+                    warn!("failed to import {}: {err}", path.display());
+                    self.messages.push(format!("{}: {err}", path.display()));
+                }
+            }
+        }
+        (!presets.is_empty()).then_some(LibraryEdit::Import { presets })
+    }
+
+    /// Lay out the browser for `presets`, highlighting the one named `active`.
+    pub fn show(
+        &mut self,
+        ui: &mut egui::Ui,
+        presets: &[TrailPreset],
+        active: &str,
+    ) -> Vec<BrowserAction> {
+        let mut actions = Vec::new();
+        self.show_filters(ui, presets);
+        self.handle_drops(ui, &mut actions);
+        ui.separator();
+
+        let visible: Vec<&TrailPreset> = presets
+            .iter()
+            .filter(|preset| self.filter.matches(preset))
+            .collect();
+        if visible.is_empty() {
+            ui.label("No presets match.");
+        }
+        ui.horizontal_wrapped(|ui| {
+            for preset in visible {
+                let is_active = preset.name.eq_ignore_ascii_case(active);
+                ui.group(|ui| {
+                    ui.set_width(THUMBNAIL_WIDTH as f32);
+                    ui.vertical(|ui| {
+                        self.show_card(ui, preset, is_active, presets.len(), &mut actions)
+                    });
+                });
+            }
+        });
+        self.thumbnails
+            .retain(|name, _| presets.iter().any(|preset| &preset.name == name));
+        actions
+    }
+
+    fn show_filters(&mut self, ui: &mut egui::Ui, presets: &[TrailPreset]) {
+        let tags: BTreeSet<String> = presets
+            .iter()
+            .flat_map(|preset| preset.tags.iter().map(|tag| tag.to_lowercase()))
+            .collect();
+        ui.horizontal(|ui| {
+            ui.label("Search");
+            ui.text_edit_singleline(&mut self.filter.text);
+            egui::ComboBox::from_id_salt("preset_tag_filter")
+                .selected_text(self.filter.tag.as_deref().unwrap_or("Any tag"))
+                .show_ui(ui, |ui| {
+                    ui.selectable_value(&mut self.filter.tag, None, "Any tag");
+                    for tag in tags {
+                        ui.selectable_value(&mut self.filter.tag, Some(tag.clone()), tag);
+                    }
+                });
+            ui.checkbox(&mut self.filter.favorites_only, "Favorites");
+        });
+    }
+
+    fn handle_drops(&mut self, ui: &mut egui::Ui, actions: &mut Vec<BrowserAction>) {
+        let (hovering, dropped) = ui.input(|input| {
+            let dropped: Vec<PathBuf> = input
+                .raw
+                .dropped_files
+                .iter()
+                .filter_map(|file| file.path.clone())
+                .collect();
+            (!input.raw.hovered_files.is_empty(), dropped)
+        });
+        if hovering {
+            ui.label(format!(
+                "Drop .{PACK_EXTENSION} preset packs to import them"
+            ));
+        } else {
+            ui.weak(format!(
+                "Drag .{PACK_EXTENSION} preset packs here to import them"
+            ));
+        }
+        if !dropped.is_empty() {
+            if let Some(edit) = self.import_files(dropped) {
+                actions.push(BrowserAction::Edit(edit));
+            }
+        }
+        if !self.messages.is_empty() {
+            for message in &self.messages {
+                ui.label(message);
+            }
+            if ui.small_button("Clear").clicked() {
+                self.messages.clear();
+            }
+        }
+    }
+
+    fn show_card(
+        &mut self,
+        ui: &mut egui::Ui,
+        preset: &TrailPreset,
+        is_active: bool,
+        preset_count: usize,
+        actions: &mut Vec<BrowserAction>,
+    ) {
+        let texture = self.thumbnail(ui.ctx(), preset);
+        let size = egui::vec2(THUMBNAIL_WIDTH as f32, THUMBNAIL_HEIGHT as f32);
+        let image = egui::Image::new((texture.id(), size));
+        if ui
+            .add(egui::ImageButton::new(image).selected(is_active))
+            .clicked()
+            && !is_active
+        {
+            actions.push(BrowserAction::Select(preset.name.clone()));
+        }
+
+        if let Some(details) = self
+            .editing
+            .as_mut()
+            .filter(|details| details.preset == preset.name)
+        {
+            ui.text_edit_singleline(&mut details.name);
+            ui.add(
+                egui::TextEdit::singleline(&mut details.tags).hint_text("tags, comma separated"),
+            );
+            ui.horizontal(|ui| {
+                if ui.small_button("OK").clicked() {
+                    let details = self.editing.take().expect("editing this preset");
+                    let tags: Vec<String> = details
+                        .tags
+                        .split(',')
+                        .map(|tag| tag.trim().to_string())
+                        .filter(|tag| !tag.is_empty())
+                        .collect();
+                    if tags != preset.tags {
+                        actions.push(BrowserAction::Edit(LibraryEdit::SetTags {
+                            name: preset.name.clone(),
+                            tags,
+                        }));
+                    }
+                    if details.name.trim() != preset.name {
+                        actions.push(BrowserAction::Edit(LibraryEdit::Rename {
+                            from: preset.name.clone(),
+                            to: details.name.trim().to_string(),
+                        }));
+                    }
+                }
+                if ui.small_button("Cancel").clicked() {
+                    self.editing = None;
+                }
+            });
+            return;
+        }
+
+        ui.horizontal(|ui| {
+            let star = if preset.favorite { "★" } else { "☆" };
+            if ui.small_button(star).on_hover_text("Favorite").clicked() {
+                actions.push(BrowserAction::Edit(LibraryEdit::SetFavorite {
+                    name: preset.name.clone(),
+                    favorite: !preset.favorite,
+                }));
+            }
+            ui.label(egui::RichText::new(&preset.name).strong());
+        });
+        if !preset.tags.is_empty() {
+            ui.weak(preset.tags.join(", "));
+        }
+        ui.horizontal(|ui| {
+            if ui.small_button("Edit").clicked() {
+                self.editing = Some(Details {
+                    preset: preset.name.clone(),
+                    name: preset.name.clone(),
+                    tags: preset.tags.join(", "),
+                });
+            }
+            if ui.small_button("Duplicate").clicked() {
+                actions.push(BrowserAction::Edit(LibraryEdit::Duplicate {
+                    name: preset.name.clone(),
+                }));
+            }
+            // Keep at least one preset so there is always something to select.
+            let can_delete = preset_count > 1;
+            if ui
+                .add_enabled(can_delete, egui::Button::new("Delete").small())
+                .clicked()
+            {
+                actions.push(BrowserAction::Edit(LibraryEdit::Delete {
+                    name: preset.name.clone(),
+                }));
+            }
+        });
+    }
+
+    /// Thumbnail for `preset`, rendered again whenever the preset changed.
+    fn thumbnail(&mut self, ctx: &egui::Context, preset: &TrailPreset) -> egui::TextureHandle {
+        if let Some((rendered, texture)) = self.thumbnails.get(&preset.name) {
+            if rendered == preset {
+                return texture.clone();
+            }
+        }
+        let image = render_thumbnail(preset, THUMBNAIL_WIDTH, THUMBNAIL_HEIGHT);
+        let color_image = egui::ColorImage::from_rgba_unmultiplied(
+            [image.width as usize, image.height as usize],
+            &image.pixels,
+        );
+        let texture = ctx.load_texture(
+            format!("preset_thumbnail_{}", preset.name),
+            color_image,
+            egui::TextureOptions::LINEAR,
+        );
+        self.thumbnails
+            .insert(preset.name.clone(), (preset.clone(), texture.clone()));
+        texture
+    }
+}
@@ -0,0 +1,480 @@
+//! egui widgets for editing `ColorGradient`s and `FloatCurve`s.
+//!
+//! Click the gradient bar or double-click the curve plot to add a stop, drag to move it,
+//! right-click to remove it. Stops can't be dragged past their neighbours, so indices stay stable
+//! during a drag. The selected curve key shows a tangent handle; tangents are kept smooth (the
+//! same slope on both sides).
+
+use std::hash::Hash;
+use std::ops::RangeInclusive;
+
+use egui::{Color32, Pos2, Rect, Sense, Stroke, Vec2};
+use glam::Vec4;
+use serpentines_core::{ColorGradient, CurveKey, FloatCurve, GradientStop};
+
+const BAR_HEIGHT: f32 = 24.0;
+const MARKER_SIZE: f32 = 10.0;
+const PLOT_HEIGHT: f32 = 120.0;
+const KEY_RADIUS: f32 = 5.0;
+/// Length of a tangent handle on screen, in points.
+const HANDLE_LENGTH: f32 = 36.0;
+const CURVE_SAMPLES: usize = 96;
+
+/// Grid steps that dragged stops and keys snap to.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Snap {
+    pub t: f32,
+    pub value: f32,
+}
+
+impl Default for Snap {
+    fn default() -> Self {
+        Self {
+            t: 0.05,
+            value: 0.1,
+        }
+    }
+}
+
+fn snap_to(value: f32, step: Option<f32>) -> f32 {
+    match step {
+        Some(step) if step > 0.0 => (value / step).round() * step,
+        _ => value,
+    }
+}
+
+fn to_color32(color: Vec4) -> Color32 {
+    let [r, g, b, a] = color.to_array().map(|channel| channel.clamp(0.0, 1.0));
+    egui::Rgba::from_rgba_unmultiplied(r, g, b, a).into()
+}
+
+/// Editor for a multi-stop color gradient.
+pub struct GradientEdit<'a> {
+    gradient: &'a mut ColorGradient,
+    snap: Option<Snap>,
+    id_salt: egui::Id,
+}
+
+impl<'a> GradientEdit<'a> {
+    pub fn new(gradient: &'a mut ColorGradient) -> Self {
+        Self {
+            gradient,
+            snap: None,
+            id_salt: egui::Id::new("gradient_edit"),
+        }
+    }
+
+    pub fn snap(mut self, snap: Option<Snap>) -> Self {
+        self.snap = snap;
+        self
+    }
+
+    pub fn id_salt(mut self, id_salt: impl Hash) -> Self {
+        self.id_salt = egui::Id::new(id_salt);
+        self
+    }
+}
+
+impl egui::Widget for GradientEdit<'_> {
+    fn ui(self, ui: &mut egui::Ui) -> egui::Response {
+        let GradientEdit {
+            gradient,
+            snap,
+            id_salt,
+        } = self;
+        let id = ui.make_persistent_id(id_salt);
+        let mut selected: Option<usize> = ui.data(|data| data.get_temp(id));
+        let mut changed = false;
+        let snap_t = snap.map(|snap| snap.t);
+
+        let width = ui.available_width();
+        let (bar, mut response) =
+            ui.allocate_exact_size(egui::vec2(width, BAR_HEIGHT), Sense::click());
+        let t_at = |x: f32| ((x - bar.left()) / bar.width()).clamp(0.0, 1.0);
+        let x_at = |t: f32| bar.left() + t * bar.width();
+
+        let painter = ui.painter();
+        let mut mesh = egui::Mesh::default();
+        let mut ts: Vec<f32> = vec![0.0];
+        ts.extend(gradient.stops().iter().map(|stop| stop.t));
+        ts.push(1.0);
+        for pair in ts.windows(2) {
+            let (left, right) = (pair[0], pair[1]);
+            let index = mesh.vertices.len() as u32;
+            for t in [left, right] {
+                let color = to_color32(gradient.sample(t));
+                mesh.colored_vertex(Pos2::new(x_at(t), bar.top()), color);
+                mesh.colored_vertex(Pos2::new(x_at(t), bar.bottom()), color);
+            }
+            mesh.add_triangle(index, index + 1, index + 2);
+            mesh.add_triangle(index + 1, index + 2, index + 3);
+        }
+        painter.add(mesh);
+        painter.rect_stroke(
+            bar,
+            2.0,
+            ui.visuals().widgets.noninteractive.bg_stroke,
+            egui::StrokeKind::Inside,
+        );
+
+        if response.clicked() {
+            if let Some(pointer) = response.interact_pointer_pos() {
+                let t = snap_to(t_at(pointer.x), snap_t);
+                let color = gradient.sample(t);
+                selected = Some(gradient.insert(GradientStop { t, color }));
+                changed = true;
+            }
+        }
+
+        let (markers, _) =
+            ui.allocate_exact_size(egui::vec2(width, MARKER_SIZE + 2.0), Sense::hover());
+        let mut remove = None;
+        for index in 0..gradient.stops().len() {
+            let stop = gradient.stops()[index];
+            let center = Pos2::new(x_at(stop.t), markers.top() + MARKER_SIZE * 0.5 + 1.0);
+            let rect = Rect::from_center_size(center, Vec2::splat(MARKER_SIZE));
+            let marker = ui.interact(rect, id.with(index), Sense::click_and_drag());
+            if marker.clicked() || marker.drag_started() {
+                selected = Some(index);
+            }
+            if marker.dragged() {
+                if let Some(pointer) = marker.interact_pointer_pos() {
+                    let stops = gradient.stops();
+                    let low = if index > 0 { stops[index - 1].t } else { 0.0 };
+                    let high = stops.get(index + 1).map_or(1.0, |next| next.t);
+                    let t = snap_to(t_at(pointer.x), snap_t).clamp(low, high);
+                    if t != stop.t {
+                        gradient.move_stop(index, t);
+                        changed = true;
+                    }
+                }
+            }
+            if marker.secondary_clicked() {
+                remove = Some(index);
+            }
+            let is_selected = selected == Some(index);
+            let stroke = if is_selected {
+                ui.visuals().selection.stroke
+            } else {
+                ui.visuals().widgets.inactive.fg_stroke
+            };
+            let painter = ui.painter();
+            painter.rect_filled(rect, 2.0, to_color32(stop.color));
+            painter.rect_stroke(rect, 2.0, stroke, egui::StrokeKind::Outside);
+            marker.on_hover_text("Drag to move, right-click to remove");
+        }
+        if let Some(index) = remove {
+            if gradient.remove(index).is_some() {
+                selected = None;
+                changed = true;
+            }
+        }
+
+        let selected_stop = selected.filter(|&index| index < gradient.stops().len());
+        if let Some(index) = selected_stop {
+            let stop = gradient.stops()[index];
+            ui.horizontal(|ui| {
+                let mut rgba = stop.color.to_array();
+                if ui.color_edit_button_rgba_unmultiplied(&mut rgba).changed() {
+                    gradient.set_color(index, Vec4::from_array(rgba));
+                    changed = true;
+                }
+                let mut t = stop.t;
+                let stops = gradient.stops();
+                let low = if index > 0 { stops[index - 1].t } else { 0.0 };
+                let high = stops.get(index + 1).map_or(1.0, |next| next.t);
+                if ui
+                    .add(
+                        egui::DragValue::new(&mut t)
+                            .range(low..=high)
+                            .speed(0.01)
+                            .prefix("at "),
+                    )
+                    .changed()
+                {
+                    gradient.move_stop(index, t);
+                    changed = true;
+                }
+                let can_remove = gradient.stops().len() > 1;
+                if ui
+                    .add_enabled(can_remove, egui::Button::new("Remove").small())
+                    .clicked()
+                {
+                    gradient.remove(index);
+                    selected = None;
+                    changed = true;
+                }
+            });
+        } else {
+            ui.weak("Click the bar to add a stop");
+        }
+
+        ui.data_mut(|data| data.insert_temp(id, selected));
+        if changed {
+            response.mark_changed();
+        }
+        response
+    }
+}
+
+/// Editor for a keyframed float curve over 0..=1.
+pub struct CurveEdit<'a> {
+    curve: &'a mut FloatCurve,
+    range: RangeInclusive<f32>,
+    snap: Option<Snap>,
+    id_salt: egui::Id,
+}
+
+impl<'a> CurveEdit<'a> {
+    /// `range` is the value range shown and accepted.
+    pub fn new(curve: &'a mut FloatCurve, range: RangeInclusive<f32>) -> Self {
+        Self {
+            curve,
+            range,
+            snap: None,
+            id_salt: egui::Id::new("curve_edit"),
+        }
+    }
+
+    pub fn snap(mut self, snap: Option<Snap>) -> Self {
+        self.snap = snap;
+        self
+    }
+
+    pub fn id_salt(mut self, id_salt: impl Hash) -> Self {
+        self.id_salt = egui::Id::new(id_salt);
+        self
+    }
+}
+
+/// Maps curve space (t, value) to a screen rect and back.
+struct PlotTransform {
+    rect: Rect,
+    min: f32,
+    max: f32,
+}
+
+impl PlotTransform {
+    fn to_screen(&self, t: f32, value: f32) -> Pos2 {
+        let y = (value - self.min) / (self.max - self.min);
+        Pos2::new(
+            self.rect.left() + t * self.rect.width(),
+            self.rect.bottom() - y * self.rect.height(),
+        )
+    }
+
+    fn to_curve(&self, pos: Pos2) -> (f32, f32) {
+        let t = ((pos.x - self.rect.left()) / self.rect.width()).clamp(0.0, 1.0);
+        let y = ((self.rect.bottom() - pos.y) / self.rect.height()).clamp(0.0, 1.0);
+        (t, self.min + y * (self.max - self.min))
+    }
+
+    /// Screen-space unit vector along a slope of `tangent`.
+    fn tangent_direction(&self, tangent: f32) -> Vec2 {
+        let scale_y = self.rect.height() / (self.max - self.min);
+        Vec2::new(self.rect.width(), -tangent * scale_y).normalized()
+    }
+
+    /// Slope pointing from `key` towards `pos`, in curve units.
+    fn tangent_towards(&self, key: Pos2, pos: Pos2) -> f32 {
+        let delta = pos - key;
+        let dt = delta.x / self.rect.width();
+        let dv = -delta.y / self.rect.height() * (self.max - self.min);
+        if dt.abs() < 1e-4 {
+            dv.signum() * 1e4
+        } else {
+            dv / dt
+        }
+    }
+}
+
+impl egui::Widget for CurveEdit<'_> {
+    fn ui(self, ui: &mut egui::Ui) -> egui::Response {
+        let CurveEdit {
+            curve,
+            range,
+            snap,
+            id_salt,
+        } = self;
+        let id = ui.make_persistent_id(id_salt);
+        let mut selected: Option<usize> = ui.data(|data| data.get_temp(id));
+        let mut changed = false;
+        let (min, max) = (*range.start(), *range.end());
+        let snap_t = snap.map(|snap| snap.t);
+        let snap_value = snap.map(|snap| snap.value);
+
+        let size = egui::vec2(ui.available_width(), PLOT_HEIGHT);
+        let (rect, mut response) = ui.allocate_exact_size(size, Sense::click());
+        let plot = PlotTransform { rect, min, max };
+
+        let painter = ui.painter_at(rect.expand(KEY_RADIUS + 1.0));
+        painter.rect_filled(rect, 2.0, ui.visuals().extreme_bg_color);
+        let grid = Stroke::new(1.0, ui.visuals().widgets.noninteractive.bg_stroke.color);
+        for step in 1..4 {
+            let fraction = step as f32 / 4.0;
+            let x = rect.left() + fraction * rect.width();
+            let y = rect.top() + fraction * rect.height();
+            painter.line_segment(
+                [Pos2::new(x, rect.top()), Pos2::new(x, rect.bottom())],
+                grid,
+            );
+            painter.line_segment(
+                [Pos2::new(rect.left(), y), Pos2::new(rect.right(), y)],
+                grid,
+            );
+        }
+        let points: Vec<Pos2> = (0..=CURVE_SAMPLES)
+            .map(|i| {
+                let t = i as f32 / CURVE_SAMPLES as f32;
+                plot.to_screen(t, curve.sample(t).clamp(min, max))
+            })
+            .collect();
+        painter.add(egui::Shape::line(
+            points,
+            ui.visuals().widgets.active.fg_stroke,
+        ));
+
+        if response.double_clicked() {
+            if let Some(pointer) = response.interact_pointer_pos() {
+                let (t, value) = plot.to_curve(pointer);
+                let (t, value) = (
+                    snap_to(t, snap_t),
+                    snap_to(value, snap_value).clamp(min, max),
+                );
+                let slope =
+                    (curve.sample((t + 0.01).min(1.0)) - curve.sample((t - 0.01).max(0.0))) / 0.02;
+                selected = Some(curve.insert(CurveKey {
+                    t,
+                    value,
+                    in_tangent: slope,
+                    out_tangent: slope,
+                }));
+                changed = true;
+            }
+        }
+
+        // Tangent handle of the selected key, handled first so it wins over overlapping keys.
+        if let Some(index) = selected.filter(|&index| index < curve.keys().len()) {
+            let key = curve.keys()[index];
+            let center = plot.to_screen(key.t, key.value);
+            let direction = plot.tangent_direction(key.out_tangent);
+            let handles = [
+                center + direction * HANDLE_LENGTH,
+                center - direction * HANDLE_LENGTH,
+            ];
+            let stroke = ui.visuals().selection.stroke;
+            for (side, handle) in handles.into_iter().enumerate() {
+                painter.line_segment([center, handle], stroke);
+                painter.circle_stroke(handle, KEY_RADIUS * 0.7, stroke);
+                let handle_rect = Rect::from_center_size(handle, Vec2::splat(KEY_RADIUS * 2.0));
+                let drag = ui.interact(handle_rect, id.with(("tangent", side)), Sense::drag());
+                if drag.dragged() {
+                    if let Some(pointer) = drag.interact_pointer_pos() {
+                        let tangent = plot.tangent_towards(center, pointer);
+                        curve.set_tangents(index, tangent, tangent);
+                        changed = true;
+                    }
+                }
+            }
+        }
+
+        let mut remove = None;
+        for index in 0..curve.keys().len() {
+            let key = curve.keys()[index];
+            let center = plot.to_screen(key.t, key.value.clamp(min, max));
+            let key_rect = Rect::from_center_size(center, Vec2::splat(KEY_RADIUS * 2.5));
+            let handle = ui.interact(key_rect, id.with(index), Sense::click_and_drag());
+            if handle.clicked() || handle.drag_started() {
+                selected = Some(index);
+            }
+            if handle.dragged() {
+                if let Some(pointer) = handle.interact_pointer_pos() {
+                    let keys = curve.keys();
+                    let low = if index > 0 { keys[index - 1].t } else { 0.0 };
+                    let high = keys.get(index + 1).map_or(1.0, |next| next.t);
+                    let (t, value) = plot.to_curve(pointer);
+                    let t = snap_to(t, snap_t).clamp(low, high);
+                    let value = snap_to(value, snap_value).clamp(min, max);
+                    if (t, value) != (key.t, key.value) {
+                        curve.move_key(index, t, value);
+                        changed = true;
+                    }
+                }
+            }
+            if handle.secondary_clicked() {
+                remove = Some(index);
+            }
+            let fill = if selected == Some(index) {
+                ui.visuals().selection.bg_fill
+            } else {
+                ui.visuals().widgets.inactive.fg_stroke.color
+            };
+            painter.circle_filled(center, KEY_RADIUS, fill);
+            handle.on_hover_text("Drag to move, right-click to remove");
+        }
+        if let Some(index) = remove {
+            if curve.remove(index).is_some() {
+                selected = None;
+                changed = true;
+            }
+        }
+
+        if let Some(index) = selected.filter(|&index| index < curve.keys().len()) {
+            let key = curve.keys()[index];
+            ui.horizontal(|ui| {
+                let keys = curve.keys();
+                let low = if index > 0 { keys[index - 1].t } else { 0.0 };
+                let high = keys.get(index + 1).map_or(1.0, |next| next.t);
+                let (mut t, mut value, mut tangent) = (key.t, key.value, key.out_tangent);
+                let t_changed = ui
+                    .add(
+                        egui::DragValue::new(&mut t)
+                            .range(low..=high)
+                            .speed(0.01)
+                            .prefix("at "),
+                    )
+                    .changed();
+                let value_changed = ui
+                    .add(
+                        egui::DragValue::new(&mut value)
+                            .range(min..=max)
+                            .speed(0.01)
+                            .prefix("value "),
+                    )
+                    .changed();
+                if t_changed || value_changed {
+                    curve.move_key(index, t, value);
+                    changed = true;
+                }
+                if ui
+                    .add(
+                        egui::DragValue::new(&mut tangent)
+                            .speed(0.05)
+                            .prefix("slope "),
+                    )
+                    .changed()
+                {
+                    curve.set_tangents(index, tangent, tangent);
+                    changed = true;
+                }
+                let can_remove = curve.keys().len() > 1;
+                if ui
+                    .add_enabled(can_remove, egui::Button::new("Remove").small())
+                    .clicked()
+                {
+                    curve.remove(index);
+                    selected = None;
+                    changed = true;
+                }
+            });
+        } else {
+            ui.weak("Double-click the plot to add a key");
+        }
+
+        ui.data_mut(|data| data.insert_temp(id, selected));
+        if changed {
+            response.mark_changed();
+        }
+        response
+    }
+}
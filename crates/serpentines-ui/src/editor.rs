@@ -0,0 +1,412 @@
+//! Preset editor with undo/redo and revert-to-saved.
+
+use egui::{Key, KeyboardShortcut, Modifiers};
+use glam::Vec4;
+use serpentines_core::presets::{
+    DECAY_SECONDS_RANGE, EMISSION_RATE_RANGE, MAX_PARTICLES_RANGE, MAX_SIZE_SCALE,
+    PARTICLE_SIZE_RANGE,
+};
+use serpentines_core::{ColorGradient, FloatCurve, PresetProblem, TrailPreset};
+
+use crate::{CurveEdit, GradientEdit, Snap};
+
+/// Undo steps kept per editing session.
+const HISTORY_LIMIT: usize = 100;
+
+const UNDO: KeyboardShortcut = KeyboardShortcut::new(Modifiers::COMMAND, Key::Z);
+const REDO: KeyboardShortcut =
+    KeyboardShortcut::new(Modifiers::COMMAND.plus(Modifiers::SHIFT), Key::Z);
+
+/// What the user did in the editor this frame.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum EditorAction {
+    /// The preset changed: an edit, undo, redo or revert.
+    Edited,
+    /// Save was clicked on a valid preset.
+    Save,
+}
+
+/// Working copy of one preset plus its edit history.
+///
+/// Continuous edits (a slider drag, typing into the name) are grouped into one undo step, which
+/// closes when the pointer is released and no text field has focus.
+#[derive(Debug, Clone)]
+pub struct PresetEditor {
+    saved: TrailPreset,
+    current: TrailPreset,
+    undo: Vec<TrailPreset>,
+    redo: Vec<TrailPreset>,
+    /// An undo step is open and further edits join it.
+    editing: bool,
+    /// Snap gradient stops and curve keys to a grid.
+    snap: bool,
+}
+
+impl PresetEditor {
+    pub fn new(saved: TrailPreset) -> Self {
+        Self {
+            current: saved.clone(),
+            saved,
+            undo: Vec::new(),
+            redo: Vec::new(),
+            editing: false,
+            snap: true,
+        }
+    }
+
+    pub fn preset(&self) -> &TrailPreset {
+        &self.current
+    }
+
+    pub fn snap(&self) -> bool {
+        self.snap
+    }
+
+    pub fn set_snap(&mut self, snap: bool) {
+        self.snap = snap;
+    }
+
+    pub fn saved(&self) -> &TrailPreset {
+        &self.saved
+    }
+
+    /// The working copy differs from the saved preset.
+    pub fn is_dirty(&self) -> bool {
+        self.current != self.saved
+    }
+
+    pub fn problems(&self) -> Vec<PresetProblem> {
+        self.current.validate().err().unwrap_or_default()
+    }
+
+    pub fn is_valid(&self) -> bool {
+        self.current.validate().is_ok()
+    }
+
+    /// Replace the working copy. Returns false if nothing changed.
+    pub fn edit(&mut self, preset: TrailPreset) -> bool {
+        if preset == self.current {
+            return false;
+        }
+        if !self.editing {
+            self.push_undo();
+            self.editing = true;
+        }
+        self.redo.clear();
+        self.current = preset;
+        true
+    }
+
+    /// Close the open undo step so the next edit starts a new one.
+    pub fn finish_edit(&mut self) {
+        self.editing = false;
+    }
+
+    pub fn can_undo(&self) -> bool {
+        !self.undo.is_empty()
+    }
+
+    pub fn can_redo(&self) -> bool {
+        !self.redo.is_empty()
+    }
+
+    pub fn undo(&mut self) -> bool {
+        self.finish_edit();
+        let Some(previous) = self.undo.pop() else {
+            return false;
+        };
+        self.redo
+            .push(std::mem::replace(&mut self.current, previous));
+        true
+    }
+
+    pub fn redo(&mut self) -> bool {
+        self.finish_edit();
+        let Some(next) = self.redo.pop() else {
+            return false;
+        };
+        self.undo.push(std::mem::replace(&mut self.current, next));
+        true
+    }
+
+    /// Go back to the saved preset as one undoable step.
+    pub fn revert(&mut self) -> bool {
+        self.finish_edit();
+        let saved = self.saved.clone();
+        let changed = self.edit(saved);
+        self.finish_edit();
+        changed
+    }
+
+    /// The working copy has been saved; it becomes the revert target.
+    pub fn mark_saved(&mut self) {
+        self.saved = self.current.clone();
+    }
+
+    fn push_undo(&mut self) {
+        if self.undo.len() == HISTORY_LIMIT {
+            self.undo.remove(0);
+        }
+        self.undo.push(self.current.clone());
+    }
+
+    /// Lay out the editor.
+    pub fn show(&mut self, ui: &mut egui::Ui) -> Option<EditorAction> {
+        let mut action = None;
+        let mut preset = self.current.clone();
+        let mut text_focused = false;
+
+        egui::Grid::new("preset_editor")
+            .num_columns(2)
+            .spacing([12.0, 6.0])
+            .show(ui, |ui| {
+                ui.label("Name");
+                text_focused = ui.text_edit_singleline(&mut preset.name).has_focus();
+                ui.end_row();
+
+                ui.label("Max particles");
+                ui.add(
+                    egui::Slider::new(&mut preset.max_particles, MAX_PARTICLES_RANGE)
+                        .logarithmic(true),
+                );
+                ui.end_row();
+
+                ui.label("Emission rate");
+                ui.add(
+                    egui::Slider::new(&mut preset.emission_rate, EMISSION_RATE_RANGE).suffix(" /s"),
+                );
+                ui.end_row();
+
+                ui.label("Decay");
+                ui.add(
+                    egui::Slider::new(&mut preset.decay_seconds, DECAY_SECONDS_RANGE).suffix(" s"),
+                );
+                ui.end_row();
+
+                ui.label("Particle size");
+                ui.add(
+                    egui::Slider::new(&mut preset.particle_size, PARTICLE_SIZE_RANGE).suffix(" px"),
+                );
+                ui.end_row();
+
+                ui.label("Start color");
+                color_edit(ui, &mut preset.color_start);
+                ui.end_row();
+
+                ui.label("End color");
+                color_edit(ui, &mut preset.color_end);
+                ui.end_row();
+            });
+
+        ui.checkbox(&mut self.snap, "Snap to grid");
+        let snap = self.snap.then(Snap::default);
+        let mut use_gradient = preset.color_over_life.is_some();
+        if ui.checkbox(&mut use_gradient, "Color over life").changed() {
+            preset.color_over_life =
+                use_gradient.then(|| ColorGradient::two_stop(preset.color_start, preset.color_end));
+        }
+        if let Some(gradient) = preset.color_over_life.as_mut() {
+            ui.add(
+                GradientEdit::new(gradient)
+                    .snap(snap)
+                    .id_salt("color_over_life"),
+            );
+        }
+        let mut use_curve = preset.size_over_life.is_some();
+        if ui.checkbox(&mut use_curve, "Size over life").changed() {
+            preset.size_over_life = use_curve.then(|| FloatCurve::linear(1.0, 1.0));
+        }
+        if let Some(curve) = preset.size_over_life.as_mut() {
+            ui.add(
+                CurveEdit::new(curve, 0.0..=MAX_SIZE_SCALE)
+                    .snap(snap)
+                    .id_salt("size_over_life"),
+            );
+        }
+
+        if self.edit(preset) {
+            action = Some(EditorAction::Edited);
+        }
+        if !text_focused && !ui.ctx().is_using_pointer() {
+            self.finish_edit();
+        }
+
+        for problem in self.problems() {
+            ui.colored_label(ui.visuals().error_fg_color, problem.to_string());
+        }
+
+        let (undo_pressed, redo_pressed) = if text_focused {
+            (false, false)
+        } else {
+            // Redo first: matching ignores extra Shift, so UNDO would also swallow Cmd+Shift+Z.
+            ui.input_mut(|input| {
+                let redo = input.consume_shortcut(&REDO);
+                (input.consume_shortcut(&UNDO), redo)
+            })
+        };
+        ui.horizontal(|ui| {
+            let undo_clicked = ui
+                .add_enabled(self.can_undo(), egui::Button::new("Undo"))
+                .clicked();
+            if (undo_clicked || undo_pressed) && self.undo() {
+                action = Some(EditorAction::Edited);
+            }
+            let redo_clicked = ui
+                .add_enabled(self.can_redo(), egui::Button::new("Redo"))
+                .clicked();
+            if (redo_clicked || redo_pressed) && self.redo() {
+                action = Some(EditorAction::Edited);
+            }
+            if ui
+                .add_enabled(self.is_dirty(), egui::Button::new("Revert to saved"))
+                .clicked()
+                && self.revert()
+            {
+                action = Some(EditorAction::Edited);
+            }
+            let can_save = self.is_dirty() && self.is_valid();
+            if ui
+                .add_enabled(can_save, egui::Button::new("Save"))
+                .clicked()
+            {
+                action = Some(EditorAction::Save);
+            }
+        });
+        action
+    }
+}
+
+fn color_edit(ui: &mut egui::Ui, color: &mut Vec4) {
+    let mut rgba = color.to_array();
+    if ui.color_edit_button_rgba_unmultiplied(&mut rgba).changed() {
+        *color = Vec4::from_array(rgba);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn named(name: &str) -> TrailPreset {
+        TrailPreset {
+            name: name.into(),
+            ..TrailPreset::default()
+        }
+    }
+
+    fn name(editor: &PresetEditor) -> &str {
+        &editor.preset().name
+    }
+
+    #[test]
+    fn continuous_edits_form_one_undo_step() {
+        let mut editor = PresetEditor::new(named("A"));
+        assert!(!editor.edit(named("A")), "no-op edits are ignored");
+        assert!(editor.edit(named("AB")));
+        assert!(editor.edit(named("ABC")));
+        editor.finish_edit();
+        assert!(editor.edit(named("ABCD")));
+        editor.finish_edit();
+        assert!(editor.is_dirty());
+
+        assert!(editor.undo());
+        assert_eq!(name(&editor), "ABC");
+        assert!(editor.undo());
+        assert_eq!(name(&editor), "A");
+        assert!(!editor.undo());
+        assert!(!editor.is_dirty());
+    }
+
+    #[test]
+    fn redo_replays_until_a_new_edit() {
+        let mut editor = PresetEditor::new(named("A"));
+        for next in ["B", "C"] {
+            editor.edit(named(next));
+            editor.finish_edit();
+        }
+        editor.undo();
+        editor.undo();
+        assert!(editor.redo());
+        assert_eq!(name(&editor), "B");
+        assert!(editor.can_redo());
+
+        editor.edit(named("D"));
+        assert!(!editor.can_redo(), "a new edit drops the redo stack");
+        assert!(!editor.redo());
+        assert!(editor.undo());
+        assert_eq!(name(&editor), "B");
+    }
+
+    #[test]
+    fn history_keeps_the_latest_steps() {
+        let mut editor = PresetEditor::new(named("0"));
+        for step in 1..=HISTORY_LIMIT + 5 {
+            editor.edit(named(&step.to_string()));
+            editor.finish_edit();
+        }
+        let mut undone = 0;
+        while editor.undo() {
+            undone += 1;
+        }
+        assert_eq!(undone, HISTORY_LIMIT);
+        assert_eq!(name(&editor), "5");
+    }
+
+    #[test]
+    fn revert_is_undoable_and_mark_saved_moves_the_target() {
+        let mut editor = PresetEditor::new(named("A"));
+        editor.edit(named("B"));
+        assert!(editor.revert());
+        assert_eq!(name(&editor), "A");
+        assert!(!editor.revert(), "already at the saved preset");
+        assert!(editor.undo());
+        assert_eq!(name(&editor), "B");
+
+        editor.mark_saved();
+        assert!(!editor.is_dirty());
+        assert_eq!(editor.saved().name, "B");
+        editor.edit(named("C"));
+        assert!(editor.revert());
+        assert_eq!(name(&editor), "B");
+    }
+
+    /// Run one frame of the editor with a Z key press holding `modifiers`.
+    fn press_z(editor: &mut PresetEditor, modifiers: Modifiers) -> Option<EditorAction> {
+        let context = egui::Context::default();
+        let input = egui::RawInput {
+            events: vec![egui::Event::Key {
+                key: Key::Z,
+                physical_key: None,
+                pressed: true,
+                repeat: false,
+                modifiers,
+            }],
+            modifiers,
+            ..Default::default()
+        };
+        let mut action = None;
+        // The first frame may run more than one layout pass; keep whatever any pass reported.
+        let _ = context.run(input, |ctx| {
+            egui::CentralPanel::default().show(ctx, |ui| action = editor.show(ui).or(action));
+        });
+        action
+    }
+
+    #[test]
+    fn shortcuts_undo_and_redo() {
+        let mut editor = PresetEditor::new(named("A"));
+        editor.edit(named("B"));
+        editor.finish_edit();
+
+        // What a non-Mac backend reports for Ctrl.
+        let command = Modifiers::CTRL | Modifiers::COMMAND;
+        assert_eq!(press_z(&mut editor, command), Some(EditorAction::Edited));
+        assert_eq!(name(&editor), "A");
+        let shift_command = command | Modifiers::SHIFT;
+        assert_eq!(
+            press_z(&mut editor, shift_command),
+            Some(EditorAction::Edited)
+        );
+        assert_eq!(name(&editor), "B");
+    }
+}
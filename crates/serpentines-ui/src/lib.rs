@@ -2,23 +2,33 @@ use crossbeam_channel::{Receiver, Sender};
 use tracing::info;
 #[cfg(target_os = "windows")]
 use winit::platform::windows::EventLoopBuilderExtWindows;
+#[cfg(target_os = "windows")]
 use winit::raw_window_handle::{HasWindowHandle, RawWindowHandle};
-use image;
 
-pub enum UiCommand {
-    Show,
-}
+mod app;
+mod browser;
+mod curves;
+mod editor;
+mod logs;
+mod preview;
+mod protocol;
+pub use app::SerpentinesApp;
+pub use browser::{BrowserAction, PresetBrowser};
+pub use curves::{CurveEdit, GradientEdit, Snap};
+pub use editor::{EditorAction, PresetEditor};
+pub use logs::{LogBuffer, LogRecord, DEFAULT_LOG_CAPACITY};
+pub use preview::TrailPreview;
+pub use protocol::{UiCommand, UiEvent, UiSettings};
 
-pub enum UiEvent {
-    HelloClicked,
-}
+use app::WindowControl;
 
 pub struct UiHandles {
     pub command_sender: Sender<UiCommand>,
     pub event_receiver: Receiver<UiEvent>,
 }
 
-pub fn spawn_ui_thread() -> UiHandles {
+/// Start the control panel on its own thread. `logs` feeds its log tab.
+pub fn spawn_ui_thread(logs: LogBuffer) -> UiHandles {
     let (command_sender, command_receiver) = crossbeam_channel::unbounded::<UiCommand>();
     let (event_sender, event_receiver) = crossbeam_channel::unbounded::<UiEvent>();
 
@@ -36,12 +46,19 @@ pub fn spawn_ui_thread() -> UiHandles {
             {
                 builder.with_any_thread(true);
             }
+            #[cfg(target_os = "linux")]
+            {
+                use winit::platform::wayland::EventLoopBuilderExtWayland;
+                use winit::platform::x11::EventLoopBuilderExtX11;
+                EventLoopBuilderExtX11::with_any_thread(builder, true);
+                EventLoopBuilderExtWayland::with_any_thread(builder, true);
+            }
         }));
         eframe::run_native(
             "Serpentines",
             native_options,
             Box::new(move |creation_context| {
-                let (_pending_command_sender, pending_command_receiver) =
+                let (pending_command_sender, pending_command_receiver) =
                     crossbeam_channel::unbounded::<UiCommand>();
                 #[cfg(target_os = "windows")]
                 let hwnd_from_context_value: Option<isize> = {
@@ -57,72 +74,36 @@ pub fn spawn_ui_thread() -> UiHandles {
                         None
                     }
                 };
-                let egui_context = creation_context.egui_ctx.clone();
+                let window = WindowControl::new(creation_context.egui_ctx.clone());
+                #[cfg(target_os = "windows")]
+                let window = window.with_hwnd(hwnd_from_context_value);
+                let forwarder_window = window.clone();
                 std::thread::spawn(move || {
-                    // Block on incoming commands from platform threads and wake egui per message
+                    // Block on incoming commands from platform threads and wake egui per message.
+                    // Visibility is applied here since a hidden window doesn't run `update`.
                     while let Ok(incoming) = command_receiver.recv() {
-                        match incoming {
-                            UiCommand::Show => {
-                                info!("UI forwarder: Show -> OS Show + Visible(true) + Focus");
-                                #[cfg(target_os = "windows")]
-                                if let Some(hwnd_val) = hwnd_from_context_value {
-                                    use windows::Win32::Foundation::HWND;
-                                    use windows::Win32::UI::WindowsAndMessaging::{
-                                        SetForegroundWindow, ShowWindow, SW_SHOW,
-                                    };
-                                    let hwnd = HWND(hwnd_val as *mut core::ffi::c_void);
-                                    unsafe {
-                                        let _ = ShowWindow(hwnd, SW_SHOW);
-                                        let _ = SetForegroundWindow(hwnd);
-                                    }
-                                }
-                                egui_context.send_viewport_cmd(egui::ViewportCommand::Visible(true));
-                                egui_context.send_viewport_cmd(egui::ViewportCommand::Focus);
-                                egui_context.request_repaint();
-                            }
+                        if forwarder_window.apply(&incoming) {
+                            continue;
+                        }
+                        if pending_command_sender.send(incoming).is_err() {
+                            break;
                         }
+                        forwarder_window.context().request_repaint();
                     }
                 });
 
-                Ok(Box::new(SerpentinesApp {
-                    pending_command_receiver,
-                    event_sender,
-                }))
+                Ok(Box::new(
+                    SerpentinesApp::with_window(pending_command_receiver, event_sender, window)
+                        .with_logs(logs),
+                ))
             }),
         )
         .expect("eframe failed to start");
     });
+    info!("UI thread spawned");
 
     UiHandles {
         command_sender,
         event_receiver,
     }
 }
-
-pub struct SerpentinesApp {
-    pending_command_receiver: Receiver<UiCommand>,
-    event_sender: Sender<UiEvent>,
-}
-
-impl eframe::App for SerpentinesApp {
-    fn update(&mut self, context: &egui::Context, _frame: &mut eframe::Frame) {
-        // Intercept OS close requests via egui/eframe: hide (Visible(false)) and cancel the close.
-        let close_requested = context.input(|i| i.viewport().close_requested());
-        if close_requested {
-            info!("UI: CloseRequested -> hide and cancel");
-            context.send_viewport_cmd(egui::ViewportCommand::Visible(false));
-            context.send_viewport_cmd(egui::ViewportCommand::CancelClose);
-            context.request_repaint();
-        }
-        while let Ok(_command) = self.pending_command_receiver.try_recv() {
-            // Currently no UI-thread commands are processed inside update.
-        }
-
-        egui::CentralPanel::default().show(context, |ui| {
-            if ui.button("Hello, egui!").clicked() {
-                info!("UI: Hello button clicked");
-                let _ = self.event_sender.send(UiEvent::HelloClicked);
-            }
-        });
-    }
-}
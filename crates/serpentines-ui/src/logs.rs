@@ -0,0 +1,269 @@
+//! Recent log records shared between the tracing layer and the log tab.
+
+use std::collections::VecDeque;
+use std::fmt;
+use std::sync::{Arc, Mutex, MutexGuard};
+use std::time::{Duration, Instant};
+
+use tracing::Level;
+
+/// Records kept by `LogBuffer::default()`.
+pub const DEFAULT_LOG_CAPACITY: usize = 2000;
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct LogRecord {
+    /// Time since the buffer was created, roughly app uptime.
+    pub uptime: Duration,
+    pub level: Level,
+    pub target: String,
+    /// The event's message followed by its other fields as `key=value`.
+    pub message: String,
+}
+
+impl fmt::Display for LogRecord {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "[{:>9.3}s] {:>5} {}: {}",
+            self.uptime.as_secs_f64(),
+            self.level,
+            self.target,
+            self.message
+        )
+    }
+}
+
+struct Inner {
+    records: VecDeque<LogRecord>,
+    capacity: usize,
+    /// Records dropped because the buffer was full.
+    dropped: u64,
+}
+
+/// Bounded ring of recent log records. Cheap to clone; clones share the buffer.
+#[derive(Clone)]
+pub struct LogBuffer {
+    inner: Arc<Mutex<Inner>>,
+    started: Instant,
+}
+
+impl Default for LogBuffer {
+    fn default() -> Self {
+        Self::new(DEFAULT_LOG_CAPACITY)
+    }
+}
+
+impl LogBuffer {
+    pub fn new(capacity: usize) -> Self {
+        let capacity = capacity.max(1);
+        Self {
+            inner: Arc::new(Mutex::new(Inner {
+                records: VecDeque::with_capacity(capacity),
+                capacity,
+                dropped: 0,
+            })),
+            started: Instant::now(),
+        }
+    }
+
+    fn lock(&self) -> MutexGuard<'_, Inner> {
+        self.inner
+            .lock()
+            .unwrap_or_else(|poisoned| poisoned.into_inner())
+    }
+
+    /// Append a record stamped with the current uptime, evicting the oldest when full.
+    pub fn push(&self, level: Level, target: impl Into<String>, message: impl Into<String>) {
+        let record = LogRecord {
+            uptime: self.started.elapsed(),
+            level,
+            target: target.into(),
+            message: message.into(),
+        };
+        let mut inner = self.lock();
+        if inner.records.len() == inner.capacity {
+            inner.records.pop_front();
+            inner.dropped += 1;
+        }
+        inner.records.push_back(record);
+    }
+
+    pub fn len(&self) -> usize {
+        self.lock().records.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.lock().records.is_empty()
+    }
+
+    pub fn dropped(&self) -> u64 {
+        self.lock().dropped
+    }
+
+    pub fn clear(&self) {
+        let mut inner = self.lock();
+        inner.records.clear();
+        inner.dropped = 0;
+    }
+
+    /// Records at `max_level` or more severe whose target or message contains `search`
+    /// (case-insensitive), oldest first.
+    pub fn filtered(&self, max_level: Level, search: &str) -> Vec<LogRecord> {
+        let search = search.trim().to_lowercase();
+        self.lock()
+            .records
+            .iter()
+            .filter(|record| record.level <= max_level)
+            .filter(|record| {
+                search.is_empty()
+                    || record.message.to_lowercase().contains(&search)
+                    || record.target.to_lowercase().contains(&search)
+            })
+            .cloned()
+            .collect()
+    }
+}
+
+/// Minimum severity choices offered by the log tab, most severe first.
+pub(crate) const LEVELS: [Level; 5] = [
+    Level::ERROR,
+    Level::WARN,
+    Level::INFO,
+    Level::DEBUG,
+    Level::TRACE,
+];
+
+/// Log tab state: level filter and search text.
+pub(crate) struct LogView {
+    max_level: Level,
+    search: String,
+}
+
+impl Default for LogView {
+    fn default() -> Self {
+        Self {
+            max_level: Level::INFO,
+            search: String::new(),
+        }
+    }
+}
+
+impl LogView {
+    pub(crate) fn show(&mut self, ui: &mut egui::Ui, logs: &LogBuffer) {
+        let records = logs.filtered(self.max_level, &self.search);
+        ui.horizontal(|ui| {
+            egui::ComboBox::from_label("Level")
+                .selected_text(self.max_level.as_str())
+                .show_ui(ui, |ui| {
+                    for level in LEVELS {
+                        ui.selectable_value(&mut self.max_level, level, level.as_str());
+                    }
+                });
+            ui.label("Search");
+            ui.text_edit_singleline(&mut self.search);
+        });
+        ui.horizontal(|ui| {
+            if ui.button("Copy for bug report").clicked() {
+                ui.ctx().copy_text(bug_report(&records, logs.dropped()));
+            }
+            if ui.button("Clear").clicked() {
+                logs.clear();
+            }
+            ui.weak(format!("{} of {} records", records.len(), logs.len()));
+        });
+        ui.separator();
+        egui::ScrollArea::vertical()
+            .id_salt("log_records")
+            .stick_to_bottom(true)
+            .auto_shrink([false, false])
+            .show(ui, |ui| {
+                for record in &records {
+                    let color = match record.level {
+                        Level::ERROR => ui.visuals().error_fg_color,
+                        Level::WARN => ui.visuals().warn_fg_color,
+                        _ => ui.visuals().text_color(),
+                    };
+                    ui.colored_label(color, egui::RichText::new(record.to_string()).monospace());
+                }
+            });
+    }
+}
+
+/// Plain-text dump of `records` with version and platform details for pasting into an issue.
+fn bug_report(records: &[LogRecord], dropped: u64) -> String {
+    let mut report = format!(
+        "Serpentines {} on {} ({})\n",
+        env!("CARGO_PKG_VERSION"),
+        std::env::consts::OS,
+        std::env::consts::ARCH
+    );
+    if dropped > 0 {
+        report.push_str(&format!("({dropped} older records were dropped)\n"));
+    }
+    for record in records {
+        report.push_str(&record.to_string());
+        report.push('\n');
+    }
+    report
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn messages(records: &[LogRecord]) -> Vec<&str> {
+        records
+            .iter()
+            .map(|record| record.message.as_str())
+            .collect()
+    }
+
+    #[test]
+    fn full_buffer_evicts_the_oldest_records() {
+        let buffer = LogBuffer::new(3);
+        for message in ["one", "two", "three", "four", "five"] {
+            buffer.push(Level::INFO, "app", message);
+        }
+        assert_eq!(buffer.len(), 3);
+        assert_eq!(buffer.dropped(), 2);
+        assert_eq!(
+            messages(&buffer.filtered(Level::TRACE, "")),
+            ["three", "four", "five"]
+        );
+
+        buffer.clear();
+        assert!(buffer.is_empty());
+        assert_eq!(buffer.dropped(), 0);
+    }
+
+    #[test]
+    fn clones_share_records() {
+        let buffer = LogBuffer::new(0);
+        let clone = buffer.clone();
+        clone.push(Level::WARN, "app", "shared");
+        clone.push(Level::WARN, "app", "latest");
+        assert_eq!(messages(&buffer.filtered(Level::TRACE, "")), ["latest"]);
+    }
+
+    #[test]
+    fn filter_by_level_and_text() {
+        let buffer = LogBuffer::default();
+        buffer.push(Level::ERROR, "serpentines_x11::overlay", "overlay lost");
+        buffer.push(Level::WARN, "serpentines_app::runtime", "Slow frame");
+        buffer.push(Level::DEBUG, "serpentines_app::ipc", "client connected");
+
+        assert_eq!(
+            messages(&buffer.filtered(Level::WARN, "")),
+            ["overlay lost", "Slow frame"]
+        );
+        assert_eq!(
+            messages(&buffer.filtered(Level::TRACE, "  SLOW ")),
+            ["Slow frame"]
+        );
+        assert_eq!(
+            messages(&buffer.filtered(Level::TRACE, "x11")),
+            ["overlay lost"]
+        );
+        assert!(buffer.filtered(Level::INFO, "client").is_empty());
+    }
+}
@@ -0,0 +1,92 @@
+//! Trail preview drawn inside the control panel with its own engine.
+
+use glam::Vec2;
+use serpentines_core::thumbnail::{demo_path, DEMO_PERIOD};
+use serpentines_core::{EngineConfig, TrailEngine, TrailPreset};
+
+/// Longest step fed to the preview engine, so a stalled window doesn't teleport particles.
+const MAX_STEP_SECONDS: f32 = 0.1;
+
+/// Runs a private `TrailEngine` in panel coordinates (egui points) and paints it with egui.
+/// Follows the pointer while it hovers the preview, otherwise plays a demo path if enabled.
+/// Independent of the desktop overlay, so it works while trails are disabled there.
+pub struct TrailPreview {
+    engine: TrailEngine,
+    auto_demo: bool,
+    demo_time: f32,
+}
+
+impl TrailPreview {
+    pub fn new(preset: TrailPreset) -> Self {
+        Self {
+            engine: TrailEngine::new(EngineConfig {
+                preset,
+                ..EngineConfig::default()
+            }),
+            auto_demo: true,
+            demo_time: 0.0,
+        }
+    }
+
+    pub fn engine(&self) -> &TrailEngine {
+        &self.engine
+    }
+
+    pub fn preset(&self) -> &TrailPreset {
+        &self.engine.config.preset
+    }
+
+    /// Switch the preview to `preset`; live particles keep their age and pick up the new colors.
+    pub fn set_preset(&mut self, preset: &TrailPreset) {
+        if self.engine.config.preset != *preset {
+            self.engine.config.preset = preset.clone();
+        }
+    }
+
+    pub fn auto_demo(&self) -> bool {
+        self.auto_demo
+    }
+
+    pub fn set_auto_demo(&mut self, auto_demo: bool) {
+        self.auto_demo = auto_demo;
+    }
+
+    /// Advance the preview by `dt` seconds with the cursor at `cursor` (panel-local points), or
+    /// along the demo path inside `size` when there is no cursor.
+    pub fn step(&mut self, dt: f32, cursor: Option<Vec2>, size: Vec2) {
+        let dt = dt.clamp(0.0, MAX_STEP_SECONDS);
+        if let Some(cursor) = cursor {
+            self.engine.set_cursor(cursor);
+        } else if self.auto_demo {
+            self.demo_time = (self.demo_time + dt) % DEMO_PERIOD;
+            self.engine
+                .set_cursor(demo_path(self.demo_time / DEMO_PERIOD, size));
+        }
+        self.engine.update(dt);
+    }
+
+    /// Lay out the preview: a demo toggle and a `height`-point tall canvas filling the width.
+    pub fn show(&mut self, ui: &mut egui::Ui, height: f32) {
+        ui.checkbox(&mut self.auto_demo, "Auto demo");
+        let size = egui::vec2(ui.available_width(), height);
+        let (rect, response) = ui.allocate_exact_size(size, egui::Sense::hover());
+        let cursor = response
+            .hover_pos()
+            .map(|pos| Vec2::new(pos.x - rect.min.x, pos.y - rect.min.y));
+        let dt = ui.input(|input| input.stable_dt);
+        self.step(dt, cursor, Vec2::new(rect.width(), rect.height()));
+
+        let painter = ui.painter_at(rect);
+        painter.rect_filled(rect, 4.0, egui::Color32::from_gray(16));
+        for instance in self.engine.instances() {
+            let [r, g, b, a] = instance.color;
+            let color = egui::Rgba::from_rgba_unmultiplied(r, g, b, a);
+            let center = rect.min + egui::vec2(instance.x, instance.y);
+            painter.circle_filled(center, instance.size * 0.5, color);
+        }
+
+        if self.auto_demo || !self.engine.is_idle() {
+            ui.ctx().request_repaint();
+        }
+    }
+}
@@ -0,0 +1,195 @@
+//! Messages between the runtime and the control panel.
+//!
+//! Both directions are plain serde enums, adjacently tagged so each message is one JSON object:
+//! `{"type": "show"}`, `{"type": "preset_selected", "data": "Comet"}`,
+//! `{"type": "load_config", "data": {"config": {...}, "presets": [...]}}`. In-process they travel
+//! over crossbeam channels as values; the JSON form is for logging and out-of-process clients.
+
+use serde::{Deserialize, Serialize};
+use serpentines_core::{EngineConfig, EngineStats, LibraryEdit, TrailPreset};
+use serpentines_platform::MonitorRect;
+
+/// Control panel preferences, persisted alongside the engine config.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct UiSettings {
+    /// Keep the window hidden at launch; it opens from the tray or a hotkey.
+    pub start_hidden: bool,
+    pub preview_auto_demo: bool,
+    pub snap_to_grid: bool,
+}
+
+impl Default for UiSettings {
+    fn default() -> Self {
+        Self {
+            start_hidden: false,
+            preview_auto_demo: true,
+            snap_to_grid: true,
+        }
+    }
+}
+
+/// Runtime -> control panel.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(tag = "type", content = "data", rename_all = "snake_case")]
+pub enum UiCommand {
+    Show,
+    Hide,
+    /// Show if hidden, hide if shown.
+    Toggle,
+    /// Replace the panel's copy of the config and the presets it can pick from.
+    LoadConfig {
+        config: Box<EngineConfig>,
+        presets: Vec<TrailPreset>,
+    },
+    LoadUiSettings(UiSettings),
+    UpdateStats(EngineStats),
+    /// The monitor layout changed; carries the full new layout.
+    MonitorsChanged(Vec<MonitorRect>),
+}
+
+/// Control panel -> runtime.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(tag = "type", content = "data", rename_all = "snake_case")]
+pub enum UiEvent {
+    /// Preset picked by name from the library.
+    PresetSelected(String),
+    /// The user changed settings; carries the whole edited config.
+    ConfigEdited(EngineConfig),
+    /// The preset editor changed the active preset; applied live but not saved.
+    PresetEdited(TrailPreset),
+    /// Store the edited preset in the library under its name and make it active.
+    PresetSaved(TrailPreset),
+    /// Duplicate, rename, delete, tag, favorite or import presets in the library.
+    LibraryEdited(LibraryEdit),
+    EnableToggled(bool),
+    UiSettingsChanged(UiSettings),
+    QuitRequested,
+}
+
+impl UiCommand {
+    pub fn to_json(&self) -> serde_json::Result<String> {
+        serde_json::to_string(self)
+    }
+
+    pub fn from_json(json: &str) -> serde_json::Result<Self> {
+        serde_json::from_str(json)
+    }
+}
+
+impl UiEvent {
+    pub fn to_json(&self) -> serde_json::Result<String> {
+        serde_json::to_string(self)
+    }
+
+    pub fn from_json(json: &str) -> serde_json::Result<Self> {
+        serde_json::from_str(json)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use serpentines_core::{ColorGradient, FloatCurve, PresetLibrary, TrailEngine};
+    use serpentines_platform::MonitorId;
+
+    use super::*;
+
+    fn preset() -> TrailPreset {
+        let mut preset = PresetLibrary::builtin().get("Comet").cloned().unwrap();
+        preset.color_over_life = Some(ColorGradient::two_stop(
+            preset.color_start,
+            preset.color_end,
+        ));
+        preset.size_over_life = Some(FloatCurve::linear(1.0, 0.2));
+        preset
+    }
+
+    fn config() -> EngineConfig {
+        EngineConfig {
+            preset: preset(),
+            show_hud: true,
+            ..EngineConfig::default()
+        }
+    }
+
+    fn monitor() -> MonitorRect {
+        MonitorRect {
+            id: MonitorId::from_key("DP-1"),
+            x: -1920,
+            y: 0,
+            width: 1920,
+            height: 1080,
+            dpi: 144,
+            refresh_hz: Some(144.0),
+        }
+    }
+
+    /// Serializing, parsing and serializing again gives the same JSON.
+    fn assert_round_trip(json: String, reparse: impl Fn(&str) -> String) {
+        let again = reparse(&json);
+        let value: serde_json::Value = serde_json::from_str(&json).unwrap();
+        assert_eq!(
+            value,
+            serde_json::from_str::<serde_json::Value>(&again).unwrap(),
+            "{json}"
+        );
+    }
+
+    #[test]
+    fn commands_round_trip() {
+        let stats = EngineStats::from_engine(&TrailEngine::new(config()));
+        let commands = vec![
+            UiCommand::Show,
+            UiCommand::Hide,
+            UiCommand::Toggle,
+            UiCommand::LoadConfig {
+                config: Box::new(config()),
+                presets: PresetLibrary::builtin().presets().to_vec(),
+            },
+            UiCommand::LoadUiSettings(UiSettings {
+                start_hidden: true,
+                ..UiSettings::default()
+            }),
+            UiCommand::UpdateStats(stats),
+            UiCommand::MonitorsChanged(vec![monitor()]),
+        ];
+        for command in commands {
+            assert_round_trip(command.to_json().unwrap(), |json| {
+                UiCommand::from_json(json).unwrap().to_json().unwrap()
+            });
+        }
+        assert!(matches!(
+            UiCommand::from_json(r#"{"type":"toggle"}"#),
+            Ok(UiCommand::Toggle)
+        ));
+    }
+
+    #[test]
+    fn events_round_trip() {
+        let events = vec![
+            UiEvent::PresetSelected("Comet".into()),
+            UiEvent::ConfigEdited(config()),
+            UiEvent::PresetEdited(preset()),
+            UiEvent::PresetSaved(preset()),
+            UiEvent::LibraryEdited(LibraryEdit::Rename {
+                from: "Comet".into(),
+                to: "Meteor".into(),
+            }),
+            UiEvent::LibraryEdited(LibraryEdit::Import {
+                presets: vec![preset()],
+            }),
+            UiEvent::EnableToggled(false),
+            UiEvent::UiSettingsChanged(UiSettings::default()),
+            UiEvent::QuitRequested,
+        ];
+        for event in events {
+            assert_round_trip(event.to_json().unwrap(), |json| {
+                UiEvent::from_json(json).unwrap().to_json().unwrap()
+            });
+        }
+        assert_eq!(
+            UiEvent::PresetSelected("Comet".into()).to_json().unwrap(),
+            r#"{"type":"preset_selected","data":"Comet"}"#
+        );
+    }
+}
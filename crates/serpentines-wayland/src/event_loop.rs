@@ -0,0 +1,40 @@
+use serpentines_platform::{EventLoop, OverlayManager, Result, ShellEvent, Subscribers, Waker};
+
+use std::sync::mpsc::Receiver;
+use std::time::Duration;
+
+use crate::WaylandOverlayManager;
+
+/// Dispatches the overlay manager's event queue; wake-ups are `wl_display.sync` round trips.
+pub struct WaylandEventLoop {
+    overlays: WaylandOverlayManager,
+    shell_events: Subscribers<ShellEvent>,
+}
+
+impl WaylandEventLoop {
+    pub fn new(overlays: WaylandOverlayManager) -> Self {
+        Self {
+            overlays,
+            shell_events: Subscribers::new(),
+        }
+    }
+}
+
+impl EventLoop for WaylandEventLoop {
+    fn pump(&mut self, timeout: Option<Duration>) -> Result<()> {
+        self.overlays.dispatch(timeout)
+    }
+
+    fn waker(&self) -> Waker {
+        let wake_handle = self.overlays.wake_handle();
+        Waker::new(move || wake_handle.wake())
+    }
+
+    fn overlays(&mut self) -> &mut dyn OverlayManager {
+        &mut self.overlays
+    }
+
+    fn subscribe_shell(&mut self) -> Receiver<ShellEvent> {
+        self.shell_events.subscribe()
+    }
+}
@@ -0,0 +1,248 @@
+use serpentines_platform::{InputEvent, InputSource, Result, Subscribers};
+use tracing::{info, warn};
+
+use std::io::{Read, Write};
+use std::os::unix::net::UnixStream;
+use std::path::PathBuf;
+use std::sync::mpsc::{channel, Receiver, RecvTimeoutError, Sender};
+use std::sync::{Arc, Mutex};
+use std::thread::JoinHandle;
+use std::time::Duration;
+
+/// How often compositor IPC is polled for the cursor position while it moves.
+const POLL_INTERVAL: Duration = Duration::from_millis(8);
+/// Slowest poll rate, reached by doubling the interval while the cursor stays put. Hyprland's
+/// event socket doesn't report pointer motion, so polling is the only option.
+const IDLE_POLL_INTERVAL: Duration = Duration::from_millis(128);
+/// Delay before retrying after the IPC socket stopped answering.
+const RETRY_INTERVAL: Duration = Duration::from_secs(1);
+
+/// How global cursor position is obtained.
+///
+/// Wayland deliberately gives clients no global pointer position: `wl_pointer` only reports
+/// motion over the client's own surfaces, and the overlays are click-through. The only sources
+/// are compositor-specific side channels, kept here so the overlay side stays protocol-pure.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum InputStrategy {
+    /// Hyprland's IPC socket answers `cursorpos` in global layout coordinates.
+    HyprlandIpc { socket: PathBuf },
+    /// No supported side channel; the source starts but never reports motion.
+    Unavailable,
+}
+
+impl InputStrategy {
+    /// Pick a strategy from the running compositor's environment.
+    pub fn detect() -> Self {
+        if let (Some(runtime_dir), Some(signature)) = (
+            std::env::var_os("XDG_RUNTIME_DIR"),
+            std::env::var_os("HYPRLAND_INSTANCE_SIGNATURE"),
+        ) {
+            let socket = PathBuf::from(runtime_dir)
+                .join("hypr")
+                .join(signature)
+                .join(".socket.sock");
+            if socket.exists() {
+                return InputStrategy::HyprlandIpc { socket };
+            }
+        }
+        InputStrategy::Unavailable
+    }
+
+    pub fn supports_global_cursor(&self) -> bool {
+        !matches!(self, InputStrategy::Unavailable)
+    }
+}
+
+struct InputWorker {
+    /// Sending (or dropping) this wakes the poll thread and ends it.
+    stop: Sender<()>,
+    thread: JoinHandle<()>,
+}
+
+pub struct WaylandInputSource {
+    strategy: InputStrategy,
+    subscribers: Arc<Mutex<Subscribers<InputEvent>>>,
+    worker: Option<InputWorker>,
+}
+
+impl WaylandInputSource {
+    pub fn new(strategy: InputStrategy) -> Self {
+        Self {
+            strategy,
+            subscribers: Arc::new(Mutex::new(Subscribers::new())),
+            worker: None,
+        }
+    }
+
+    pub fn strategy(&self) -> &InputStrategy {
+        &self.strategy
+    }
+}
+
+impl Default for WaylandInputSource {
+    fn default() -> Self {
+        Self::new(InputStrategy::detect())
+    }
+}
+
+impl InputSource for WaylandInputSource {
+    fn start(&mut self) -> Result<()> {
+        if self.worker.is_some() {
+            return Ok(());
+        }
+        let socket = match &self.strategy {
+            InputStrategy::HyprlandIpc { socket } => socket.clone(),
+            InputStrategy::Unavailable => {
+                warn!(
+                    "global cursor input on Wayland is only supported on Hyprland \
+                     (HYPRLAND_INSTANCE_SIGNATURE is not set); trails will not follow the cursor"
+                );
+                return Ok(());
+            }
+        };
+        let (stop, stop_requested) = channel();
+        let thread = {
+            let subscribers = Arc::clone(&self.subscribers);
+            std::thread::spawn(move || poll_hyprland_cursor(&socket, &stop_requested, &subscribers))
+        };
+        self.worker = Some(InputWorker { stop, thread });
+        info!("Wayland input started ({:?})", self.strategy);
+        Ok(())
+    }
+
+    fn stop(&mut self) -> Result<()> {
+        let Some(worker) = self.worker.take() else {
+            return Ok(());
+        };
+        let _ = worker.stop.send(());
+        if worker.thread.join().is_err() {
+            warn!("Wayland input thread panicked");
+        }
+        info!("Wayland input stopped");
+        Ok(())
+    }
+
+    fn subscribe(&mut self) -> Receiver<InputEvent> {
+        match self.subscribers.lock() {
+            Ok(mut subscribers) => subscribers.subscribe(),
+            Err(poisoned) => poisoned.into_inner().subscribe(),
+        }
+    }
+}
+
+impl Drop for WaylandInputSource {
+    fn drop(&mut self) {
+        let _ = self.stop();
+    }
+}
+
+/// Poll `cursorpos` until stopped, backing off towards `IDLE_POLL_INTERVAL` while the cursor is
+/// stationary and snapping back to `POLL_INTERVAL` as soon as it moves. Waits on
+/// `stop_requested` between polls, so stopping never waits out a retry delay.
+fn poll_hyprland_cursor(
+    socket: &PathBuf,
+    stop_requested: &Receiver<()>,
+    subscribers: &Mutex<Subscribers<InputEvent>>,
+) {
+    let mut last = None;
+    let mut interval = POLL_INTERVAL;
+    let mut failing = false;
+    loop {
+        match query_hyprland_cursor(socket) {
+            Ok(position) if last != Some(position) => {
+                last = Some(position);
+                interval = POLL_INTERVAL;
+                failing = false;
+                if let Ok(mut subscribers) = subscribers.lock() {
+                    subscribers.publish(&InputEvent::CursorMoved {
+                        x: position.0,
+                        y: position.1,
+                    });
+                }
+            }
+            Ok(_) => {
+                interval = (interval * 2).clamp(POLL_INTERVAL, IDLE_POLL_INTERVAL);
+                failing = false;
+            }
+            Err(err) => {
+                // Log once per outage rather than on every retry.
+                if !failing {
+                    warn!("Hyprland cursor query failed: {err}");
+                    failing = true;
+                }
+                interval = RETRY_INTERVAL;
+            }
+        }
+        if stop_requested.recv_timeout(interval) != Err(RecvTimeoutError::Timeout) {
+            break;
+        }
+    }
+}
+
+/// One request per connection: Hyprland closes the socket after each reply, so it can't be
+/// kept open between polls.
+fn query_hyprland_cursor(socket: &PathBuf) -> Result<(f32, f32)> {
+    let mut stream = UnixStream::connect(socket)?;
+    stream.write_all(b"j/cursorpos")?;
+    let mut response = String::new();
+    stream.read_to_string(&mut response)?;
+    let value: serde_json::Value = serde_json::from_str(&response)?;
+    let coordinate = |key: &str| value.get(key).and_then(serde_json::Value::as_f64);
+    match (coordinate("x"), coordinate("y")) {
+        (Some(x), Some(y)) => Ok((x as f32, y as f32)),
+        _ => Err(format!("unexpected cursorpos reply: {response}").into()),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::os::unix::net::UnixListener;
+    use std::path::Path;
+    use std::time::Instant;
+
+    use super::*;
+
+    /// Answer every `cursorpos` request with the same position, like an idle Hyprland.
+    fn fake_hyprland(socket: &Path) {
+        let listener = UnixListener::bind(socket).unwrap();
+        std::thread::spawn(move || {
+            for mut stream in listener.incoming().map_while(|stream| stream.ok()) {
+                let mut request = [0; 64];
+                let _ = stream.read(&mut request);
+                let _ = stream.write_all(br#"{"x": 10.5, "y": -20.0}"#);
+            }
+        });
+    }
+
+    #[test]
+    fn reports_the_hyprland_cursor() {
+        let dir = tempfile::tempdir().unwrap();
+        let socket = dir.path().join(".socket.sock");
+        fake_hyprland(&socket);
+        let mut source = WaylandInputSource::new(InputStrategy::HyprlandIpc { socket });
+        let events = source.subscribe();
+        source.start().unwrap();
+
+        let event = events.recv_timeout(Duration::from_secs(5)).unwrap();
+        assert_eq!(event, InputEvent::CursorMoved { x: 10.5, y: -20.0 });
+        source.stop().unwrap();
+    }
+
+    #[test]
+    fn stop_does_not_wait_out_the_retry_delay() {
+        let dir = tempfile::tempdir().unwrap();
+        let socket = dir.path().join("missing.sock");
+        let mut source = WaylandInputSource::new(InputStrategy::HyprlandIpc { socket });
+        source.start().unwrap();
+        // Let the first query fail so the thread is waiting for RETRY_INTERVAL.
+        std::thread::sleep(Duration::from_millis(50));
+
+        let started = Instant::now();
+        source.stop().unwrap();
+        assert!(
+            started.elapsed() < RETRY_INTERVAL / 2,
+            "stop took {:?}",
+            started.elapsed()
+        );
+    }
+}
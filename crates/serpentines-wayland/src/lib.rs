@@ -0,0 +1,59 @@
+//! Linux Wayland platform implementations for Serpentines (wlr-layer-shell compositors).
+use serpentines_platform::{
+    Capabilities, NullRenderer, Platform, PlatformBackend, Result, TrayProvider,
+};
+use serpentines_sni::SniTray;
+use tracing::warn;
+
+mod event_loop;
+mod input;
+mod overlay;
+pub use crate::event_loop::WaylandEventLoop;
+pub use crate::input::{InputStrategy, WaylandInputSource};
+pub use crate::overlay::{WakeHandle, WaylandOverlayManager};
+
+use wayland_client::Connection;
+
+// Public app entry ----------------
+/// Backend descriptor for the app's backend registry. Preferred over X11 when the compositor
+/// supports wlr-layer-shell.
+pub fn backend() -> PlatformBackend {
+    PlatformBackend {
+        name: "wayland",
+        priority: 20,
+        detect: WaylandOverlayManager::is_supported,
+        capabilities: || Capabilities {
+            global_input: InputStrategy::detect().supports_global_cursor(),
+            click_through_overlays: true,
+            // Geometry is logical and the compositor scales the overlays itself.
+            per_monitor_dpi: false,
+            tray: true,
+            hotkeys: false,
+        },
+        create: create_platform,
+    }
+}
+
+/// Connect to the compositor and build the layer-shell overlays, tray and input source.
+pub fn create_platform() -> Result<Platform> {
+    let connection = Connection::connect_to_env()?;
+    let event_loop = WaylandEventLoop::new(WaylandOverlayManager::new(connection)?);
+    Ok(Platform {
+        event_loop: Box::new(event_loop),
+        input: Box::new(WaylandInputSource::default()),
+        renderer: Box::new(NullRenderer),
+        tray: create_tray(),
+        hotkeys: None,
+    })
+}
+
+/// SNI tray, or `None` when D-Bus or the tray host is unavailable.
+fn create_tray() -> Option<Box<dyn TrayProvider>> {
+    match SniTray::new() {
+        Ok(tray) => Some(Box::new(tray)),
+        Err(err) => {
+            warn!("tray unavailable: {err}");
+            None
+        }
+    }
+}
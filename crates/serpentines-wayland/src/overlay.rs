@@ -0,0 +1,570 @@
+use serpentines_platform::{
+    diff_monitors, MonitorEvent, MonitorId, MonitorRect, OverlayManager, Result, Subscribers,
+    BASE_DPI,
+};
+use tracing::{info, warn};
+
+use std::collections::{HashMap, HashSet};
+use std::os::fd::{AsRawFd, RawFd};
+use std::sync::mpsc::Receiver;
+use std::time::Duration;
+
+use wayland_client::backend::WaylandError;
+use wayland_client::globals::{registry_queue_init, GlobalListContents};
+use wayland_client::protocol::{
+    wl_callback, wl_compositor, wl_output, wl_region, wl_registry, wl_surface,
+};
+use wayland_client::{
+    delegate_noop, Connection, Dispatch, DispatchError, EventQueue, QueueHandle, WEnum,
+};
+use wayland_protocols::xdg::xdg_output::zv1::client::{zxdg_output_manager_v1, zxdg_output_v1};
+use wayland_protocols_wlr::layer_shell::v1::client::{zwlr_layer_shell_v1, zwlr_layer_surface_v1};
+
+const LAYER_NAMESPACE: &str = "serpentines-overlay";
+
+/// What the compositor told us about one `wl_output`.
+#[derive(Default)]
+struct OutputInfo {
+    global_name: u32,
+    output: Option<wl_output::WlOutput>,
+    xdg_output: Option<zxdg_output_v1::ZxdgOutputV1>,
+    make: String,
+    model: String,
+    connector: Option<String>,
+    position: (i32, i32),
+    mode_size: (i32, i32),
+    /// Current mode's refresh rate in mHz, 0 when the compositor doesn't say.
+    mode_refresh: i32,
+    logical_position: Option<(i32, i32)>,
+    logical_size: Option<(i32, i32)>,
+    scale: i32,
+    ready: bool,
+}
+
+impl OutputInfo {
+    /// Stable id: make and model come from EDID, the connector name pins which port it's on.
+    fn monitor_id(&self) -> MonitorId {
+        if self.make.is_empty() && self.model.is_empty() && self.connector.is_none() {
+            let (x, y) = self.logical_position.unwrap_or(self.position);
+            return MonitorId::from_geometry(x, y, self.mode_size.0, self.mode_size.1);
+        }
+        MonitorId::from_key(&format!(
+            "wayland:{}:{}:{}",
+            self.make,
+            self.model,
+            self.connector.as_deref().unwrap_or_default()
+        ))
+    }
+
+    /// Geometry in the compositor's global (logical) space. xdg-output is authoritative when
+    /// present; otherwise the mode size is divided by the integer scale.
+    ///
+    /// `dpi` is always `BASE_DPI`: overlays, cursor and layout all live in logical space and the
+    /// compositor scales the surfaces, so scaling particles by the output scale too would draw
+    /// them twice as large on a 2x output.
+    fn monitor_rect(&self) -> MonitorRect {
+        let scale = self.scale.max(1);
+        let (x, y) = self.logical_position.unwrap_or(self.position);
+        let (width, height) = self
+            .logical_size
+            .unwrap_or((self.mode_size.0 / scale, self.mode_size.1 / scale));
+        MonitorRect {
+            id: self.monitor_id(),
+            x,
+            y,
+            width,
+            height,
+            dpi: BASE_DPI as u32,
+            refresh_hz: (self.mode_refresh > 0).then(|| self.mode_refresh as f32 / 1000.0),
+        }
+    }
+
+    /// Physical pixels per logical pixel. With xdg-output this is the ratio of the mode to the
+    /// logical size, which also covers fractional scales that `wl_output.scale` rounds up.
+    fn effective_scale(&self) -> f32 {
+        let physical = self.mode_size.0.max(self.mode_size.1);
+        match self.logical_size {
+            Some((width, height)) if physical > 0 && width.max(height) > 0 => {
+                physical as f32 / width.max(height) as f32
+            }
+            _ => self.scale.max(1) as f32,
+        }
+    }
+}
+
+/// Protocol state fed by the event queue.
+#[derive(Default)]
+struct WaylandState {
+    compositor: Option<wl_compositor::WlCompositor>,
+    layer_shell: Option<zwlr_layer_shell_v1::ZwlrLayerShellV1>,
+    xdg_output_manager: Option<zxdg_output_manager_v1::ZxdgOutputManagerV1>,
+    outputs: Vec<OutputInfo>,
+    closed_overlays: Vec<MonitorId>,
+    topology_dirty: bool,
+}
+
+impl WaylandState {
+    fn output_mut(&mut self, global_name: u32) -> Option<&mut OutputInfo> {
+        self.outputs
+            .iter_mut()
+            .find(|info| info.global_name == global_name)
+    }
+
+    fn bind_xdg_output(&mut self, global_name: u32, qh: &QueueHandle<Self>) {
+        let Some(manager) = self.xdg_output_manager.clone() else {
+            return;
+        };
+        if let Some(info) = self.output_mut(global_name) {
+            if let (Some(output), None) = (info.output.as_ref(), info.xdg_output.as_ref()) {
+                info.xdg_output = Some(manager.get_xdg_output(output, qh, global_name));
+            }
+        }
+    }
+}
+
+/// Makes a waiting `WaylandOverlayManager::dispatch` return. Usable from any thread.
+#[derive(Clone)]
+pub struct WakeHandle {
+    connection: Connection,
+    queue_handle: QueueHandle<WaylandState>,
+}
+
+impl WakeHandle {
+    pub fn wake(&self) {
+        // The sync callback lands on the manager's queue, which unblocks its dispatch.
+        self.connection.display().sync(&self.queue_handle, ());
+        if let Err(err) = self.connection.flush() {
+            warn!("failed to flush wake request: {err}");
+        }
+    }
+}
+
+struct LayerOverlay {
+    surface: wl_surface::WlSurface,
+    layer_surface: zwlr_layer_surface_v1::ZwlrLayerSurfaceV1,
+}
+
+/// One `wlr-layer-shell` surface per output on the overlay layer, with an empty input region so
+/// pointer and touch input reach the windows below.
+///
+/// Monitor geometry is reported in the compositor's logical space (Wayland has no global physical
+/// pixel space) at `BASE_DPI`; the compositor applies each output's scale, fractional or not.
+pub struct WaylandOverlayManager {
+    connection: Connection,
+    queue: EventQueue<WaylandState>,
+    state: WaylandState,
+    overlays: HashMap<MonitorId, LayerOverlay>,
+    monitor_rects: Vec<MonitorRect>,
+    monitor_events: Subscribers<MonitorEvent>,
+}
+
+impl WaylandOverlayManager {
+    pub fn new(connection: Connection) -> Result<Self> {
+        let mut queue = connection.new_event_queue();
+        let qh = queue.handle();
+        connection.display().get_registry(&qh, ());
+        let mut state = WaylandState::default();
+        // First roundtrip binds globals, second delivers the output descriptions they triggered.
+        queue.roundtrip(&mut state)?;
+        queue.roundtrip(&mut state)?;
+        if state.compositor.is_none() {
+            return Err("compositor does not advertise wl_compositor".into());
+        }
+        if state.layer_shell.is_none() {
+            return Err("compositor does not support wlr-layer-shell; overlays unavailable".into());
+        }
+        if state.xdg_output_manager.is_none() {
+            warn!("xdg-output unavailable; deriving output geometry from wl_output");
+        }
+        Ok(Self {
+            connection,
+            queue,
+            state,
+            overlays: HashMap::new(),
+            monitor_rects: Vec::new(),
+            monitor_events: Subscribers::new(),
+        })
+    }
+
+    /// Whether a Wayland session with wlr-layer-shell is reachable.
+    pub fn is_supported() -> bool {
+        if std::env::var_os("WAYLAND_DISPLAY").is_none() {
+            return false;
+        }
+        let Ok(connection) = Connection::connect_to_env() else {
+            return false;
+        };
+        let Ok((globals, _queue)) = registry_queue_init::<ProbeState>(&connection) else {
+            return false;
+        };
+        globals.contents().with_list(|list| {
+            list.iter()
+                .any(|global| global.interface == "zwlr_layer_shell_v1")
+        })
+    }
+
+    pub fn connection(&self) -> &Connection {
+        &self.connection
+    }
+
+    pub fn wake_handle(&self) -> WakeHandle {
+        WakeHandle {
+            connection: self.connection.clone(),
+            queue_handle: self.queue.handle(),
+        }
+    }
+
+    /// Wait up to `timeout` (indefinitely when `None`) for compositor events, dispatch them, then
+    /// apply any topology change.
+    pub fn dispatch(&mut self, timeout: Option<Duration>) -> Result<()> {
+        self.queue.dispatch_pending(&mut self.state)?;
+        self.queue.flush()?;
+        // No guard means events are already queued; dispatch them without waiting.
+        if let Some(guard) = self.queue.prepare_read() {
+            if wait_readable(guard.connection_fd().as_raw_fd(), timeout)? {
+                match guard.read() {
+                    Ok(_) => {}
+                    Err(WaylandError::Io(err)) if err.kind() == std::io::ErrorKind::WouldBlock => {}
+                    Err(err) => return Err(DispatchError::Backend(err).into()),
+                }
+            }
+        }
+        self.queue.dispatch_pending(&mut self.state)?;
+        for id in std::mem::take(&mut self.state.closed_overlays) {
+            if let Some(overlay) = self.overlays.remove(&id) {
+                destroy_overlay(overlay);
+            }
+        }
+        if std::mem::take(&mut self.state.topology_dirty) {
+            self.handle_environment_change("output change");
+        }
+        Ok(())
+    }
+
+    pub fn refresh_overlays(&mut self) -> Result<()> {
+        let ready: Vec<&OutputInfo> = self
+            .state
+            .outputs
+            .iter()
+            .filter(|info| info.ready)
+            .collect();
+        let monitors: Vec<MonitorRect> = ready.iter().map(|info| info.monitor_rect()).collect();
+        let scales: Vec<f32> = ready.iter().map(|info| info.effective_scale()).collect();
+        let events = diff_monitors(&self.monitor_rects, &monitors);
+        let desired: HashSet<MonitorId> = monitors.iter().map(|rect| rect.id).collect();
+        let existing_keys: Vec<MonitorId> = self.overlays.keys().copied().collect();
+        for key in existing_keys {
+            if !desired.contains(&key) {
+                if let Some(overlay) = self.overlays.remove(&key) {
+                    destroy_overlay(overlay);
+                }
+            }
+        }
+        let qh = self.queue.handle();
+        for info in self.state.outputs.iter().filter(|info| info.ready) {
+            let id = info.monitor_id();
+            if self.overlays.contains_key(&id) {
+                continue;
+            }
+            let (Some(compositor), Some(layer_shell), Some(output)) = (
+                self.state.compositor.as_ref(),
+                self.state.layer_shell.as_ref(),
+                info.output.as_ref(),
+            ) else {
+                continue;
+            };
+            let surface = compositor.create_surface(&qh, ());
+            let empty_region = compositor.create_region(&qh, ());
+            surface.set_input_region(Some(&empty_region));
+            empty_region.destroy();
+            let layer_surface = layer_shell.get_layer_surface(
+                &surface,
+                Some(output),
+                zwlr_layer_shell_v1::Layer::Overlay,
+                LAYER_NAMESPACE.into(),
+                &qh,
+                id,
+            );
+            use zwlr_layer_surface_v1::Anchor;
+            layer_surface.set_anchor(Anchor::Top | Anchor::Bottom | Anchor::Left | Anchor::Right);
+            layer_surface.set_exclusive_zone(-1);
+            layer_surface
+                .set_keyboard_interactivity(zwlr_layer_surface_v1::KeyboardInteractivity::None);
+            surface.commit();
+            self.overlays.insert(
+                id,
+                LayerOverlay {
+                    surface,
+                    layer_surface,
+                },
+            );
+        }
+        self.connection.flush()?;
+
+        self.monitor_rects = monitors;
+        info!(
+            "Overlay refresh => {} outputs: {}",
+            self.monitor_rects.len(),
+            self.monitor_rects
+                .iter()
+                .zip(&scales)
+                .map(|(r, scale)| format!(
+                    "[{} {}x{} @ ({}, {}) scale {:.2}]",
+                    r.id, r.width, r.height, r.x, r.y, scale
+                ))
+                .collect::<Vec<_>>()
+                .join(", ")
+        );
+        for event in &events {
+            self.monitor_events.publish(event);
+        }
+        Ok(())
+    }
+
+    pub fn log_current_layout(&self, reason: &str) {
+        if self.monitor_rects.is_empty() {
+            info!("Overlay layout ({}) -> no active outputs", reason);
+            return;
+        }
+        let mut details = Vec::new();
+        for (index, rect) in self.monitor_rects.iter().enumerate() {
+            details.push(format!(
+                "output {} ({}): origin=({}, {}), size={}x{}, dpi={}",
+                index, rect.id, rect.x, rect.y, rect.width, rect.height, rect.dpi
+            ));
+        }
+        info!("Overlay layout ({}) -> {}", reason, details.join("; "));
+    }
+
+    pub fn handle_environment_change(&mut self, reason: &str) {
+        match self.refresh_overlays() {
+            Ok(()) => self.log_current_layout(reason),
+            Err(err) => warn!("failed to refresh overlays after {reason}: {err}"),
+        }
+    }
+
+    pub fn teardown_overlays(&mut self) -> Result<()> {
+        for (_, overlay) in self.overlays.drain() {
+            destroy_overlay(overlay);
+        }
+        self.monitor_rects.clear();
+        self.connection.flush()?;
+        Ok(())
+    }
+}
+
+/// Block until `fd` is readable or `timeout` passes. Returns whether it became readable.
+fn wait_readable(fd: RawFd, timeout: Option<Duration>) -> Result<bool> {
+    let mut poll_fd = libc::pollfd {
+        fd,
+        events: libc::POLLIN,
+        revents: 0,
+    };
+    // Round up so a sub-millisecond timeout still sleeps instead of spinning.
+    let timeout_ms = timeout.map_or(-1, |timeout| {
+        timeout.as_micros().div_ceil(1000).min(i32::MAX as u128) as i32
+    });
+    match unsafe { libc::poll(&mut poll_fd, 1, timeout_ms) } {
+        ready if ready > 0 => Ok(true),
+        0 => Ok(false),
+        _ => {
+            let err = std::io::Error::last_os_error();
+            if err.kind() == std::io::ErrorKind::Interrupted {
+                Ok(false)
+            } else {
+                Err(err.into())
+            }
+        }
+    }
+}
+
+fn destroy_overlay(overlay: LayerOverlay) {
+    overlay.layer_surface.destroy();
+    overlay.surface.destroy();
+}
+
+impl OverlayManager for WaylandOverlayManager {
+    fn init(&mut self) -> Result<()> {
+        self.refresh_overlays()
+    }
+
+    fn monitors(&self) -> Result<Vec<MonitorRect>> {
+        Ok(self.monitor_rects.clone())
+    }
+
+    fn create_overlays(&mut self) -> Result<()> {
+        self.refresh_overlays()
+    }
+
+    fn destroy_overlays(&mut self) -> Result<()> {
+        self.teardown_overlays()
+    }
+
+    fn subscribe(&mut self) -> Receiver<MonitorEvent> {
+        self.monitor_events.subscribe()
+    }
+}
+
+impl Dispatch<wl_registry::WlRegistry, ()> for WaylandState {
+    fn event(
+        state: &mut Self,
+        registry: &wl_registry::WlRegistry,
+        event: wl_registry::Event,
+        _: &(),
+        _: &Connection,
+        qh: &QueueHandle<Self>,
+    ) {
+        match event {
+            wl_registry::Event::Global {
+                name,
+                interface,
+                version,
+            } => match interface.as_str() {
+                "wl_compositor" => {
+                    state.compositor = Some(registry.bind(name, version.min(4), qh, ()));
+                }
+                "zwlr_layer_shell_v1" => {
+                    state.layer_shell = Some(registry.bind(name, version.min(4), qh, ()));
+                }
+                "zxdg_output_manager_v1" => {
+                    state.xdg_output_manager = Some(registry.bind(name, version.min(3), qh, ()));
+                    let names: Vec<u32> =
+                        state.outputs.iter().map(|info| info.global_name).collect();
+                    for global_name in names {
+                        state.bind_xdg_output(global_name, qh);
+                    }
+                }
+                "wl_output" => {
+                    let output = registry.bind(name, version.min(4), qh, name);
+                    state.outputs.push(OutputInfo {
+                        global_name: name,
+                        output: Some(output),
+                        scale: 1,
+                        ..Default::default()
+                    });
+                    state.bind_xdg_output(name, qh);
+                }
+                _ => {}
+            },
+            wl_registry::Event::GlobalRemove { name } => {
+                let before = state.outputs.len();
+                state.outputs.retain(|info| info.global_name != name);
+                if state.outputs.len() != before {
+                    state.topology_dirty = true;
+                }
+            }
+            _ => {}
+        }
+    }
+}
+
+impl Dispatch<wl_output::WlOutput, u32> for WaylandState {
+    fn event(
+        state: &mut Self,
+        _: &wl_output::WlOutput,
+        event: wl_output::Event,
+        global_name: &u32,
+        _: &Connection,
+        _: &QueueHandle<Self>,
+    ) {
+        let Some(info) = state.output_mut(*global_name) else {
+            return;
+        };
+        match event {
+            wl_output::Event::Geometry {
+                x, y, make, model, ..
+            } => {
+                info.position = (x, y);
+                info.make = make;
+                info.model = model;
+            }
+            wl_output::Event::Mode {
+                flags: WEnum::Value(flags),
+                width,
+                height,
+                refresh,
+            } if flags.contains(wl_output::Mode::Current) => {
+                info.mode_size = (width, height);
+                info.mode_refresh = refresh;
+            }
+            wl_output::Event::Scale { factor } => info.scale = factor,
+            wl_output::Event::Name { name } => info.connector = Some(name),
+            wl_output::Event::Done => {
+                info.ready = true;
+                state.topology_dirty = true;
+            }
+            _ => {}
+        }
+    }
+}
+
+impl Dispatch<zxdg_output_v1::ZxdgOutputV1, u32> for WaylandState {
+    fn event(
+        state: &mut Self,
+        _: &zxdg_output_v1::ZxdgOutputV1,
+        event: zxdg_output_v1::Event,
+        global_name: &u32,
+        _: &Connection,
+        _: &QueueHandle<Self>,
+    ) {
+        let Some(info) = state.output_mut(*global_name) else {
+            return;
+        };
+        match event {
+            zxdg_output_v1::Event::LogicalPosition { x, y } => info.logical_position = Some((x, y)),
+            zxdg_output_v1::Event::LogicalSize { width, height } => {
+                info.logical_size = Some((width, height))
+            }
+            zxdg_output_v1::Event::Name { name } => {
+                info.connector.get_or_insert(name);
+            }
+            zxdg_output_v1::Event::Done => state.topology_dirty = true,
+            _ => {}
+        }
+    }
+}
+
+impl Dispatch<zwlr_layer_surface_v1::ZwlrLayerSurfaceV1, MonitorId> for WaylandState {
+    fn event(
+        state: &mut Self,
+        layer_surface: &zwlr_layer_surface_v1::ZwlrLayerSurfaceV1,
+        event: zwlr_layer_surface_v1::Event,
+        id: &MonitorId,
+        _: &Connection,
+        _: &QueueHandle<Self>,
+    ) {
+        match event {
+            zwlr_layer_surface_v1::Event::Configure { serial, .. } => {
+                layer_surface.ack_configure(serial);
+            }
+            zwlr_layer_surface_v1::Event::Closed => {
+                warn!("compositor closed overlay surface for {id}; recreating");
+                state.closed_overlays.push(*id);
+                state.topology_dirty = true;
+            }
+            _ => {}
+        }
+    }
+}
+
+/// Throwaway state for `WaylandOverlayManager::is_supported`.
+struct ProbeState;
+
+impl Dispatch<wl_registry::WlRegistry, GlobalListContents> for ProbeState {
+    fn event(
+        _: &mut Self,
+        _: &wl_registry::WlRegistry,
+        _: wl_registry::Event,
+        _: &GlobalListContents,
+        _: &Connection,
+        _: &QueueHandle<Self>,
+    ) {
+    }
+}
+
+delegate_noop!(WaylandState: ignore wl_compositor::WlCompositor);
+delegate_noop!(WaylandState: ignore wl_surface::WlSurface);
+delegate_noop!(WaylandState: ignore wl_region::WlRegion);
+delegate_noop!(WaylandState: ignore wl_callback::WlCallback);
+delegate_noop!(WaylandState: ignore zwlr_layer_shell_v1::ZwlrLayerShellV1);
+delegate_noop!(WaylandState: ignore zxdg_output_manager_v1::ZxdgOutputManagerV1);
@@ -0,0 +1,115 @@
+use serpentines_platform::{HotkeyId, HotkeyProvider, Key, KeyChord, Result, Subscribers};
+use tracing::info;
+
+use std::collections::HashMap;
+use std::sync::mpsc::Receiver;
+use std::sync::{Arc, Mutex};
+
+use windows::Win32::Foundation::HWND;
+use windows::Win32::UI::Input::KeyboardAndMouse::{
+    RegisterHotKey, UnregisterHotKey, HOT_KEY_MODIFIERS, MOD_ALT, MOD_CONTROL, MOD_NOREPEAT,
+    MOD_SHIFT, MOD_WIN, VIRTUAL_KEY, VK_BACK, VK_DELETE, VK_DOWN, VK_END, VK_ESCAPE, VK_F1,
+    VK_HOME, VK_INSERT, VK_LEFT, VK_NEXT, VK_PRIOR, VK_RETURN, VK_RIGHT, VK_SPACE, VK_TAB, VK_UP,
+};
+
+pub(crate) type HotkeySubscribers = Arc<Mutex<Subscribers<HotkeyId>>>;
+
+/// Thread hotkeys via `RegisterHotKey`. Windows posts `WM_HOTKEY` to the registering thread's queue,
+/// so this must live on the event loop thread, which forwards presses through `subscribers`.
+pub struct WinHotkeyProvider {
+    registered: HashMap<HotkeyId, KeyChord>,
+    subscribers: HotkeySubscribers,
+}
+
+impl WinHotkeyProvider {
+    pub(crate) fn new(subscribers: HotkeySubscribers) -> Self {
+        Self {
+            registered: HashMap::new(),
+            subscribers,
+        }
+    }
+}
+
+impl HotkeyProvider for WinHotkeyProvider {
+    fn register(&mut self, id: HotkeyId, chord: &KeyChord) -> Result<()> {
+        chord.validate()?;
+        if self.registered.contains_key(&id) {
+            self.unregister(id)?;
+        }
+        unsafe {
+            RegisterHotKey(
+                HWND(std::ptr::null_mut()),
+                id.0 as i32,
+                modifier_flags(chord),
+                virtual_key(chord.key).0 as u32,
+            )
+            .map_err(|err| format!("{chord} is unavailable: {err}"))?;
+        }
+        info!("hotkey registered: {chord}");
+        self.registered.insert(id, *chord);
+        Ok(())
+    }
+
+    fn unregister(&mut self, id: HotkeyId) -> Result<()> {
+        if self.registered.remove(&id).is_some() {
+            unsafe { UnregisterHotKey(HWND(std::ptr::null_mut()), id.0 as i32)? };
+        }
+        Ok(())
+    }
+
+    fn subscribe(&mut self) -> Receiver<HotkeyId> {
+        match self.subscribers.lock() {
+            Ok(mut subscribers) => subscribers.subscribe(),
+            Err(poisoned) => poisoned.into_inner().subscribe(),
+        }
+    }
+}
+
+impl Drop for WinHotkeyProvider {
+    fn drop(&mut self) {
+        let ids: Vec<HotkeyId> = self.registered.keys().copied().collect();
+        for id in ids {
+            let _ = self.unregister(id);
+        }
+    }
+}
+
+fn modifier_flags(chord: &KeyChord) -> HOT_KEY_MODIFIERS {
+    let mut flags = MOD_NOREPEAT;
+    if chord.modifiers.ctrl {
+        flags |= MOD_CONTROL;
+    }
+    if chord.modifiers.alt {
+        flags |= MOD_ALT;
+    }
+    if chord.modifiers.shift {
+        flags |= MOD_SHIFT;
+    }
+    if chord.modifiers.super_key {
+        flags |= MOD_WIN;
+    }
+    flags
+}
+
+fn virtual_key(key: Key) -> VIRTUAL_KEY {
+    match key {
+        // VK codes for A-Z and 0-9 are their ASCII values.
+        Key::Char(c) => VIRTUAL_KEY(c as u16),
+        Key::F(number) => VIRTUAL_KEY(VK_F1.0 + number as u16 - 1),
+        Key::Space => VK_SPACE,
+        Key::Tab => VK_TAB,
+        Key::Enter => VK_RETURN,
+        Key::Escape => VK_ESCAPE,
+        Key::Backspace => VK_BACK,
+        Key::Delete => VK_DELETE,
+        Key::Insert => VK_INSERT,
+        Key::Home => VK_HOME,
+        Key::End => VK_END,
+        Key::PageUp => VK_PRIOR,
+        Key::PageDown => VK_NEXT,
+        Key::Up => VK_UP,
+        Key::Down => VK_DOWN,
+        Key::Left => VK_LEFT,
+        Key::Right => VK_RIGHT,
+    }
+}
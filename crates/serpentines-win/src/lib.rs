@@ -1,105 +1,149 @@
 //! Windows platform implementations (stubs) for Serpentines.
-use serpentines_platform::{GpuRenderer, InputSource, OverlayManager, Result};
+use serpentines_platform::{
+    Capabilities, EventLoop, GpuRenderer, HotkeyId, InputEvent, InputSource, OverlayManager, Platform,
+    PlatformBackend, Result, ShellEvent, Subscribers, TrayAction, TrayItem, TrayMenu,
+    TrayProvider, Waker,
+};
 use tracing::{info, warn};
 
+mod hotkey;
 mod overlay;
+pub use crate::hotkey::WinHotkeyProvider;
+use crate::hotkey::HotkeySubscribers;
 use crate::overlay::WinOverlayManager;
-use windows::Win32::Foundation::{HINSTANCE, HWND};
+use windows::Win32::Foundation::{FALSE, HINSTANCE, HWND, LPARAM, WPARAM};
 use windows::Win32::System::LibraryLoader::GetModuleHandleW;
-use windows::Win32::UI::WindowsAndMessaging::{MSG, PeekMessageW, TranslateMessage, DispatchMessageW, WM_QUIT, PM_REMOVE, PostQuitMessage, WaitMessage};
+use windows::Win32::System::Threading::{GetCurrentThreadId, INFINITE};
+use windows::Win32::UI::WindowsAndMessaging::{MSG, PeekMessageW, TranslateMessage, DispatchMessageW, WM_QUIT, PM_REMOVE, MsgWaitForMultipleObjects, PostThreadMessageW, QS_ALLINPUT, WM_APP, WM_HOTKEY};
 
-use crossbeam_channel::Sender as CbSender;
-use serpentines_ui::{spawn_ui_thread, UiCommand};
+use std::collections::HashMap;
+use std::sync::mpsc::Receiver;
 use std::sync::{Arc, Mutex};
-use tray_icon::menu::{Menu, MenuEvent, MenuId, MenuItem};
+use std::time::Duration;
+use tray_icon::menu::{
+    CheckMenuItem, IsMenuItem, Menu, MenuEvent, MenuId, MenuItem, PredefinedMenuItem, Submenu,
+};
 use tray_icon::{Icon, MouseButton, TrayIconBuilder, TrayIconEvent};
 use image;
 
+/// Thread message that only exists to end a `MsgWaitForMultipleObjects` wait.
+const WM_APP_WAKE: u32 = WM_APP + 1;
+
+type ActionSubscribers = Arc<Mutex<Subscribers<TrayAction>>>;
+type MenuActions = Arc<Mutex<HashMap<MenuId, TrayAction>>>;
+
 // Public app entry ----------------
-/// Start the Windows message loop, create tray icon, overlay, and a placeholder control panel.
-pub fn run_app() -> Result<()> {
-    unsafe {
-        let hinstance = HINSTANCE(GetModuleHandleW(None)?.0);
-        // Spawn egui UI on a separate thread
-        let ui_handles = spawn_ui_thread();
-        let ui_command_sender_cell: Arc<Mutex<CbSender<UiCommand>>> =
-            Arc::new(Mutex::new(ui_handles.command_sender.clone()));
-        let mut overlay_manager = WinOverlayManager::new(hinstance);
-        overlay_manager.create_overlays()?;
-        overlay_manager.log_current_layout("initial overlay creation");
-
-        // Create tray icon + menu via tray-icon crate
-        let (tray, settings_id, exit_id) = create_tray_icon()?;
-        info!("tray icon created; wiring event handlers");
+/// Backend descriptor for the app's backend registry.
+pub fn backend() -> PlatformBackend {
+    PlatformBackend {
+        name: "windows",
+        priority: 0,
+        detect: || true,
+        capabilities: || Capabilities {
+            // Input hook is still a stub.
+            global_input: false,
+            click_through_overlays: true,
+            per_monitor_dpi: true,
+            tray: true,
+            hotkeys: true,
+        },
+        create: create_platform,
+    }
+}
 
-        // Route tray icon clicks and menu selections
-        handle_tray_icon_events(&ui_command_sender_cell);
-        handle_tray_menu_events(&ui_command_sender_cell, &settings_id, &exit_id);
+/// Create the overlay window class, tray icon and input/GPU stubs. Must run on the thread that
+/// will pump messages.
+pub fn create_platform() -> Result<Platform> {
+    let hinstance = unsafe { HINSTANCE(GetModuleHandleW(None)?.0) };
+    let hotkey_presses: HotkeySubscribers = Arc::new(Mutex::new(Subscribers::new()));
+    let event_loop = WinEventLoop::new(hinstance, Arc::clone(&hotkey_presses))?;
+    Ok(Platform {
+        event_loop: Box::new(event_loop),
+        input: Box::new(WinInputSource::new()),
+        renderer: Box::new(WinGpuRenderer::new()),
+        tray: create_tray(),
+        hotkeys: Some(Box::new(WinHotkeyProvider::new(hotkey_presses))),
+    })
+}
+
+/// Win32 message pump for the overlay windows, tray, hotkeys and wake-ups.
+pub struct WinEventLoop {
+    // Boxed: overlay windows keep a pointer to the manager in GWLP_USERDATA, so it must not move.
+    overlays: Box<WinOverlayManager>,
+    thread_id: u32,
+    shell_events: Subscribers<ShellEvent>,
+    hotkey_presses: HotkeySubscribers,
+}
+
+impl WinEventLoop {
+    fn new(hinstance: HINSTANCE, hotkey_presses: HotkeySubscribers) -> Result<Self> {
+        Ok(Self {
+            overlays: Box::new(WinOverlayManager::new(hinstance)),
+            thread_id: unsafe { GetCurrentThreadId() },
+            shell_events: Subscribers::new(),
+            hotkey_presses,
+        })
+    }
+}
 
-        let mut message = MSG::default();
-        'main: loop {
+impl EventLoop for WinEventLoop {
+    fn pump(&mut self, timeout: Option<Duration>) -> Result<()> {
+        let timeout_ms = timeout.map_or(INFINITE, |timeout| {
+            timeout.as_micros().div_ceil(1000).min(INFINITE as u128 - 1) as u32
+        });
+        unsafe {
+            // Efficient wait for the next message. Tray handlers run via callbacks.
+            let _ = MsgWaitForMultipleObjects(None, FALSE, timeout_ms, QS_ALLINPUT);
+            let mut message = MSG::default();
             while PeekMessageW(&mut message, HWND(std::ptr::null_mut()), 0, 0, PM_REMOVE).into() {
                 if message.message == WM_QUIT {
-                    break 'main;
+                    self.shell_events.publish(&ShellEvent::Quit);
+                    continue;
+                }
+                if message.message == WM_APP_WAKE {
+                    continue;
+                }
+                if message.message == WM_HOTKEY {
+                    if let Ok(mut presses) = self.hotkey_presses.lock() {
+                        presses.publish(&HotkeyId(message.wParam.0 as u32));
+                    }
+                    continue;
                 }
                 let _ = TranslateMessage(&message);
                 DispatchMessageW(&message);
             }
-            // Efficient wait for the next message. Tray handlers run via callbacks.
-            let _ = WaitMessage();
         }
-
-        // Cleanup
-        drop(tray);
-        overlay_manager.destroy_overlays()?;
+        Ok(())
     }
-    Ok(())
-}
 
-fn handle_tray_icon_events(ui_command_sender_cell: &Arc<Mutex<CbSender<UiCommand>>>) {
-    let ui_sender_cell = Arc::clone(ui_command_sender_cell);
-    TrayIconEvent::set_event_handler(Some(move |event: tray_icon::TrayIconEvent| match event {
-        TrayIconEvent::Click { button, .. } if button == MouseButton::Left => {
-            show_ui(&ui_sender_cell);
-        }
-        _ => {}
-    }));
-}
+    fn waker(&self) -> Waker {
+        let thread_id = self.thread_id;
+        Waker::new(move || {
+            if let Err(err) = unsafe { PostThreadMessageW(thread_id, WM_APP_WAKE, WPARAM(0), LPARAM(0)) } {
+                warn!("failed to post wake message: {err}");
+            }
+        })
+    }
 
-fn handle_tray_menu_events(
-    ui_command_sender_cell: &Arc<Mutex<CbSender<UiCommand>>>,
-    settings_menu_id: &MenuId,
-    exit_menu_id: &MenuId,
-) {
-    let ui_sender_cell2 = Arc::clone(ui_command_sender_cell);
-    let settings_id = settings_menu_id.clone();
-    let exit_id = exit_menu_id.clone();
-    MenuEvent::set_event_handler(Some(move |event: tray_icon::menu::MenuEvent| {
-        if event.id() == &settings_id {
-            show_ui(&ui_sender_cell2);
-        } else if event.id() == &exit_id {
-            unsafe { PostQuitMessage(0) };
-        }
-    }));
-}
+    fn overlays(&mut self) -> &mut dyn OverlayManager {
+        self.overlays.as_mut()
+    }
 
-fn show_ui(ui_sender_cell: &Arc<Mutex<CbSender<UiCommand>>>) {
-    if let Ok(sender) = ui_sender_cell.lock() {
-        match sender.send(UiCommand::Show) {
-            Ok(()) => info!("win: sent UiCommand::Show ok"),
-            Err(_) => warn!("UI command channel closed on Show; cannot show window"),
-        }
-    } else {
-        warn!("Failed to lock UI command sender");
+    fn subscribe_shell(&mut self) -> Receiver<ShellEvent> {
+        self.shell_events.subscribe()
     }
 }
 
 // ---------------- Input and GPU stubs (unchanged behavior) ----------------
 
-pub struct WinInputSource;
+pub struct WinInputSource {
+    subscribers: Subscribers<InputEvent>,
+}
 impl WinInputSource {
     pub fn new() -> Self {
-        Self
+        Self {
+            subscribers: Subscribers::new(),
+        }
     }
 }
 impl InputSource for WinInputSource {
@@ -111,6 +155,9 @@ impl InputSource for WinInputSource {
         info!("Input hook stop (stub)");
         Ok(())
     }
+    fn subscribe(&mut self) -> std::sync::mpsc::Receiver<InputEvent> {
+        self.subscribers.subscribe()
+    }
 }
 
 pub struct WinGpuRenderer;
@@ -132,30 +179,140 @@ impl GpuRenderer for WinGpuRenderer {
 
 // --------------- tray-icon integration ---------------
 
-fn create_tray_icon() -> Result<(tray_icon::TrayIcon, MenuId, MenuId)> {
-    let ico_bytes = include_bytes!("../../../assets/icon.ico");
-    let img = image::load_from_memory_with_format(ico_bytes, image::ImageFormat::Ico).expect("Failed to load icon").to_rgba8();
-    let rgba = img.as_raw().clone();
-    let width = img.width();
-    let height = img.height();
-    let icon = Icon::from_rgba(rgba, width, height).map_err(box_err)?;
-
-    let menu = Menu::new();
-    let settings_item = MenuItem::new("Open Settings", true, None);
-    let exit_item = MenuItem::new("Exit", true, None);
-    let settings_id = settings_item.id().clone();
-    let exit_id = exit_item.id().clone();
-    menu.append(&settings_item).map_err(box_err)?;
-    menu.append(&exit_item).map_err(box_err)?;
-
-    let tray = TrayIconBuilder::new()
-        .with_icon(icon)
-        .with_tooltip("Serpentines")
-        .with_menu(Box::new(menu))
-        .build()
-        .map_err(box_err)?;
-
-    Ok((tray, settings_id, exit_id))
+/// Tray icon whose menu is rebuilt from a `TrayMenu`. Selections are mapped back to actions by
+/// menu id.
+pub struct WinTrayProvider {
+    tray: tray_icon::TrayIcon,
+    actions: MenuActions,
+    subscribers: ActionSubscribers,
+}
+
+impl WinTrayProvider {
+    pub fn new() -> Result<Self> {
+        let ico_bytes = include_bytes!("../../../assets/icon.ico");
+        let img = image::load_from_memory_with_format(ico_bytes, image::ImageFormat::Ico)?.to_rgba8();
+        let rgba = img.as_raw().clone();
+        let width = img.width();
+        let height = img.height();
+        let icon = Icon::from_rgba(rgba, width, height).map_err(box_err)?;
+
+        let tray = TrayIconBuilder::new()
+            .with_icon(icon)
+            .with_tooltip("Serpentines")
+            .with_menu(Box::new(Menu::new()))
+            .build()
+            .map_err(box_err)?;
+        let actions: MenuActions = Arc::new(Mutex::new(HashMap::new()));
+        let subscribers: ActionSubscribers = Arc::new(Mutex::new(Subscribers::new()));
+        info!("tray icon created; wiring event handlers");
+
+        // Route tray icon clicks and menu selections
+        handle_tray_icon_events(&subscribers);
+        handle_tray_menu_events(&subscribers, &actions);
+        Ok(Self {
+            tray,
+            actions,
+            subscribers,
+        })
+    }
+}
+
+impl TrayProvider for WinTrayProvider {
+    fn set_menu(&mut self, menu: &TrayMenu) -> Result<()> {
+        let mut actions = HashMap::new();
+        let native = Menu::new();
+        for item in &menu.items {
+            native.append(build_menu_item(item, &mut actions)?.as_ref()).map_err(box_err)?;
+        }
+        self.tray.set_menu(Some(Box::new(native)));
+        self.tray
+            .set_tooltip((!menu.tooltip.is_empty()).then_some(menu.tooltip.as_str()))
+            .map_err(box_err)?;
+        if let Ok(mut current) = self.actions.lock() {
+            *current = actions;
+        }
+        Ok(())
+    }
+
+    fn subscribe(&mut self) -> Receiver<TrayAction> {
+        match self.subscribers.lock() {
+            Ok(mut subscribers) => subscribers.subscribe(),
+            Err(poisoned) => poisoned.into_inner().subscribe(),
+        }
+    }
+}
+
+fn create_tray() -> Option<Box<dyn TrayProvider>> {
+    match WinTrayProvider::new() {
+        Ok(tray) => Some(Box::new(tray)),
+        Err(err) => {
+            warn!("tray unavailable: {err}");
+            None
+        }
+    }
+}
+
+fn build_menu_item(
+    item: &TrayItem,
+    actions: &mut HashMap<MenuId, TrayAction>,
+) -> Result<Box<dyn IsMenuItem>> {
+    Ok(match item {
+        TrayItem::Action {
+            label,
+            action,
+            enabled,
+        } => {
+            let native = MenuItem::new(label, *enabled, None);
+            actions.insert(native.id().clone(), action.clone());
+            Box::new(native)
+        }
+        TrayItem::Check {
+            label,
+            checked,
+            action,
+        } => {
+            let native = CheckMenuItem::new(label, true, *checked, None);
+            actions.insert(native.id().clone(), action.clone());
+            Box::new(native)
+        }
+        TrayItem::Submenu { label, items } => {
+            let native = Submenu::new(label, true);
+            for child in items {
+                native.append(build_menu_item(child, actions)?.as_ref()).map_err(box_err)?;
+            }
+            Box::new(native)
+        }
+        TrayItem::Separator => Box::new(PredefinedMenuItem::separator()),
+    })
+}
+
+fn handle_tray_icon_events(subscribers: &ActionSubscribers) {
+    let subscribers = Arc::clone(subscribers);
+    TrayIconEvent::set_event_handler(Some(move |event: tray_icon::TrayIconEvent| match event {
+        TrayIconEvent::Click { button, .. } if button == MouseButton::Left => {
+            publish_action(&subscribers, &TrayAction::OpenSettings);
+        }
+        _ => {}
+    }));
+}
+
+fn handle_tray_menu_events(subscribers: &ActionSubscribers, actions: &MenuActions) {
+    let subscribers = Arc::clone(subscribers);
+    let actions = Arc::clone(actions);
+    MenuEvent::set_event_handler(Some(move |event: tray_icon::menu::MenuEvent| {
+        let action = actions.lock().ok().and_then(|actions| actions.get(event.id()).cloned());
+        if let Some(action) = action {
+            publish_action(&subscribers, &action);
+        }
+    }));
+}
+
+fn publish_action(subscribers: &ActionSubscribers, action: &TrayAction) {
+    if let Ok(mut subscribers) = subscribers.lock() {
+        subscribers.publish(action);
+    } else {
+        warn!("Failed to lock tray action subscribers");
+    }
 }
 
 // Removed polling helpers; using set_event_handler callbacks instead
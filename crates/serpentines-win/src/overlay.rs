@@ -1,14 +1,18 @@
-use serpentines_platform::{MonitorRect, OverlayManager, Result};
+use serpentines_platform::{
+    diff_monitors, MonitorEvent, MonitorId, MonitorRect, OverlayManager, Result, Subscribers,
+};
 use tracing::{info, warn};
 
 use std::collections::{HashMap, HashSet};
 use std::ffi::c_void;
 use std::mem::size_of;
+use std::sync::mpsc::Receiver;
 
 use windows::core::{Error, PCWSTR};
 use windows::Win32::Foundation::{BOOL, COLORREF, HINSTANCE, HWND, LPARAM, LRESULT, RECT, WPARAM};
 use windows::Win32::Graphics::Gdi::{
-    EnumDisplayMonitors, GetMonitorInfoW, HBRUSH, HDC, HMONITOR, MONITORINFO,
+    EnumDisplayDevicesW, EnumDisplayMonitors, EnumDisplaySettingsW, GetMonitorInfoW, DEVMODEW,
+    DISPLAY_DEVICEW, ENUM_CURRENT_SETTINGS, HBRUSH, HDC, HMONITOR, MONITORINFO, MONITORINFOEXW,
 };
 use windows::Win32::UI::HiDpi::{GetDpiForMonitor, MDT_EFFECTIVE_DPI};
 use windows::Win32::UI::WindowsAndMessaging::*;
@@ -44,8 +48,9 @@ impl DisplayOverlay {
 pub struct WinOverlayManager {
     instance_handle_value: isize,
     class_name_pointer: isize,
-    overlays: HashMap<isize, DisplayOverlay>,
+    overlays: HashMap<MonitorId, DisplayOverlay>,
     monitor_rects: Vec<MonitorRect>,
+    monitor_events: Subscribers<MonitorEvent>,
 }
 
 impl WinOverlayManager {
@@ -56,13 +61,16 @@ impl WinOverlayManager {
             class_name_pointer: class_name.0 as isize,
             overlays: HashMap::new(),
             monitor_rects: Vec::new(),
+            monitor_events: Subscribers::new(),
         }
     }
 
     pub fn refresh_overlays(&mut self) -> Result<()> {
         let monitors = Self::enumerate_monitors()?;
-        let desired: HashSet<isize> = monitors.iter().map(|(id, _)| *id).collect();
-        let existing_keys: Vec<isize> = self.overlays.keys().copied().collect();
+        let previous: Vec<MonitorRect> = self.overlays.values().map(|overlay| overlay.rect).collect();
+        let events = diff_monitors(&previous, &monitors);
+        let desired: HashSet<MonitorId> = monitors.iter().map(|rect| rect.id).collect();
+        let existing_keys: Vec<MonitorId> = self.overlays.keys().copied().collect();
         for key in existing_keys {
             if !desired.contains(&key) {
                 if let Some(overlay) = self.overlays.remove(&key) {
@@ -72,8 +80,8 @@ impl WinOverlayManager {
                 }
             }
         }
-        for (id, rect) in monitors.iter() {
-            match self.overlays.get_mut(id) {
+        for rect in monitors.iter() {
+            match self.overlays.get_mut(&rect.id) {
                 Some(current) => {
                     unsafe {
                         update_overlay_window(current.window_handle(), rect)?;
@@ -90,7 +98,7 @@ impl WinOverlayManager {
                         )?
                     };
                     self.overlays.insert(
-                        *id,
+                        rect.id,
                         DisplayOverlay {
                             window_handle_value: window_handle.0 as isize,
                             rect: *rect,
@@ -100,19 +108,22 @@ impl WinOverlayManager {
             }
         }
 
-        self.monitor_rects = monitors.iter().map(|(_, rect)| *rect).collect();
+        self.monitor_rects = monitors;
         info!(
             "Overlay refresh => {} displays: {}",
             self.monitor_rects.len(),
             self.monitor_rects
                 .iter()
                 .map(|r| format!(
-                    "[{}x{} @ ({}, {}) dpi {}]",
-                    r.width, r.height, r.x, r.y, r.dpi
+                    "[{} {}x{} @ ({}, {}) dpi {}]",
+                    r.id, r.width, r.height, r.x, r.y, r.dpi
                 ))
                 .collect::<Vec<_>>()
                 .join(", ")
         );
+        for event in &events {
+            self.monitor_events.publish(event);
+        }
         Ok(())
     }
 
@@ -124,8 +135,8 @@ impl WinOverlayManager {
         let mut details = Vec::new();
         for (index, rect) in self.monitor_rects.iter().enumerate() {
             details.push(format!(
-                "display {}: origin=({}, {}), size={}x{}, dpi={}",
-                index, rect.x, rect.y, rect.width, rect.height, rect.dpi
+                "display {} ({}): origin=({}, {}), size={}x{}, dpi={}",
+                index, rect.id, rect.x, rect.y, rect.width, rect.height, rect.dpi
             ));
         }
         info!("Overlay layout ({}) -> {}", reason, details.join("; "));
@@ -149,26 +160,30 @@ impl WinOverlayManager {
         Ok(())
     }
 
-    fn enumerate_monitors() -> Result<Vec<(isize, MonitorRect)>> {
+    fn enumerate_monitors() -> Result<Vec<MonitorRect>> {
         unsafe extern "system" fn enum_proc(
             monitor_handle: HMONITOR,
             _hdc: HDC,
             _lprc: *mut RECT,
             long_parameter: LPARAM,
         ) -> BOOL {
-            let monitor_data_pointer = long_parameter.0 as *mut Vec<(isize, MonitorRect)>;
+            let monitor_data_pointer = long_parameter.0 as *mut Vec<MonitorRect>;
             if monitor_data_pointer.is_null() {
                 return BOOL(0);
             }
             let monitor_data = &mut *monitor_data_pointer;
-            let mut monitor_info = MONITORINFO {
-                cbSize: size_of::<MONITORINFO>() as u32,
+            let mut monitor_info = MONITORINFOEXW {
+                monitorInfo: MONITORINFO {
+                    cbSize: size_of::<MONITORINFOEXW>() as u32,
+                    ..Default::default()
+                },
                 ..Default::default()
             };
-            if !GetMonitorInfoW(monitor_handle, &mut monitor_info).as_bool() {
+            let info_pointer = &mut monitor_info as *mut MONITORINFOEXW as *mut MONITORINFO;
+            if !GetMonitorInfoW(monitor_handle, info_pointer).as_bool() {
                 return BOOL(1);
             }
-            let rect = monitor_info.rcMonitor;
+            let rect = monitor_info.monitorInfo.rcMonitor;
             let width = rect.right - rect.left;
             let height = rect.bottom - rect.top;
             let mut horizontal_dpi = 96u32;
@@ -180,20 +195,19 @@ impl WinOverlayManager {
                 );
                 horizontal_dpi = 96;
             }
-            monitor_data.push((
-                monitor_handle.0 as isize,
-                MonitorRect {
-                    x: rect.left,
-                    y: rect.top,
-                    width,
-                    height,
-                    dpi: horizontal_dpi,
-                },
-            ));
+            monitor_data.push(MonitorRect {
+                id: monitor_identity(&monitor_info.szDevice, &rect),
+                x: rect.left,
+                y: rect.top,
+                width,
+                height,
+                dpi: horizontal_dpi,
+                refresh_hz: refresh_rate(&monitor_info.szDevice),
+            });
             BOOL(1)
         }
 
-        let mut monitors: Vec<(isize, MonitorRect)> = Vec::new();
+        let mut monitors: Vec<MonitorRect> = Vec::new();
         let long_parameter = LPARAM(&mut monitors as *mut _ as isize);
         unsafe {
             let result = EnumDisplayMonitors(None, None, Some(enum_proc), long_parameter);
@@ -204,14 +218,64 @@ impl WinOverlayManager {
         Ok(monitors)
     }
 
-    unsafe fn destroy_overlay(&self, id: isize, overlay: DisplayOverlay) {
+    unsafe fn destroy_overlay(&self, id: MonitorId, overlay: DisplayOverlay) {
         SetWindowLongPtrW(overlay.window_handle(), GWLP_USERDATA, 0);
         if let Err(err) = DestroyWindow(overlay.window_handle()) {
-            warn!("DestroyWindow failed for overlay {id:?}: {err}");
+            warn!("DestroyWindow failed for overlay {id}: {err}");
         }
     }
 }
 
+/// Current refresh rate of a display output. Windows reports 0 or 1 for "hardware default".
+unsafe fn refresh_rate(device_name: &[u16; 32]) -> Option<f32> {
+    let mut mode = DEVMODEW {
+        dmSize: size_of::<DEVMODEW>() as u16,
+        ..Default::default()
+    };
+    if !EnumDisplaySettingsW(PCWSTR(device_name.as_ptr()), ENUM_CURRENT_SETTINGS, &mut mode).as_bool() {
+        return None;
+    }
+    (mode.dmDisplayFrequency > 1).then_some(mode.dmDisplayFrequency as f32)
+}
+
+/// Stable id for a monitor: the monitor's device interface path (which embeds the EDID
+/// manufacturer/product code) when available, else the adapter output name (`\\.\DISPLAY1`),
+/// else its geometry.
+unsafe fn monitor_identity(device_name: &[u16; 32], rect: &RECT) -> MonitorId {
+    let mut display_device = DISPLAY_DEVICEW {
+        cb: size_of::<DISPLAY_DEVICEW>() as u32,
+        ..Default::default()
+    };
+    if EnumDisplayDevicesW(
+        PCWSTR(device_name.as_ptr()),
+        0,
+        &mut display_device,
+        EDD_GET_DEVICE_INTERFACE_NAME,
+    )
+    .as_bool()
+    {
+        let device_id = wide_to_string(&display_device.DeviceID);
+        if !device_id.is_empty() {
+            return MonitorId::from_key(&device_id);
+        }
+    }
+    let output_name = wide_to_string(device_name);
+    if !output_name.is_empty() {
+        return MonitorId::from_key(&output_name);
+    }
+    MonitorId::from_geometry(
+        rect.left,
+        rect.top,
+        rect.right - rect.left,
+        rect.bottom - rect.top,
+    )
+}
+
+fn wide_to_string(buffer: &[u16]) -> String {
+    let len = buffer.iter().position(|&c| c == 0).unwrap_or(buffer.len());
+    String::from_utf16_lossy(&buffer[..len])
+}
+
 impl OverlayManager for WinOverlayManager {
     fn init(&mut self) -> Result<()> {
         self.refresh_overlays()
@@ -228,6 +292,10 @@ impl OverlayManager for WinOverlayManager {
     fn destroy_overlays(&mut self) -> Result<()> {
         self.teardown_overlays()
     }
+
+    fn subscribe(&mut self) -> Receiver<MonitorEvent> {
+        self.monitor_events.subscribe()
+    }
 }
 
 pub unsafe extern "system" fn handle_overlay_window_message(
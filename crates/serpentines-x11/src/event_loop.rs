@@ -0,0 +1,130 @@
+use serpentines_platform::{EventLoop, OverlayManager, Result, ShellEvent, Subscribers, Waker};
+use tracing::warn;
+
+use std::os::fd::{AsRawFd, RawFd};
+use std::sync::mpsc::Receiver;
+use std::sync::Arc;
+use std::time::Duration;
+
+use x11rb::connection::Connection;
+use x11rb::protocol::xproto::{
+    ClientMessageEvent, ConnectionExt as _, CreateWindowAux, EventMask, Window, WindowClass,
+};
+use x11rb::protocol::Event;
+use x11rb::rust_connection::RustConnection;
+
+use crate::X11OverlayManager;
+
+/// Main-connection event loop: RandR notifications for the overlays, plus wake-ups posted as
+/// client messages to an unmapped window. Nothing here publishes shell events yet.
+pub struct X11EventLoop {
+    connection: Arc<RustConnection>,
+    overlays: X11OverlayManager,
+    wake_window: Window,
+    shell_events: Subscribers<ShellEvent>,
+}
+
+impl X11EventLoop {
+    pub fn new(connection: Arc<RustConnection>, screen_number: usize) -> Result<Self> {
+        let overlays = X11OverlayManager::new(Arc::clone(&connection), screen_number)?;
+        let wake_window = create_message_window(&connection, screen_number)?;
+        Ok(Self {
+            connection,
+            overlays,
+            wake_window,
+            shell_events: Subscribers::new(),
+        })
+    }
+
+    fn handle_event(&mut self, event: Event) {
+        match event {
+            Event::ClientMessage(message) if message.window == self.wake_window => {}
+            event => {
+                self.overlays.handle_event(&event);
+            }
+        }
+    }
+}
+
+impl EventLoop for X11EventLoop {
+    fn pump(&mut self, timeout: Option<Duration>) -> Result<()> {
+        self.connection.flush()?;
+        // x11rb reads everything available on poll, so the socket is only waited on once the
+        // queue is empty.
+        if let Some(event) = self.connection.poll_for_event()? {
+            self.handle_event(event);
+        } else {
+            wait_readable(self.connection.stream().as_raw_fd(), timeout)?;
+        }
+        while let Some(event) = self.connection.poll_for_event()? {
+            self.handle_event(event);
+        }
+        Ok(())
+    }
+
+    fn waker(&self) -> Waker {
+        wake_window_waker(&self.connection, self.wake_window)
+    }
+
+    fn overlays(&mut self) -> &mut dyn OverlayManager {
+        &mut self.overlays
+    }
+
+    fn subscribe_shell(&mut self) -> Receiver<ShellEvent> {
+        self.shell_events.subscribe()
+    }
+}
+
+fn create_message_window(connection: &RustConnection, screen_number: usize) -> Result<Window> {
+    let root = connection.setup().roots[screen_number].root;
+    let window = connection.generate_id()?;
+    connection.create_window(
+        x11rb::COPY_DEPTH_FROM_PARENT,
+        window,
+        root,
+        0,
+        0,
+        1,
+        1,
+        0,
+        WindowClass::INPUT_ONLY,
+        x11rb::COPY_FROM_PARENT,
+        &CreateWindowAux::new(),
+    )?;
+    connection.flush()?;
+    Ok(window)
+}
+
+fn wake_window_waker(connection: &Arc<RustConnection>, wake_window: Window) -> Waker {
+    let connection = Arc::clone(connection);
+    Waker::new(move || {
+        let message = ClientMessageEvent::new(32, wake_window, 0u32, [0u32; 5]);
+        let sent = connection
+            .send_event(false, wake_window, EventMask::NO_EVENT, message)
+            .map(|_| ())
+            .and_then(|()| connection.flush());
+        if let Err(err) = sent {
+            warn!("failed to post wake message: {err}");
+        }
+    })
+}
+
+/// Block until `fd` is readable or `timeout` passes. Interrupted waits count as spurious wake-ups.
+fn wait_readable(fd: RawFd, timeout: Option<Duration>) -> Result<()> {
+    let mut poll_fd = libc::pollfd {
+        fd,
+        events: libc::POLLIN,
+        revents: 0,
+    };
+    // Round up so a sub-millisecond timeout still sleeps instead of spinning.
+    let timeout_ms = timeout.map_or(-1, |timeout| {
+        timeout.as_micros().div_ceil(1000).min(i32::MAX as u128) as i32
+    });
+    if unsafe { libc::poll(&mut poll_fd, 1, timeout_ms) } < 0 {
+        let err = std::io::Error::last_os_error();
+        if err.kind() != std::io::ErrorKind::Interrupted {
+            return Err(err.into());
+        }
+    }
+    Ok(())
+}
@@ -0,0 +1,197 @@
+use serpentines_platform::{InputEvent, InputSource, MouseButton, Result, Subscribers};
+use tracing::{info, warn};
+
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::mpsc::Receiver;
+use std::sync::{Arc, Mutex};
+use std::thread::JoinHandle;
+
+use x11rb::connection::Connection;
+use x11rb::protocol::xinput::{self, ConnectionExt as _, XIEventMask};
+use x11rb::protocol::xproto::{
+    ClientMessageEvent, ConnectionExt as _, CreateWindowAux, EventMask, Window, WindowClass,
+};
+use x11rb::protocol::Event;
+use x11rb::rust_connection::RustConnection;
+
+/// Global pointer input via XInput2 raw events on the root window.
+///
+/// From XI 2.1 on, raw events reach the root window regardless of which window has focus or
+/// grabs; on an XI 2.0 server they pause while another client holds a grab. They carry deltas,
+/// not positions, so each raw motion is followed by a `QueryPointer` for the absolute position.
+/// Runs on its own connection and thread.
+pub struct X11InputSource {
+    subscribers: Arc<Mutex<Subscribers<InputEvent>>>,
+    worker: Option<InputWorker>,
+}
+
+struct InputWorker {
+    connection: Arc<RustConnection>,
+    wake_window: Window,
+    stop_requested: Arc<AtomicBool>,
+    thread: JoinHandle<()>,
+}
+
+impl X11InputSource {
+    pub fn new() -> Self {
+        Self {
+            subscribers: Arc::new(Mutex::new(Subscribers::new())),
+            worker: None,
+        }
+    }
+}
+
+impl Default for X11InputSource {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl InputSource for X11InputSource {
+    fn start(&mut self) -> Result<()> {
+        if self.worker.is_some() {
+            return Ok(());
+        }
+        let (connection, screen_number) = x11rb::connect(None)?;
+        let connection = Arc::new(connection);
+        let root = connection.setup().roots[screen_number].root;
+        let version = connection.xinput_xi_query_version(2, 2)?.reply()?;
+        match (version.major_version, version.minor_version) {
+            (major, _) if major < 2 => {
+                return Err(format!("XInput {major}.x is too old; XInput2 is required").into());
+            }
+            (2, 0) => warn!(
+                "XInput 2.0 server; cursor trails pause while another client grabs the pointer"
+            ),
+            _ => {}
+        }
+        connection.xinput_xi_select_events(
+            root,
+            &[xinput::EventMask {
+                deviceid: xinput::Device::ALL_MASTER.into(),
+                mask: vec![
+                    XIEventMask::RAW_MOTION
+                        | XIEventMask::RAW_BUTTON_PRESS
+                        | XIEventMask::RAW_BUTTON_RELEASE,
+                ],
+            }],
+        )?;
+        // Unmapped window whose only job is to receive the client message that wakes the worker on stop.
+        let wake_window = connection.generate_id()?;
+        connection.create_window(
+            x11rb::COPY_DEPTH_FROM_PARENT,
+            wake_window,
+            root,
+            0,
+            0,
+            1,
+            1,
+            0,
+            WindowClass::INPUT_ONLY,
+            x11rb::COPY_FROM_PARENT,
+            &CreateWindowAux::new(),
+        )?;
+        connection.flush()?;
+
+        let stop_requested = Arc::new(AtomicBool::new(false));
+        let thread = {
+            let connection = Arc::clone(&connection);
+            let stop_requested = Arc::clone(&stop_requested);
+            let subscribers = Arc::clone(&self.subscribers);
+            std::thread::spawn(move || {
+                run_input_loop(&connection, root, &stop_requested, &subscribers)
+            })
+        };
+        self.worker = Some(InputWorker {
+            connection,
+            wake_window,
+            stop_requested,
+            thread,
+        });
+        info!(
+            "XInput {}.{} raw input started",
+            version.major_version, version.minor_version
+        );
+        Ok(())
+    }
+
+    fn stop(&mut self) -> Result<()> {
+        let Some(worker) = self.worker.take() else {
+            return Ok(());
+        };
+        worker.stop_requested.store(true, Ordering::SeqCst);
+        let wake = ClientMessageEvent::new(32, worker.wake_window, 0u32, [0u32; 5]);
+        worker
+            .connection
+            .send_event(false, worker.wake_window, EventMask::NO_EVENT, wake)?;
+        worker.connection.flush()?;
+        if worker.thread.join().is_err() {
+            warn!("X11 input thread panicked");
+        }
+        info!("XInput2 raw input stopped");
+        Ok(())
+    }
+
+    fn subscribe(&mut self) -> Receiver<InputEvent> {
+        match self.subscribers.lock() {
+            Ok(mut subscribers) => subscribers.subscribe(),
+            Err(poisoned) => poisoned.into_inner().subscribe(),
+        }
+    }
+}
+
+impl Drop for X11InputSource {
+    fn drop(&mut self) {
+        let _ = self.stop();
+    }
+}
+
+fn run_input_loop(
+    connection: &RustConnection,
+    root: Window,
+    stop_requested: &AtomicBool,
+    subscribers: &Mutex<Subscribers<InputEvent>>,
+) {
+    let publish = |event: InputEvent| {
+        if let Ok(mut subscribers) = subscribers.lock() {
+            subscribers.publish(&event);
+        }
+    };
+    while !stop_requested.load(Ordering::SeqCst) {
+        let event = match connection.wait_for_event() {
+            Ok(event) => event,
+            Err(err) => {
+                warn!("X11 input connection failed: {err}");
+                return;
+            }
+        };
+        match event {
+            Event::XinputRawMotion(_) => match connection.query_pointer(root).map(|c| c.reply()) {
+                Ok(Ok(pointer)) => publish(InputEvent::CursorMoved {
+                    x: pointer.root_x as f32,
+                    y: pointer.root_y as f32,
+                }),
+                Ok(Err(err)) => warn!("QueryPointer failed: {err}"),
+                Err(err) => warn!("QueryPointer failed: {err}"),
+            },
+            Event::XinputRawButtonPress(raw) => publish(InputEvent::Button {
+                button: mouse_button(raw.detail),
+                pressed: true,
+            }),
+            Event::XinputRawButtonRelease(raw) => publish(InputEvent::Button {
+                button: mouse_button(raw.detail),
+                pressed: false,
+            }),
+            _ => {}
+        }
+    }
+}
+
+fn mouse_button(detail: u32) -> MouseButton {
+    match detail {
+        1 => MouseButton::Left,
+        2 => MouseButton::Middle,
+        3 => MouseButton::Right,
+        other => MouseButton::Other(other as u16),
+    }
+}
@@ -0,0 +1,59 @@
+//! Linux X11 platform implementations for Serpentines.
+use serpentines_platform::{
+    Capabilities, NullRenderer, Platform, PlatformBackend, Result, TrayProvider,
+};
+use serpentines_sni::SniTray;
+use tracing::warn;
+
+mod event_loop;
+mod input;
+mod overlay;
+pub use crate::event_loop::X11EventLoop;
+pub use crate::input::X11InputSource;
+pub use crate::overlay::X11OverlayManager;
+
+use std::sync::Arc;
+
+// Public app entry ----------------
+/// Backend descriptor for the app's backend registry. Also used under XWayland when no
+/// layer-shell compositor is available.
+pub fn backend() -> PlatformBackend {
+    PlatformBackend {
+        name: "x11",
+        priority: 10,
+        detect: || std::env::var_os("DISPLAY").is_some() && x11rb::connect(None).is_ok(),
+        capabilities: || Capabilities {
+            global_input: true,
+            click_through_overlays: true,
+            // X11 has a single desktop-wide Xft.dpi.
+            per_monitor_dpi: false,
+            tray: true,
+            hotkeys: false,
+        },
+        create: create_platform,
+    }
+}
+
+/// Connect to the X server and build the overlays, tray and input source.
+pub fn create_platform() -> Result<Platform> {
+    let (connection, screen_number) = x11rb::connect(None)?;
+    let event_loop = X11EventLoop::new(Arc::new(connection), screen_number)?;
+    Ok(Platform {
+        event_loop: Box::new(event_loop),
+        input: Box::new(X11InputSource::new()),
+        renderer: Box::new(NullRenderer),
+        tray: create_tray(),
+        hotkeys: None,
+    })
+}
+
+/// SNI tray, or `None` when D-Bus or the tray host is unavailable.
+fn create_tray() -> Option<Box<dyn TrayProvider>> {
+    match SniTray::new() {
+        Ok(tray) => Some(Box::new(tray)),
+        Err(err) => {
+            warn!("tray unavailable: {err}");
+            None
+        }
+    }
+}
@@ -0,0 +1,453 @@
+use serpentines_platform::{
+    diff_monitors, MonitorEvent, MonitorId, MonitorRect, OverlayManager, Result, Subscribers,
+    BASE_DPI,
+};
+use tracing::{info, warn};
+
+use std::collections::{HashMap, HashSet};
+use std::sync::mpsc::Receiver;
+use std::sync::Arc;
+
+use x11rb::connection::Connection;
+use x11rb::protocol::randr::{
+    ConnectionExt as _, GetScreenResourcesCurrentReply, ModeFlag, NotifyMask,
+};
+use x11rb::protocol::shape::{ConnectionExt as _, SK, SO};
+use x11rb::protocol::xfixes::{ConnectionExt as _, SelectionEventMask};
+use x11rb::protocol::xproto::{
+    Atom, AtomEnum, ClipOrdering, ColormapAlloc, ConnectionExt as _, CreateWindowAux, EventMask,
+    VisualClass, Visualid, Window, WindowClass,
+};
+use x11rb::protocol::Event;
+use x11rb::rust_connection::RustConnection;
+
+/// Number of 32-bit words of EDID to read; the first 128 bytes identify the panel.
+const EDID_LENGTH_WORDS: u32 = 32;
+
+/// Per-monitor transparent overlays built from override-redirect ARGB windows.
+///
+/// Each overlay's input region is set to empty with XShape, so clicks and hover fall through to
+/// whatever is underneath.
+///
+/// Without a compositing manager an ARGB window is drawn opaque, so fullscreen overlays would
+/// black out the desktop. They are only created while `_NET_WM_CM_S<screen>` has an owner, and
+/// XFixes reports when that changes.
+pub struct X11OverlayManager {
+    connection: Arc<RustConnection>,
+    root: Window,
+    root_depth: u8,
+    argb_visual: Option<Visualid>,
+    /// `_NET_WM_CM_S<screen>`, owned by the running compositing manager.
+    cm_selection: Atom,
+    /// Whether a compositing manager was running at the last refresh.
+    composited: Option<bool>,
+    overlays: HashMap<MonitorId, Window>,
+    monitor_rects: Vec<MonitorRect>,
+    monitor_events: Subscribers<MonitorEvent>,
+}
+
+impl X11OverlayManager {
+    pub fn new(connection: Arc<RustConnection>, screen_number: usize) -> Result<Self> {
+        let screen = &connection.setup().roots[screen_number];
+        let root = screen.root;
+        let root_depth = screen.root_depth;
+        let argb_visual = screen
+            .allowed_depths
+            .iter()
+            .filter(|depth| depth.depth == 32)
+            .flat_map(|depth| depth.visuals.iter())
+            .find(|visual| visual.class == VisualClass::TRUE_COLOR)
+            .map(|visual| visual.visual_id);
+        if argb_visual.is_none() {
+            warn!("no 32-bit TrueColor visual; overlays will not be translucent");
+        }
+        connection.randr_select_input(
+            root,
+            NotifyMask::SCREEN_CHANGE | NotifyMask::CRTC_CHANGE | NotifyMask::OUTPUT_CHANGE,
+        )?;
+        let cm_name = format!("_NET_WM_CM_S{screen_number}");
+        let cm_selection = connection
+            .intern_atom(false, cm_name.as_bytes())?
+            .reply()?
+            .atom;
+        let watch_compositor = || -> Result<()> {
+            connection.xfixes_query_version(5, 0)?.reply()?;
+            connection.xfixes_select_selection_input(
+                root,
+                cm_selection,
+                SelectionEventMask::SET_SELECTION_OWNER
+                    | SelectionEventMask::SELECTION_WINDOW_DESTROY
+                    | SelectionEventMask::SELECTION_CLIENT_CLOSE,
+            )?;
+            Ok(())
+        };
+        if let Err(err) = watch_compositor() {
+            warn!("XFixes unavailable ({err}); compositing manager changes won't be noticed");
+        }
+        Ok(Self {
+            connection,
+            root,
+            root_depth,
+            argb_visual,
+            cm_selection,
+            composited: None,
+            overlays: HashMap::new(),
+            monitor_rects: Vec::new(),
+            monitor_events: Subscribers::new(),
+        })
+    }
+
+    pub fn refresh_overlays(&mut self) -> Result<()> {
+        let monitors = self.enumerate_monitors()?;
+        let events = diff_monitors(&self.monitor_rects, &monitors);
+        let composited = self.compositor_running()?;
+        if self.composited != Some(composited) {
+            if composited {
+                info!("compositing manager found; showing overlays");
+            } else {
+                warn!("no compositing manager running; overlays stay hidden until one starts");
+            }
+            self.composited = Some(composited);
+        }
+        let desired: HashSet<MonitorId> = monitors
+            .iter()
+            .filter(|_| composited)
+            .map(|rect| rect.id)
+            .collect();
+        let existing_keys: Vec<MonitorId> = self.overlays.keys().copied().collect();
+        for key in existing_keys {
+            if !desired.contains(&key) {
+                if let Some(window) = self.overlays.remove(&key) {
+                    self.destroy_overlay(key, window);
+                }
+            }
+        }
+        for rect in monitors.iter().filter(|_| composited) {
+            match self.overlays.get(&rect.id) {
+                Some(window) => {
+                    self.connection.configure_window(
+                        *window,
+                        &x11rb::protocol::xproto::ConfigureWindowAux::new()
+                            .x(rect.x)
+                            .y(rect.y)
+                            .width(rect.width.max(1) as u32)
+                            .height(rect.height.max(1) as u32),
+                    )?;
+                }
+                None => {
+                    let window = self.create_overlay_window(rect)?;
+                    self.overlays.insert(rect.id, window);
+                }
+            }
+        }
+        self.connection.flush()?;
+
+        self.monitor_rects = monitors;
+        info!(
+            "Overlay refresh => {} displays: {}",
+            self.monitor_rects.len(),
+            self.monitor_rects
+                .iter()
+                .map(|r| format!(
+                    "[{} {}x{} @ ({}, {}) dpi {}]",
+                    r.id, r.width, r.height, r.x, r.y, r.dpi
+                ))
+                .collect::<Vec<_>>()
+                .join(", ")
+        );
+        for event in &events {
+            self.monitor_events.publish(event);
+        }
+        Ok(())
+    }
+
+    pub fn log_current_layout(&self, reason: &str) {
+        if self.monitor_rects.is_empty() {
+            info!("Overlay layout ({}) -> no active displays", reason);
+            return;
+        }
+        let mut details = Vec::new();
+        for (index, rect) in self.monitor_rects.iter().enumerate() {
+            details.push(format!(
+                "display {} ({}): origin=({}, {}), size={}x{}, dpi={}",
+                index, rect.id, rect.x, rect.y, rect.width, rect.height, rect.dpi
+            ));
+        }
+        info!("Overlay layout ({}) -> {}", reason, details.join("; "));
+    }
+
+    pub fn handle_environment_change(&mut self, reason: &str) {
+        match self.refresh_overlays() {
+            Ok(()) => self.log_current_layout(reason),
+            Err(err) => warn!("failed to refresh overlays after {reason}: {err}"),
+        }
+    }
+
+    /// Route an X event from the main connection. Returns true when the event was ours.
+    pub fn handle_event(&mut self, event: &Event) -> bool {
+        match event {
+            Event::RandrScreenChangeNotify(_) => {
+                self.handle_environment_change("screen change");
+                true
+            }
+            Event::RandrNotify(_) => {
+                self.handle_environment_change("output change");
+                true
+            }
+            Event::XfixesSelectionNotify(notify) if notify.selection == self.cm_selection => {
+                self.handle_environment_change("compositing manager change");
+                true
+            }
+            _ => false,
+        }
+    }
+
+    pub fn teardown_overlays(&mut self) -> Result<()> {
+        let overlays = std::mem::take(&mut self.overlays);
+        for (id, window) in overlays {
+            self.destroy_overlay(id, window);
+        }
+        self.monitor_rects.clear();
+        self.connection.flush()?;
+        Ok(())
+    }
+
+    fn enumerate_monitors(&self) -> Result<Vec<MonitorRect>> {
+        let reply = self
+            .connection
+            .randr_get_monitors(self.root, true)?
+            .reply()?;
+        let dpi = self.xft_dpi().unwrap_or(BASE_DPI as u32);
+        let resources = self
+            .connection
+            .randr_get_screen_resources_current(self.root)?
+            .reply()?;
+        let mut monitors: Vec<MonitorRect> = Vec::with_capacity(reply.monitors.len());
+        for monitor in reply.monitors {
+            let base = self.monitor_identity(&monitor);
+            let mut id = base;
+            // Last resort if two monitors still share an id: number the later ones in RandR order.
+            let mut duplicate = 1;
+            while monitors.iter().any(|rect| rect.id == id) {
+                duplicate += 1;
+                id = MonitorId::from_key(&format!("{base}#{duplicate}"));
+            }
+            monitors.push(MonitorRect {
+                id,
+                x: monitor.x as i32,
+                y: monitor.y as i32,
+                width: monitor.width as i32,
+                height: monitor.height as i32,
+                dpi,
+                refresh_hz: monitor
+                    .outputs
+                    .first()
+                    .and_then(|output| self.output_refresh_hz(*output, &resources)),
+            });
+        }
+        Ok(monitors)
+    }
+
+    /// Stable id for a RandR monitor: its panel's EDID plus the monitor name (`DP-1`) when the
+    /// output exposes one, so identical panels on different ports stay distinct; else the name
+    /// alone, else its geometry.
+    fn monitor_identity(&self, monitor: &x11rb::protocol::randr::MonitorInfo) -> MonitorId {
+        let name = self.atom_name(monitor.name);
+        if let Some(edid) = monitor
+            .outputs
+            .first()
+            .and_then(|output| self.output_edid(*output))
+        {
+            let hex: String = edid.iter().map(|byte| format!("{byte:02x}")).collect();
+            return MonitorId::from_key(&format!("edid:{hex}:{}", name.unwrap_or_default()));
+        }
+        if let Some(name) = name {
+            return MonitorId::from_key(&name);
+        }
+        MonitorId::from_geometry(
+            monitor.x as i32,
+            monitor.y as i32,
+            monitor.width as i32,
+            monitor.height as i32,
+        )
+    }
+
+    fn compositor_running(&self) -> Result<bool> {
+        let owner = self
+            .connection
+            .get_selection_owner(self.cm_selection)?
+            .reply()?
+            .owner;
+        Ok(owner != x11rb::NONE)
+    }
+
+    fn output_edid(&self, output: u32) -> Option<Vec<u8>> {
+        let atom = self
+            .connection
+            .intern_atom(true, b"EDID")
+            .ok()?
+            .reply()
+            .ok()?
+            .atom;
+        if atom == 0 {
+            return None;
+        }
+        let reply = self
+            .connection
+            .randr_get_output_property(
+                output,
+                atom,
+                AtomEnum::ANY,
+                0,
+                EDID_LENGTH_WORDS,
+                false,
+                false,
+            )
+            .ok()?
+            .reply()
+            .ok()?;
+        (!reply.data.is_empty()).then_some(reply.data)
+    }
+
+    /// Refresh rate of the mode currently driving `output`, from its pixel clock and timings.
+    fn output_refresh_hz(
+        &self,
+        output: u32,
+        resources: &GetScreenResourcesCurrentReply,
+    ) -> Option<f32> {
+        let crtc = self
+            .connection
+            .randr_get_output_info(output, resources.config_timestamp)
+            .ok()?
+            .reply()
+            .ok()?
+            .crtc;
+        if crtc == x11rb::NONE {
+            return None;
+        }
+        let mode_id = self
+            .connection
+            .randr_get_crtc_info(crtc, resources.config_timestamp)
+            .ok()?
+            .reply()
+            .ok()?
+            .mode;
+        let mode = resources.modes.iter().find(|mode| mode.id == mode_id)?;
+        let mut lines = f64::from(mode.vtotal);
+        if mode.mode_flags.contains(ModeFlag::DOUBLE_SCAN) {
+            lines *= 2.0;
+        }
+        if mode.mode_flags.contains(ModeFlag::INTERLACE) {
+            lines /= 2.0;
+        }
+        let dots = f64::from(mode.htotal) * lines;
+        (dots > 0.0).then(|| (f64::from(mode.dot_clock) / dots) as f32)
+    }
+
+    fn atom_name(&self, atom: u32) -> Option<String> {
+        let reply = self.connection.get_atom_name(atom).ok()?.reply().ok()?;
+        String::from_utf8(reply.name).ok()
+    }
+
+    /// Desktop scale as configured through `Xft.dpi` in the root window's resource database.
+    fn xft_dpi(&self) -> Option<u32> {
+        let reply = self
+            .connection
+            .get_property(
+                false,
+                self.root,
+                AtomEnum::RESOURCE_MANAGER,
+                AtomEnum::STRING,
+                0,
+                u32::MAX / 4,
+            )
+            .ok()?
+            .reply()
+            .ok()?;
+        let resources = String::from_utf8_lossy(&reply.value);
+        resources.lines().find_map(|line| {
+            let value = line.strip_prefix("Xft.dpi:")?;
+            value
+                .trim()
+                .parse::<f32>()
+                .ok()
+                .map(|dpi| dpi.round() as u32)
+        })
+    }
+
+    fn create_overlay_window(&self, rect: &MonitorRect) -> Result<Window> {
+        let window = self.connection.generate_id()?;
+        let mut aux = CreateWindowAux::new()
+            .override_redirect(1)
+            .background_pixel(0)
+            .border_pixel(0)
+            .event_mask(EventMask::STRUCTURE_NOTIFY);
+        let (depth, visual) = match self.argb_visual {
+            Some(visual) => {
+                let colormap = self.connection.generate_id()?;
+                self.connection.create_colormap(
+                    ColormapAlloc::NONE,
+                    colormap,
+                    self.root,
+                    visual,
+                )?;
+                aux = aux.colormap(colormap);
+                (32, visual)
+            }
+            None => (self.root_depth, x11rb::COPY_FROM_PARENT),
+        };
+        self.connection.create_window(
+            depth,
+            window,
+            self.root,
+            rect.x as i16,
+            rect.y as i16,
+            rect.width.max(1) as u16,
+            rect.height.max(1) as u16,
+            0,
+            WindowClass::INPUT_OUTPUT,
+            visual,
+            &aux,
+        )?;
+        // Empty input region: the overlay never receives pointer events.
+        self.connection.shape_rectangles(
+            SO::SET,
+            SK::INPUT,
+            ClipOrdering::UNSORTED,
+            window,
+            0,
+            0,
+            &[],
+        )?;
+        self.connection.map_window(window)?;
+        Ok(window)
+    }
+
+    fn destroy_overlay(&self, id: MonitorId, window: Window) {
+        if let Err(err) = self.connection.destroy_window(window) {
+            warn!("destroy_window failed for overlay {id}: {err}");
+        }
+    }
+}
+
+impl OverlayManager for X11OverlayManager {
+    fn init(&mut self) -> Result<()> {
+        self.refresh_overlays()
+    }
+
+    fn monitors(&self) -> Result<Vec<MonitorRect>> {
+        Ok(self.monitor_rects.clone())
+    }
+
+    fn create_overlays(&mut self) -> Result<()> {
+        self.refresh_overlays()
+    }
+
+    fn destroy_overlays(&mut self) -> Result<()> {
+        self.teardown_overlays()
+    }
+
+    fn subscribe(&mut self) -> Receiver<MonitorEvent> {
+        self.monitor_events.subscribe()
+    }
+}